@@ -0,0 +1,3 @@
+-- Add migration script here
+CREATE INDEX IF NOT EXISTS idx_messages_room_id_id
+ON messages (room_id, id);
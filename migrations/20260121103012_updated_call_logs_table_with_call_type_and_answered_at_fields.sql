@@ -0,0 +1,19 @@
+-- Add migration script here
+ALTER TABLE call_logs
+ADD COLUMN call_type TEXT NOT NULL DEFAULT 'voice_call';
+
+ALTER TABLE call_logs
+ADD CONSTRAINT call_type_check
+CHECK (call_type IN ('voice_call', 'video_call'));
+
+ALTER TABLE call_logs
+ADD COLUMN answered_at VARCHAR(20);
+
+CREATE INDEX idx_call_logs_room
+ON call_logs (room_id);
+
+CREATE INDEX idx_call_logs_caller
+ON call_logs (caller_id);
+
+CREATE INDEX idx_call_logs_callee
+ON call_logs (callee_id);
@@ -0,0 +1,16 @@
+-- Add migration script here
+ALTER TABLE rooms
+ADD COLUMN is_space BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE rooms
+ADD COLUMN space_description TEXT;
+
+ALTER TABLE rooms
+ADD COLUMN space_theme TEXT;
+
+ALTER TABLE rooms
+ADD COLUMN share_token VARCHAR(64) UNIQUE;
+
+CREATE INDEX idx_rooms_spaces_created_by
+ON rooms (created_by)
+WHERE is_space = TRUE;
@@ -0,0 +1,10 @@
+-- Add migration script here
+ALTER TABLE messages
+ADD COLUMN text_search TSVECTOR
+GENERATED ALWAYS AS (to_tsvector('english', COALESCE(text_content, ''))) STORED;
+
+CREATE INDEX idx_messages_text_search
+ON messages USING GIN (text_search);
+
+CREATE INDEX idx_message_edits_previous_context_search
+ON message_edits USING GIN (to_tsvector('english', previous_context));
@@ -0,0 +1,9 @@
+-- Add migration script here
+-- no foreign key on purpose: replies keep pointing at a deleted parent so clients can render a
+-- "message deleted" placeholder instead of silently losing the quote
+ALTER TABLE messages
+ADD COLUMN reply_to_message_id BIGINT;
+
+CREATE INDEX idx_messages_reply_to_message_id
+ON messages (reply_to_message_id, id)
+WHERE reply_to_message_id IS NOT NULL;
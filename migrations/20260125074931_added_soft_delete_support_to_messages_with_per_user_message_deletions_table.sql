@@ -0,0 +1,42 @@
+-- Add migration script here
+ALTER TABLE messages
+ADD COLUMN is_deleted BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE messages
+ADD COLUMN deleted_at VARCHAR(20);
+
+ALTER TABLE messages
+DROP CONSTRAINT status_check;
+
+ALTER TABLE messages
+ADD CONSTRAINT status_check
+CHECK (status IN ('sent', 'delivered', 'seen', 'updated', 'reacted', 'deleted'));
+
+ALTER TABLE message_status_receipts
+DROP CONSTRAINT message_status_check;
+
+ALTER TABLE message_status_receipts
+ADD CONSTRAINT message_status_check
+CHECK (status IN ('sent', 'delivered', 'seen', 'updated', 'reacted', 'deleted'));
+
+-- "delete for me": messages hidden for a single user
+CREATE TABLE message_deletions (
+    user_id    BIGINT NOT NULL,
+    message_id BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    PRIMARY KEY (user_id, message_id),
+
+    CONSTRAINT fk_deletion_user
+        FOREIGN KEY (user_id)
+        REFERENCES users(id)
+        ON DELETE CASCADE,
+
+    CONSTRAINT fk_deletion_message
+        FOREIGN KEY (message_id)
+        REFERENCES messages(id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX idx_message_deletions_message
+ON message_deletions (message_id);
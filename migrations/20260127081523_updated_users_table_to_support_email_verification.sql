@@ -0,0 +1,8 @@
+-- Add migration script here
+ALTER TABLE users
+ADD COLUMN email_verified BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN email_verification_token VARCHAR(64) UNIQUE,
+ADD COLUMN email_verification_sent_at TIMESTAMP;
+
+-- accounts created before verification existed are trusted as they are
+UPDATE users SET email_verified = TRUE;
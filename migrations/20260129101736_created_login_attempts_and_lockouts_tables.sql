@@ -0,0 +1,28 @@
+-- Add migration script here
+CREATE TABLE failed_login_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    email VARCHAR(255) NOT NULL,
+    ip_address VARCHAR(64),
+    attempted_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_failed_login_attempts_email
+ON failed_login_attempts (email, attempted_at);
+
+CREATE INDEX idx_failed_login_attempts_ip_address
+ON failed_login_attempts (ip_address, attempted_at);
+
+CREATE TABLE login_lockouts (
+    id BIGSERIAL PRIMARY KEY,
+    scope VARCHAR(10) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    failed_attempts INT NOT NULL,
+    locked_until TIMESTAMP NOT NULL,
+    cleared_at TIMESTAMP,
+    cleared_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CONSTRAINT login_lockouts_scope_check CHECK (scope IN ('account', 'ip'))
+);
+
+CREATE INDEX idx_login_lockouts_subject
+ON login_lockouts (scope, subject, locked_until);
@@ -0,0 +1,6 @@
+-- Add migration script here
+-- A lockout or a successful login resets the counter for its own scope only, instead of deleting
+-- attempts that still count towards the other one
+ALTER TABLE failed_login_attempts
+ADD COLUMN counts_for_account BOOLEAN NOT NULL DEFAULT TRUE,
+ADD COLUMN counts_for_ip BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,12 @@
+-- Add migration script here
+CREATE TABLE two_factor_challenges (
+    id VARCHAR(64) PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    attempts INT NOT NULL DEFAULT 0,
+    consumed_at TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_two_factor_challenges_user_id
+ON two_factor_challenges (user_id);
@@ -0,0 +1,12 @@
+-- Add migration script here
+-- rooms that somehow ended up with several active calls keep only the latest one
+UPDATE call_logs
+SET ended_at = started_at, updated_at = NOW()
+WHERE ended_at IS NULL
+    AND id NOT IN (
+        SELECT MAX(id) FROM call_logs WHERE ended_at IS NULL GROUP BY room_id
+    );
+
+CREATE UNIQUE INDEX idx_call_logs_active_room
+ON call_logs (room_id)
+WHERE ended_at IS NULL;
@@ -11,6 +11,12 @@ CREATE TABLE IF NOT EXISTS users (
     access_token VARCHAR(1024),
     refresh_token VARCHAR(1024),
     one_time_password_token VARCHAR(1024),
+    email_verified BOOLEAN NOT NULL DEFAULT FALSE,
+    email_verification_token VARCHAR(64) UNIQUE,
+    email_verification_sent_at TIMESTAMP, -- last time a verification mail went out, for the resend cooldown
+    totp_secret VARCHAR(64), -- base32, set on enrollment and only trusted once totp_enabled is true
+    totp_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    totp_last_used_step BIGINT, -- time step of the last accepted code, so a code can't be replayed
     status VARCHAR(10) NOT NULL DEFAULT 'offline',
     last_seen VARCHAR(20),
     is_admin BOOLEAN NOT NULL DEFAULT FALSE,
@@ -25,6 +31,75 @@ CREATE TABLE IF NOT EXISTS users (
 -- Index for users.email
 CREATE INDEX IF NOT EXISTS idx_users_email ON users(email);
 
+-- Sessions Table (one row per logged in device, refresh tokens are rotated per session)
+CREATE TABLE IF NOT EXISTS sessions (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    refresh_jti VARCHAR(64) NOT NULL, -- id of the only refresh token currently valid for the session
+    device_name TEXT,
+    ip_address VARCHAR(64),
+    user_agent TEXT,
+    expires_at TIMESTAMP NOT NULL,
+    last_used_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMP,
+    revoked_reason TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_user_id ON sessions (user_id);
+
+-- TOTP Recovery Codes Table (argon2 hashed, each code works once)
+CREATE TABLE IF NOT EXISTS totp_recovery_codes (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash VARCHAR(255) NOT NULL,
+    used_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_totp_recovery_codes_user_id ON totp_recovery_codes (user_id);
+
+-- Two-Factor Challenges Table (one per password step, answered once and only tried a few times)
+CREATE TABLE IF NOT EXISTS two_factor_challenges (
+    id VARCHAR(64) PRIMARY KEY, -- the jti of the challenge token
+    user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    attempts INT NOT NULL DEFAULT 0,
+    consumed_at TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_two_factor_challenges_user_id ON two_factor_challenges (user_id);
+
+-- Failed Login Attempts Table (each scope's counter is reset on its own lockout, the account's on successful login)
+CREATE TABLE IF NOT EXISTS failed_login_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    email VARCHAR(255) NOT NULL,
+    ip_address VARCHAR(64),
+    counts_for_account BOOLEAN NOT NULL DEFAULT TRUE,
+    counts_for_ip BOOLEAN NOT NULL DEFAULT TRUE,
+    attempted_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_failed_login_attempts_email ON failed_login_attempts (email, attempted_at);
+CREATE INDEX IF NOT EXISTS idx_failed_login_attempts_ip_address ON failed_login_attempts (ip_address, attempted_at);
+
+-- Login Lockouts Table (temporary lockouts of an account email or a client IP)
+CREATE TABLE IF NOT EXISTS login_lockouts (
+    id BIGSERIAL PRIMARY KEY,
+    scope VARCHAR(10) NOT NULL, -- 'account' or 'ip'
+    subject VARCHAR(255) NOT NULL, -- the email or IP address that is locked out
+    failed_attempts INT NOT NULL,
+    locked_until TIMESTAMP NOT NULL,
+    cleared_at TIMESTAMP, -- set when an admin lifts the lockout early
+    cleared_by BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CONSTRAINT login_lockouts_scope_check CHECK (scope IN ('account', 'ip'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_lockouts_subject ON login_lockouts (scope, subject, locked_until);
+
 -- Rooms Table
 CREATE TABLE IF NOT EXISTS rooms (
     id BIGSERIAL PRIMARY KEY,
@@ -38,6 +113,10 @@ CREATE TABLE IF NOT EXISTS rooms (
     archived_by BIGINT[] DEFAULT '{}',
     pinned_by BIGINT[] DEFAULT '{}',
     is_public BOOLEAN NOT NULL DEFAULT FALSE,
+    is_space BOOLEAN NOT NULL DEFAULT FALSE, -- personal self-messaging notebooks
+    space_description TEXT,
+    space_theme TEXT,
+    share_token VARCHAR(64) UNIQUE, -- read-only share link for spaces
     created_at TIMESTAMP NOT NULL DEFAULT NOW(),
     updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
     CONSTRAINT fk_co_member FOREIGN KEY (co_member) REFERENCES users(id)
@@ -52,6 +131,9 @@ CREATE INDEX IF NOT EXISTS idx_rooms_bookmarked_by ON rooms USING GIN(bookmarked
 CREATE INDEX IF NOT EXISTS idx_rooms_archived_by ON rooms USING GIN(archived_by);
 CREATE INDEX IF NOT EXISTS idx_rooms_pinned_by ON rooms USING GIN(pinned_by);
 
+-- Index for listing a user's spaces
+CREATE INDEX IF NOT EXISTS idx_rooms_spaces_created_by ON rooms (created_by) WHERE is_space = TRUE;
+
 -- Room Members Table
 CREATE TABLE IF NOT EXISTS room_members (
       id BIGSERIAL PRIMARY KEY,
@@ -81,11 +163,24 @@ CREATE TABLE IF NOT EXISTS messages (
   created_at TIMESTAMP NOT NULL DEFAULT NOW(),
   updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
   updates_counter INTEGER NOT NULL DEFAULT 0,
+  reply_to_message_id BIGINT, -- no foreign key, replies outlive a deleted parent
+  is_deleted BOOLEAN NOT NULL DEFAULT FALSE, -- "delete for everyone" tombstone
+  deleted_at VARCHAR(20),
+  text_search TSVECTOR GENERATED ALWAYS AS (to_tsvector('english', COALESCE(text_content, ''))) STORED,
   CONSTRAINT updates_counter_check CHECK (updates_counter >= 0),
   CONSTRAINT type_check CHECK (type IN ('regular', 'voice_note', 'voice_call', 'video_call')),
-  CONSTRAINT status_check CHECK (status IN ('sent', 'delivered', 'seen', 'updated', 'reacted'))
+  CONSTRAINT status_check CHECK (status IN ('sent', 'delivered', 'seen', 'updated', 'reacted', 'deleted'))
 );
 
+-- Index for keyset pagination of room messages
+CREATE INDEX IF NOT EXISTS idx_messages_room_id_id ON messages (room_id, id);
+
+-- Index for full-text message search
+CREATE INDEX IF NOT EXISTS idx_messages_text_search ON messages USING GIN (text_search);
+
+-- Index for message threads
+CREATE INDEX IF NOT EXISTS idx_messages_reply_to_message_id ON messages (reply_to_message_id, id) WHERE reply_to_message_id IS NOT NULL;
+
 -- Message Status Receipt Table
 CREATE TABLE IF NOT EXISTS message_status_receipts (
      id BIGSERIAL PRIMARY KEY,
@@ -99,7 +194,7 @@ CREATE TABLE IF NOT EXISTS message_status_receipts (
      updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
      updates_count_tracker INTEGER NOT NULL DEFAULT 0,
      CONSTRAINT updates_count_tracker_check CHECK (updates_count_tracker >= 0),
-     CONSTRAINT message_status_check CHECK (status IN ('sent', 'delivered', 'seen', 'updated', 'reacted')),
+     CONSTRAINT message_status_check CHECK (status IN ('sent', 'delivered', 'seen', 'updated', 'reacted', 'deleted')),
      CONSTRAINT message_status_receipts_action_check CHECK (action IN ('original-send', 'edit', 'delete', 'reaction', 'system'))
 );
 
@@ -117,13 +212,22 @@ CREATE TABLE IF NOT EXISTS call_logs (
    caller_id BIGINT REFERENCES users(id) ON DELETE SET NULL,
    callee_id BIGINT REFERENCES users(id) ON DELETE SET NULL,
    status TEXT NOT NULL DEFAULT 'missed',
+   call_type TEXT NOT NULL DEFAULT 'voice_call',
    started_at VARCHAR(20) NOT NULL,
+   answered_at VARCHAR(20),
    ended_at VARCHAR(20),
    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
-   CONSTRAINT status_check CHECK (status IN ('missed', 'completed', 'rejected'))
+   CONSTRAINT status_check CHECK (status IN ('missed', 'completed', 'rejected')),
+   CONSTRAINT call_type_check CHECK (call_type IN ('voice_call', 'video_call'))
 );
 
+-- Indexes for call history lookups
+CREATE INDEX IF NOT EXISTS idx_call_logs_room ON call_logs (room_id);
+CREATE INDEX IF NOT EXISTS idx_call_logs_caller ON call_logs (caller_id);
+CREATE INDEX IF NOT EXISTS idx_call_logs_callee ON call_logs (callee_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_call_logs_active_room ON call_logs (room_id) WHERE ended_at IS NULL;
+
 -- Message Bookmarks Table
 CREATE TABLE IF NOT EXISTS message_bookmarks (
     user_id    BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
@@ -146,6 +250,16 @@ CREATE TABLE IF NOT EXISTS message_archives (
 CREATE INDEX IF NOT EXISTS idx_message_archives_user ON message_archives (user_id);
 CREATE INDEX IF NOT EXISTS idx_message_archives_message ON message_archives (message_id);
 
+-- Message Deletions Table ("delete for me")
+CREATE TABLE IF NOT EXISTS message_deletions (
+    user_id    BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    message_id BIGINT NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, message_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_message_deletions_message ON message_deletions (message_id);
+
 -- Message Edits Table
 CREATE TABLE IF NOT EXISTS message_edits (
     id BIGSERIAL PRIMARY KEY,
@@ -157,6 +271,7 @@ CREATE TABLE IF NOT EXISTS message_edits (
 
 CREATE INDEX IF NOT EXISTS idx_message_edits_message_id ON message_edits (message_id);
 CREATE INDEX IF NOT EXISTS idx_message_edits_created_at ON message_edits (created_at);
+CREATE INDEX IF NOT EXISTS idx_message_edits_previous_context_search ON message_edits USING GIN (to_tsvector('english', previous_context));
 
 -- Message Reactions Table
 CREATE TABLE IF NOT EXISTS message_reactions (
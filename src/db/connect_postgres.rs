@@ -1,12 +1,16 @@
+use crate::utils::load_config::DatabaseSection;
 use sqlx::postgres::PgPoolOptions;
 use std::time::Duration;
 
-pub async fn connect_pg(database_url: String) -> sqlx::PgPool {
+pub async fn connect_pg(database_url: String, config: Option<&DatabaseSection>) -> sqlx::PgPool {
     // println!("Attempting to connect to PostgreSQL database...");
 
+    let max_connections = config.and_then(|db| db.max_connections).unwrap_or(5);
+    let acquire_timeout = config.and_then(|db| db.connect_timeout_secs).unwrap_or(3);
+
     let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .acquire_timeout(Duration::from_secs(3))
+        .max_connections(max_connections)
+        .acquire_timeout(Duration::from_secs(acquire_timeout))
         .connect(&database_url)
         .await;
 
@@ -0,0 +1,55 @@
+use crate::AppState;
+use crate::domains::auth::login_protection::clear_failed_logins;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::{Extension, Path, State};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use tracing::error;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LoginLockout {
+    id: i64,
+    scope: String,
+    subject: String,
+    failed_attempts: i32,
+    locked_until: NaiveDateTime,
+    cleared_at: Option<NaiveDateTime>,
+    cleared_by: Option<i64>,
+    created_at: NaiveDateTime,
+}
+
+pub async fn clear_login_lockout(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Path(lockout_id): Path<i64>,
+) -> ApiResult<LoginLockout> {
+    let lockout = sqlx::query_as::<_, LoginLockout>(
+        r#"
+        UPDATE login_lockouts
+        SET cleared_at = NOW(), cleared_by = $1
+        WHERE id = $2 AND cleared_at IS NULL
+        RETURNING *
+        "#,
+    )
+    .bind(session.user.id)
+    .bind(lockout_id)
+    .fetch_optional(&state.db)
+    .await
+    .context("Failed to clear login lockout")?
+    .ok_or_else(|| AppError::NotFound("Login lockout not found or already cleared".to_string()))?;
+
+    // give the subject a clean slate, otherwise their next typo would lock them out again
+    if clear_failed_logins(&state, &lockout.scope, &lockout.subject)
+        .await
+        .is_err()
+    {
+        error!("FAILED TO CLEAR FAILED LOGINS!");
+    }
+
+    Ok(ApiResponse::ok(
+        "Login lockout cleared successfully",
+        lockout,
+    ))
+}
@@ -0,0 +1,82 @@
+use crate::AppState;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::{Query, State};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+// default and maximum page sizes for lockout listings
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct GetLoginLockoutsParams {
+    pub active_only: Option<bool>,
+    pub scope: Option<String>, // "account" or "ip"
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LoginLockout {
+    id: i64,
+    scope: String,
+    subject: String,
+    failed_attempts: i32,
+    locked_until: NaiveDateTime,
+    cleared_at: Option<NaiveDateTime>,
+    cleared_by: Option<i64>,
+    created_at: NaiveDateTime,
+    is_active: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    count: usize,
+    lockouts: Option<Vec<LoginLockout>>,
+}
+
+pub async fn get_login_lockouts(
+    State(state): State<AppState>,
+    Query(params): Query<GetLoginLockoutsParams>,
+) -> ApiResult<ResponseCore> {
+    if let Some(scope) = params.scope.as_deref()
+        && !matches!(scope, "account" | "ip")
+    {
+        return Err(AppError::BadRequest(
+            "Scope must be 'account' or 'ip'".to_string(),
+        ));
+    }
+
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .clamp(1, MAX_PAGE_LIMIT);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    let lockouts = sqlx::query_as::<_, LoginLockout>(
+        r#"
+        SELECT *, (cleared_at IS NULL AND locked_until > NOW()) AS is_active
+        FROM login_lockouts
+        WHERE ($1::TEXT IS NULL OR scope = $1)
+            AND (NOT $2 OR (cleared_at IS NULL AND locked_until > NOW()))
+        ORDER BY id DESC
+        LIMIT $3 OFFSET $4
+        "#,
+    )
+    .bind(params.scope.as_deref())
+    .bind(params.active_only.unwrap_or(false))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await
+    .context("Failed to fetch login lockouts")?;
+
+    Ok(ApiResponse::ok(
+        "Login lockouts fetched successfully",
+        ResponseCore {
+            count: lockouts.len(),
+            lockouts: Some(lockouts),
+        },
+    ))
+}
@@ -1,4 +1,6 @@
 pub mod activate_user;
 pub mod add_admin;
+pub mod clear_login_lockout;
 pub mod deactivate_user;
+pub mod get_login_lockouts;
 pub mod remove_admin;
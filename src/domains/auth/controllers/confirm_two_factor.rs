@@ -0,0 +1,79 @@
+use crate::AppState;
+use crate::domains::auth::two_factor::{build_totp, redeem_totp_code, replace_recovery_codes};
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::{
+    Json,
+    extract::{Extension, State},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmTwoFactorRequest {
+    code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    recovery_codes: Vec<String>,
+}
+
+pub async fn confirm_two_factor(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Json(payload): Json<ConfirmTwoFactorRequest>,
+) -> ApiResult<ResponseCore> {
+    // Step 1: There must be a pending enrollment
+    let pending_secret = match sqlx::query_as::<_, (Option<String>, bool)>(
+        "SELECT totp_secret, totp_enabled FROM users WHERE id = $1",
+    )
+    .bind(session.user.id)
+    .fetch_one(&state.db)
+    .await
+    .context("Failed to confirm two-factor authentication")?
+    {
+        (_, true) => {
+            return Err(AppError::Conflict(
+                "Two-factor authentication is already enabled".to_string(),
+            ));
+        }
+        (Some(secret), false) => secret,
+        (None, false) => {
+            return Err(AppError::BadRequest(
+                "Start two-factor enrollment before confirming it".to_string(),
+            ));
+        }
+    };
+
+    // Step 2: The first code proves the authenticator app holds the secret
+    let code_accepted = match build_totp(&pending_secret, &session.user.email) {
+        Ok(totp) => redeem_totp_code(&state, session.user.id, &totp, &payload.code)
+            .await
+            .map_err(anyhow::Error::from),
+        Err(e) => Err(e),
+    }
+    .context("Failed to confirm two-factor authentication")?;
+
+    if !code_accepted {
+        return Err(AppError::Unauthorized(
+            "Invalid or already used code".to_string(),
+        ));
+    }
+
+    // Step 3: Recovery codes first, so 2FA is never on without a way back in
+    let recovery_codes = replace_recovery_codes(&state, session.user.id)
+        .await
+        .context("Failed to generate recovery codes")?;
+
+    sqlx::query("UPDATE users SET totp_enabled = TRUE, updated_at = NOW() WHERE id = $1")
+        .bind(session.user.id)
+        .execute(&state.db)
+        .await
+        .context("Failed to enable two-factor authentication")?;
+
+    Ok(ApiResponse::ok(
+        "Two-factor authentication enabled. Store these recovery codes somewhere safe, they won't be shown again",
+        ResponseCore { recovery_codes },
+    ))
+}
@@ -0,0 +1,99 @@
+use crate::AppState;
+use crate::domains::auth::two_factor::redeem_second_factor;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::{
+    Json,
+    extract::{Extension, State},
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct DisableTwoFactorRequest {
+    code: Option<String>,
+    recovery_code: Option<String>,
+}
+
+pub async fn disable_two_factor(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Json(payload): Json<DisableTwoFactorRequest>,
+) -> ApiResult<()> {
+    // Step 1: Only an enabled second factor can be turned off
+    let secret = match sqlx::query_as::<_, (Option<String>, bool)>(
+        "SELECT totp_secret, totp_enabled FROM users WHERE id = $1",
+    )
+    .bind(session.user.id)
+    .fetch_one(&state.db)
+    .await
+    .context("Failed to disable two-factor authentication")?
+    {
+        (Some(secret), true) => secret,
+        _ => {
+            return Err(AppError::BadRequest(
+                "Two-factor authentication is not enabled".to_string(),
+            ));
+        }
+    };
+
+    if payload.code.is_none() && payload.recovery_code.is_none() {
+        return Err(AppError::BadRequest(
+            "Provide a code from your authenticator app or a recovery code".to_string(),
+        ));
+    }
+
+    // Step 2: A stolen session alone isn't enough to take the second factor away
+    let code_accepted = redeem_second_factor(
+        &state,
+        session.user.id,
+        &session.user.email,
+        &secret,
+        payload.code.as_deref(),
+        payload.recovery_code.as_deref(),
+    )
+    .await
+    .context("Failed to disable two-factor authentication")?;
+
+    if !code_accepted {
+        return Err(AppError::Unauthorized(
+            "Invalid or already used code".to_string(),
+        ));
+    }
+
+    // Step 3: Forget the secret, the recovery codes and any open challenges together
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .context("Failed to disable two-factor authentication")?;
+
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET totp_enabled = FALSE, totp_secret = NULL, totp_last_used_step = NULL, updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(session.user.id)
+    .execute(&mut *tx)
+    .await
+    .context("Failed to disable two-factor authentication")?;
+
+    for query in [
+        "DELETE FROM totp_recovery_codes WHERE user_id = $1",
+        "DELETE FROM two_factor_challenges WHERE user_id = $1",
+    ] {
+        sqlx::query(query)
+            .bind(session.user.id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to disable two-factor authentication")?;
+    }
+
+    tx.commit()
+        .await
+        .context("Failed to disable two-factor authentication")?;
+
+    Ok(ApiResponse::ok("Two-factor authentication disabled", ()))
+}
@@ -0,0 +1,53 @@
+use crate::AppState;
+use crate::domains::auth::two_factor::{build_totp, new_totp_secret};
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::{Extension, State};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    secret: String,
+    otpauth_uri: String,
+}
+
+pub async fn enroll_two_factor(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+) -> ApiResult<ResponseCore> {
+    let secret = new_totp_secret();
+
+    let otpauth_uri = build_totp(&secret, &session.user.email)
+        .context("Failed to start two-factor enrollment")?
+        .get_url();
+
+    // the secret stays pending until it's confirmed with a first code; enrolling again before
+    // that simply replaces it
+    let enroll_result = sqlx::query(
+        r#"
+        UPDATE users
+        SET totp_secret = $1, totp_last_used_step = NULL, updated_at = NOW()
+        WHERE id = $2 AND totp_enabled = FALSE
+        "#,
+    )
+    .bind(&secret)
+    .bind(session.user.id)
+    .execute(&state.db)
+    .await
+    .context("Failed to start two-factor enrollment")?;
+
+    if enroll_result.rows_affected() == 0 {
+        return Err(AppError::Conflict(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+
+    Ok(ApiResponse::ok(
+        "Scan the code in your authenticator app, then confirm with a first code",
+        ResponseCore {
+            secret,
+            otpauth_uri,
+        },
+    ))
+}
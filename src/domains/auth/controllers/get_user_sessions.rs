@@ -0,0 +1,66 @@
+use crate::AppState;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::ErrorContext;
+use axum::extract::{Extension, State};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ActiveSession {
+    pub id: i64,
+    pub device_label: String,
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+    #[sqlx(skip)]
+    pub is_current: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    count: usize,
+    sessions: Option<Vec<ActiveSession>>,
+}
+
+pub async fn get_user_sessions(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+) -> ApiResult<ResponseCore> {
+    // only sessions that can still be used - revoked and expired ones are left out
+    let mut sessions = sqlx::query_as::<_, ActiveSession>(
+        r#"
+        SELECT
+            id,
+            COALESCE(device_name, user_agent, 'Unknown device') AS device_label,
+            device_name,
+            user_agent,
+            ip_address,
+            created_at,
+            last_used_at,
+            expires_at
+        FROM sessions
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        ORDER BY last_used_at DESC
+        "#,
+    )
+    .bind(session.user.id)
+    .fetch_all(&state.db)
+    .await
+    .context("Failed to fetch active sessions")?;
+
+    for active_session in sessions.iter_mut() {
+        active_session.is_current = Some(active_session.id) == session.session_id;
+    }
+
+    Ok(ApiResponse::ok(
+        "Active sessions fetched successfully",
+        ResponseCore {
+            count: sessions.len(),
+            sessions: Some(sessions),
+        },
+    ))
+}
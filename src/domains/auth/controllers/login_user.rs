@@ -1,39 +1,30 @@
-use crate::utils::generate_tokens::{User, generate_tokens};
+use crate::domains::auth::login_protection::{find_active_lockout, reject_login, too_many_attempts};
+use crate::domains::auth::sign_in::sign_in;
+use crate::domains::auth::two_factor::create_challenge;
+use crate::models::user::UserProfile;
+use crate::utils::generate_tokens::{User, generate_two_factor_challenge};
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
 use axum::extract::State;
-use axum::{Json, http::StatusCode, response::IntoResponse};
+use axum::Json;
 use serde::{Deserialize, Serialize};
 // utils import
 use crate::AppState;
-use crate::utils::cookie_deploy_handler::deploy_auth_cookie;
+use crate::utils::load_config::EmailVerificationMode;
+use crate::utils::metrics::LOGINS;
+use crate::utils::session_manager::ClientMeta;
 use crate::utils::verification_handler::verification_handler; // your existing password verification function
-use chrono::NaiveDateTime;
 use tower_cookies::Cookies;
 use tracing::error;
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
-pub struct UserProfile {
-    // #[sqlx(rename = "id")]
-    id: i64,
-    full_name: String,
-    email: String,
-    profile_image: Option<String>,
-    #[serde(skip_serializing)]
-    password: String,
-    is_admin: bool,
-    is_active: bool,
-    status: String,
-    country: String,
-    phone_number: String,
-    is_logged_out: bool,
-    pub created_at: NaiveDateTime,
-    pub updated_at: NaiveDateTime,
-}
-
 #[derive(Debug, Serialize)]
 pub struct ResponseCore {
     user_profile: UserProfile,
+    session_id: Option<i64>,
     access_token: Option<String>,
     refresh_token: Option<String>,
+    two_factor_required: bool,
+    challenge_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -42,141 +33,135 @@ pub struct LoginRequest {
     password: String,
 }
 
-#[derive(Debug, Serialize)]
-pub struct LoginResponse {
-    response_message: String,
-    response: Option<ResponseCore>,
-    error: Option<String>,
-}
-
 // Reuse UserProfile and ResponseCore from register controller
 
 pub async fn login_user(
     cookies: Cookies,
     // Extension(db_pool): Extension<PgPool>,
     State(state): State<AppState>,
+    meta: ClientMeta,
     Json(payload): Json<LoginRequest>,
-) -> impl IntoResponse {
+) -> ApiResult<ResponseCore> {
+    // Locked out accounts and addresses are turned away before the password is even looked at
+    if let Some(lockout) = find_active_lockout(&state, &payload.email, meta.ip_address.as_deref())
+        .await
+        .context("Login failed")?
+    {
+        error!("LOGIN BLOCKED BY LOCKOUT!");
+
+        state
+            .metrics
+            .increment(LOGINS, &[("outcome", "locked_out")]);
+
+        return Err(too_many_attempts(&lockout));
+    }
+
     // Fetch user by email
-    let user_result = sqlx::query_as::<_, UserProfile>(
-        "SELECT id, full_name, email, profile_image, password, is_active, is_admin, country, phone_number, is_logged_out, status, created_at, updated_at FROM users WHERE email = $1",
-    )
-    .bind(&payload.email)
-    .fetch_optional(&state.db)
-    .await;
-
-    let user = match user_result {
-        Ok(Some(user)) => user,
-        Ok(None) => {
-            error!("LOGIN FAILED: PROVIDE EMAIL AND PASSWORD!");
-
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(LoginResponse {
-                    response_message: "Login failed".to_string(),
-                    response: None,
-                    error: Some("Login failed - provide a correct email and password".to_string()),
-                }),
-            );
-        }
-        Err(e) => {
-            error!("USER LOGIN FAILED!");
-
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(LoginResponse {
-                    response_message: "Login failed".to_string(),
-                    response: None,
-                    error: Some(format!("Database error: {}", e)),
-                }),
-            );
-        }
+    let user = state
+        .repos
+        .users
+        .credentials(&payload.email)
+        .await
+        .context("Login failed")?;
+
+    let Some(user) = user else {
+        return Err(reject_login(
+            &state,
+            &payload.email,
+            meta.ip_address.as_deref(),
+            "Login failed - provide a correct email and password",
+        )
+        .await);
     };
 
-    match verification_handler(&payload.password, &user.password).await {
-        Ok(true) => {
-            let tokens = match generate_tokens(
-                "auth",
-                User {
-                    id: user.id,
-                    email: payload.email.clone(),
-                },
-            )
+    let password_check = verification_handler(&payload.password, &user.password)
+        .await
+        .map_err(|e| AppError::internal("Login failed", e))?;
+
+    if !password_check {
+        return Err(reject_login(
+            &state,
+            &payload.email,
+            meta.ip_address.as_deref(),
+            "Invalid email or password",
+        )
+        .await);
+    }
+
+    let user_profile = state
+        .repos
+        .users
+        .find(user.id)
+        .await
+        .context("Login failed")?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    if !user.email_verified
+        && state.config.email_verification_mode() == EmailVerificationMode::Login
+    {
+        return Err(AppError::Forbidden(
+            "Please verify your email address before logging in".to_string(),
+        ));
+    }
+
+    if user.totp_enabled {
+        // the password only gets the user halfway - the real tokens are issued once the
+        // challenge is exchanged with a TOTP or recovery code
+        let challenge_id = create_challenge(&state, user.id)
             .await
-            {
-                Ok(tokens) => tokens,
-                Err(e) => {
-                    error!("TOKEN GENERATION ERROR!");
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(LoginResponse {
-                            response_message: "Failed to generate tokens".to_string(),
-                            response: None,
-                            error: Some(format!("Token generation error: {}", e)),
-                        }),
-                    );
-                }
-            };
-
-            deploy_auth_cookie(cookies, tokens.auth_cookie.unwrap()).await;
-
-            let _ = sqlx::query_as::<_, UserProfile>(
-                r#"
-                    UPDATE users
-                    SET
-                        access_token = $1,
-                        refresh_token = $2,
-                        is_logged_out = $3,
-                        updated_at = NOW()
-                    WHERE email = $4
-                "#,
-            )
-            .bind(&tokens.access_token)
-            .bind(&tokens.refresh_token)
-            .bind(false)
-            .bind(&payload.email)
-            .fetch_one(&state.db)
-            .await;
-
-            (
-                StatusCode::OK,
-                Json(LoginResponse {
-                    response_message: "Login successful".to_string(),
-                    response: Some(ResponseCore {
-                        user_profile: UserProfile {
-                            is_logged_out: false,
-                            ..user
-                        },
-                        access_token: tokens.access_token,
-                        refresh_token: tokens.refresh_token,
-                    }),
-                    error: None,
-                }),
-            )
-        }
-        Ok(false) => {
-            error!("USER LOGIN FAILED!");
-
-            (
-                StatusCode::UNAUTHORIZED,
-                Json(LoginResponse {
-                    response_message: "Login failed".to_string(),
-                    response: None,
-                    error: Some("Invalid email or password".to_string()),
-                }),
-            )
-        }
-        Err(e) => {
-            error!("USER LOGIN FAILED!");
-
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(LoginResponse {
-                    response_message: "Login failed".to_string(),
-                    response: None,
-                    error: Some(format!("Password verification error: {}", e)),
-                }),
-            )
-        }
+            .context("Failed to start two-factor challenge")?;
+
+        let challenge_token = generate_two_factor_challenge(
+            &state.config,
+            User {
+                id: user.id,
+                email: user.email.clone(),
+            },
+            &challenge_id,
+        )
+        .await
+        .map_err(|e| AppError::internal("Failed to generate two-factor challenge", e))?
+        .two_factor_challenge_token;
+
+        state
+            .metrics
+            .increment(LOGINS, &[("outcome", "two_factor_required")]);
+
+        return Ok(ApiResponse::ok(
+            "Two-factor authentication required",
+            ResponseCore {
+                user_profile,
+                session_id: None,
+                access_token: None,
+                refresh_token: None,
+                two_factor_required: true,
+                challenge_token,
+            },
+        ));
     }
+
+    let signed_in = sign_in(
+        &state,
+        cookies,
+        User {
+            id: user.id,
+            email: payload.email.clone(),
+        },
+        &meta,
+    )
+    .await?;
+
+    state.metrics.increment(LOGINS, &[("outcome", "succeeded")]);
+
+    Ok(ApiResponse::ok(
+        "Login successful",
+        ResponseCore {
+            user_profile,
+            session_id: Some(signed_in.session_id),
+            access_token: signed_in.access_token,
+            refresh_token: signed_in.refresh_token,
+            two_factor_required: false,
+            challenge_token: None,
+        },
+    ))
 }
@@ -1,107 +1,61 @@
 use crate::AppState;
+use crate::utils::session_manager::{read_token_claims, revoke_session};
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
 use axum::extract::State;
-use axum::{Json, extract::Query, http::StatusCode, response::IntoResponse};
-use chrono::NaiveDateTime;
-use serde::{Deserialize, Serialize};
+use axum::{
+    extract::Query,
+    http::{HeaderMap, header},
+};
+use serde::Deserialize;
 use tower_cookies::{Cookie, Cookies};
 use tracing::error;
 
-#[derive(Debug, Serialize)]
-pub struct LogoutResponse {
-    response_message: String,
-    response: Option<UserProfile>,
-    error: Option<String>,
-}
-
 #[derive(Deserialize)]
 pub struct SearchParams {
     user_email: String,
 }
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
-pub struct UserProfile {
-    id: i64,
-    full_name: String,
-    email: String,
-    profile_image: Option<String>,
-    is_admin: bool,
-    is_active: bool,
-    status: String,
-    last_seen: Option<String>,
-    country: String,
-    phone_number: String,
-    is_logged_out: bool,
-    pub created_at: NaiveDateTime,
-    pub updated_at: NaiveDateTime,
-}
-
 pub async fn logout_user(
     State(state): State<AppState>,
     Query(params): Query<SearchParams>,
+    headers: HeaderMap,
     cookies: Cookies,
-) -> impl IntoResponse {
+) -> ApiResult<()> {
     // info!("Logout request for user: {}", params.user_email);
 
+    // End the session the bearer token belongs to - an expired access token still identifies it
+    let session_id = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .and_then(|token| read_token_claims(token, false).ok())
+        .filter(|claims| claims.email == params.user_email)
+        .and_then(|claims| claims.sid);
+
+    if let Some(session_id) = session_id
+        && revoke_session(&state, session_id, "logout").await.is_err()
+    {
+        error!("FAILED TO REVOKE SESSION!");
+    }
+
     // Remove auth cookie
     let mut cookie = Cookie::new("rusty_chat_auth_cookie", "");
     cookie.set_path("/");
     cookie.set_max_age(tower_cookies::cookie::time::Duration::ZERO);
     cookies.remove(cookie);
 
-    // Clear tokens in database - IMPORTANT: Add RETURNING clause
-    let user = sqlx::query_as::<_, UserProfile>(
-        r#"
-                UPDATE users
-                SET
-                    access_token = $1,
-                    refresh_token = $2,
-                    is_logged_out = $3,
-                    updated_at = NOW()
-                WHERE email = $4
-                RETURNING
-                id,
-                full_name,
-                is_logged_out,
-                email,
-                profile_image,
-                status,
-                last_seen,
-                is_admin,
-                is_active,
-                is_logged_out,
-                country,
-                phone_number,
-                created_at,
-                updated_at
-            "#,
-    )
-    .bind("") // access_token
-    .bind("") // refresh_token
-    .bind(true)
-    .bind(&params.user_email)
-    .fetch_one(&state.db)
-    .await;
+    // Clear tokens in database
+    let logged_out = state
+        .repos
+        .users
+        .log_out(&params.user_email)
+        .await
+        .context("Logout failed")?;
 
-    match user {
-        Ok(_user) => (
-            StatusCode::OK,
-            Json(LogoutResponse {
-                response_message: "Logout successful".to_string(),
-                error: None,
-                response: None,
-            }),
-        ),
-        Err(e) => {
-            error!("USER LOGOUT WAS UNSUCCESSFUL!");
-
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(LogoutResponse {
-                    response_message: "Logout failed".to_string(),
-                    error: Some(e.to_string()),
-                    response: None,
-                }),
-            )
-        }
+    if !logged_out {
+        return Err(AppError::NotFound("User not found".to_string()));
     }
+
+    Ok(ApiResponse::message("Logout successful"))
 }
@@ -1,3 +1,16 @@
+pub mod confirm_two_factor;
+pub mod disable_two_factor;
+pub mod enroll_two_factor;
+pub mod get_user_sessions;
 pub mod login_user;
 pub mod logout_user;
+pub mod refresh_session;
+pub mod regenerate_recovery_codes;
 pub mod register_user;
+pub mod request_password_reset;
+pub mod resend_verification_email;
+pub mod reset_password;
+pub mod revoke_other_sessions;
+pub mod revoke_user_session;
+pub mod verify_email;
+pub mod verify_two_factor;
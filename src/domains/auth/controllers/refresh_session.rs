@@ -0,0 +1,131 @@
+use crate::AppState;
+use crate::utils::cookie_deploy_handler::deploy_auth_cookie;
+use crate::utils::generate_tokens::{User, generate_session_tokens};
+use crate::utils::session_manager::{
+    ClientMeta, fetch_session, read_token_claims, revoke_session, rotate_session,
+};
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::State;
+use axum::Json;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use tower_cookies::Cookies;
+use tracing::error;
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    refresh_token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    session_id: i64,
+    expires_at: NaiveDateTime,
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+}
+
+fn unauthorized(message: &str) -> AppError {
+    AppError::Unauthorized(message.to_string())
+}
+
+pub async fn refresh_session(
+    cookies: Cookies,
+    State(state): State<AppState>,
+    meta: ClientMeta,
+    Json(payload): Json<RefreshRequest>,
+) -> ApiResult<ResponseCore> {
+    // Step 1: Verify the refresh token itself - an expired one comes back as `token_expired`
+    let claims = read_token_claims(&payload.refresh_token, true)?;
+
+    // refresh tokens from before they were given a purpose have none
+    if claims
+        .purpose
+        .as_deref()
+        .is_some_and(|purpose| purpose != "refresh")
+    {
+        return Err(unauthorized("Token is not a refresh token"));
+    }
+
+    // tokens issued before sessions existed cannot be rotated
+    let (Some(session_id), Some(refresh_jti)) = (claims.sid, claims.jti.as_deref()) else {
+        error!("REFRESH TOKEN NOT BOUND TO A SESSION!");
+
+        return Err(unauthorized(
+            "Refresh token is not bound to a session, please re-authenticate",
+        ));
+    };
+
+    // Step 2: Deactivated users can't renew their sessions
+    match sqlx::query_scalar::<_, bool>("SELECT is_active FROM users WHERE id = $1")
+        .bind(claims.id)
+        .fetch_optional(&state.db)
+        .await
+        .context("Failed to refresh session")?
+    {
+        Some(true) => (),
+        Some(false) => {
+            return Err(AppError::Forbidden("Your account is deactivated".to_string()));
+        }
+        None => {
+            return Err(unauthorized("User not found, please re-authenticate"));
+        }
+    }
+
+    // Step 3: Rotate - only succeeds while this refresh token is still the session's current one
+    let rotated = rotate_session(&state, session_id, claims.id, refresh_jti, &meta)
+        .await
+        .context("Failed to refresh session")?;
+
+    let Some(session) = rotated else {
+        // A validly signed token for a live session whose jti no longer matches has been used
+        // before - someone is replaying it, so the whole session is killed.
+        if let Ok(Some(session)) = fetch_session(&state, session_id, claims.id).await
+            && session.revoked_at.is_none()
+            && session.refresh_jti != refresh_jti
+        {
+            error!("REFRESH TOKEN REUSE DETECTED!");
+
+            if revoke_session(&state, session.id, "refresh_token_reuse")
+                .await
+                .is_err()
+            {
+                error!("FAILED TO REVOKE REUSED SESSION!");
+            }
+
+            return Err(unauthorized(
+                "Refresh token reuse detected, session has been revoked",
+            ));
+        }
+
+        return Err(unauthorized(
+            "Session expired or revoked, please re-authenticate",
+        ));
+    };
+
+    // Step 4: Issue the new token pair
+    let tokens = generate_session_tokens(
+        &state.config,
+        User {
+            id: claims.id,
+            email: claims.email,
+        },
+        session.id,
+        &session.refresh_jti,
+    )
+    .await
+    .map_err(|e| AppError::internal("Failed to generate tokens", e))?;
+
+    deploy_auth_cookie(cookies, tokens.auth_cookie.unwrap()).await;
+
+    Ok(ApiResponse::ok(
+        "Session refreshed successfully",
+        ResponseCore {
+            session_id: session.id,
+            expires_at: session.expires_at,
+            access_token: tokens.access_token,
+            refresh_token: tokens.refresh_token,
+        },
+    ))
+}
@@ -0,0 +1,78 @@
+use crate::AppState;
+use crate::domains::auth::two_factor::{redeem_second_factor, replace_recovery_codes};
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::{
+    Json,
+    extract::{Extension, State},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct RegenerateRecoveryCodesRequest {
+    code: Option<String>,
+    recovery_code: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    recovery_codes: Vec<String>,
+}
+
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Json(payload): Json<RegenerateRecoveryCodesRequest>,
+) -> ApiResult<ResponseCore> {
+    // Step 1: Recovery codes only exist while two-factor authentication is on
+    let secret = match sqlx::query_as::<_, (Option<String>, bool)>(
+        "SELECT totp_secret, totp_enabled FROM users WHERE id = $1",
+    )
+    .bind(session.user.id)
+    .fetch_one(&state.db)
+    .await
+    .context("Failed to regenerate recovery codes")?
+    {
+        (Some(secret), true) => secret,
+        _ => {
+            return Err(AppError::BadRequest(
+                "Two-factor authentication is not enabled".to_string(),
+            ));
+        }
+    };
+
+    if payload.code.is_none() && payload.recovery_code.is_none() {
+        return Err(AppError::BadRequest(
+            "Provide a code from your authenticator app or a recovery code".to_string(),
+        ));
+    }
+
+    // Step 2: Prove the second factor is still at hand
+    let code_accepted = redeem_second_factor(
+        &state,
+        session.user.id,
+        &session.user.email,
+        &secret,
+        payload.code.as_deref(),
+        payload.recovery_code.as_deref(),
+    )
+    .await
+    .context("Failed to regenerate recovery codes")?;
+
+    if !code_accepted {
+        return Err(AppError::Unauthorized(
+            "Invalid or already used code".to_string(),
+        ));
+    }
+
+    // Step 3: The old codes stop working as soon as the new ones exist
+    let recovery_codes = replace_recovery_codes(&state, session.user.id)
+        .await
+        .context("Failed to regenerate recovery codes")?;
+
+    Ok(ApiResponse::ok(
+        "Recovery codes regenerated. Store them somewhere safe, they won't be shown again",
+        ResponseCore { recovery_codes },
+    ))
+}
@@ -0,0 +1,89 @@
+use crate::AppState;
+use crate::utils::generate_tokens::{User, generate_tokens};
+use crate::utils::mail_sender::OutgoingMail;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::{Json, extract::State};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct PasswordResetRequest {
+    email: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ResetCandidate {
+    id: i64,
+    email: String,
+    full_name: String,
+}
+
+// same wording whether or not the email is registered, so the endpoint can't be used to probe
+// for accounts
+const RESET_REQUESTED_MESSAGE: &str =
+    "If an account exists for this email, a password reset code has been sent to it";
+
+pub async fn request_password_reset(
+    State(state): State<AppState>,
+    Json(payload): Json<PasswordResetRequest>,
+) -> ApiResult<()> {
+    // Step 1: Look up an active account for the email
+    let Some(user) = sqlx::query_as::<_, ResetCandidate>(
+        "SELECT id, email, full_name FROM users WHERE email = $1 AND is_active = TRUE",
+    )
+    .bind(payload.email.trim())
+    .fetch_optional(&state.db)
+    .await
+    .context("Failed to request password reset")?
+    else {
+        return Ok(ApiResponse::message(RESET_REQUESTED_MESSAGE));
+    };
+
+    // Step 2: Mint the one-time password token and store it - a newer request replaces an older
+    // token, so only the latest mail can be used
+    let otp_token = generate_tokens(
+        &state.config,
+        "one_time_password",
+        User {
+            id: user.id,
+            email: user.email.clone(),
+        },
+    )
+    .await
+    .map_err(|e| AppError::internal("Failed to generate password reset code", e))?
+    .one_time_password_token
+    .unwrap_or_default();
+
+    sqlx::query(
+        "UPDATE users SET one_time_password_token = $1, updated_at = NOW() WHERE id = $2",
+    )
+    .bind(&otp_token)
+    .bind(user.id)
+    .execute(&state.db)
+    .await
+    .context("Failed to request password reset")?;
+
+    // Step 3: Hand the code to the configured mail sender
+    let lifetime_mins = state
+        .config
+        .token_ttls()
+        .one_time_password_secs
+        .div_ceil(60);
+
+    let mail = OutgoingMail {
+        to: user.email,
+        subject: "Reset your Rusty Chat password".to_string(),
+        body: format!(
+            "Hi {},\n\nUse the code below to reset your password. It expires in {} minutes and can only be used once.\n\n{}\n\nIf you didn't ask for a password reset, you can ignore this email.",
+            user.full_name, lifetime_mins, otp_token
+        ),
+    };
+
+    state
+        .mailer
+        .send(&mail)
+        .await
+        .context("Failed to send password reset code")?;
+
+    Ok(ApiResponse::message(RESET_REQUESTED_MESSAGE))
+}
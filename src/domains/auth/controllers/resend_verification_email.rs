@@ -0,0 +1,79 @@
+use crate::AppState;
+use crate::domains::auth::email_verification::send_verification_email;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::ErrorContext;
+use axum::{Json, extract::State};
+use serde::Deserialize;
+use tracing::info;
+
+// used when the [auth] config section doesn't set a cooldown
+const DEFAULT_RESEND_COOLDOWN_SECS: u64 = 60;
+
+#[derive(Debug, Deserialize)]
+pub struct ResendVerificationRequest {
+    email: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct PendingVerification {
+    id: i64,
+    email: String,
+    full_name: String,
+    email_verified: bool,
+    cooldown_remaining_secs: i64,
+}
+
+// unknown and already verified emails get the same answer, so the endpoint can't be used to
+// probe for accounts
+const RESEND_ACCEPTED_MESSAGE: &str =
+    "If this email belongs to an unverified account, a new verification code has been sent to it";
+
+pub async fn resend_verification_email(
+    State(state): State<AppState>,
+    Json(payload): Json<ResendVerificationRequest>,
+) -> ApiResult<()> {
+    let cooldown_secs = state
+        .config
+        .auth
+        .as_ref()
+        .and_then(|auth| auth.verification_resend_cooldown_secs)
+        .unwrap_or(DEFAULT_RESEND_COOLDOWN_SECS);
+
+    let user = match sqlx::query_as::<_, PendingVerification>(
+        r#"
+        SELECT
+            id, email, full_name, email_verified,
+            COALESCE(
+                CEIL(EXTRACT(EPOCH FROM (
+                    email_verification_sent_at + make_interval(secs => $2) - NOW()
+                )))::BIGINT,
+                0
+            ) AS cooldown_remaining_secs
+        FROM users
+        WHERE email = $1
+        "#,
+    )
+    .bind(payload.email.trim())
+    .bind(cooldown_secs as f64)
+    .fetch_optional(&state.db)
+    .await
+    .context("Failed to resend verification email")?
+    {
+        Some(user) if !user.email_verified => user,
+        _ => return Ok(ApiResponse::message(RESEND_ACCEPTED_MESSAGE)),
+    };
+
+    // a cooldown only exists for unverified accounts, so telling the caller about it would give
+    // them away - the request is quietly dropped instead
+    if user.cooldown_remaining_secs > 0 {
+        info!("VERIFICATION EMAIL RESEND SKIPPED: COOLDOWN ACTIVE");
+
+        return Ok(ApiResponse::message(RESEND_ACCEPTED_MESSAGE));
+    }
+
+    send_verification_email(&state, user.id, &user.email, &user.full_name)
+        .await
+        .context("Failed to resend verification email")?;
+
+    Ok(ApiResponse::message(RESEND_ACCEPTED_MESSAGE))
+}
@@ -0,0 +1,88 @@
+use crate::AppState;
+use crate::utils::hashing_handler::hashing_handler;
+use crate::utils::session_manager::read_token_claims;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use crate::utils::realtime_hub::AccessRevocation;
+use axum::{Json, extract::State};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordRequest {
+    email: String,
+    one_time_password_token: String,
+    new_password: String,
+}
+
+fn rejected(message: &str) -> AppError {
+    AppError::Unauthorized(message.to_string())
+}
+
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> ApiResult<()> {
+    if payload.new_password.trim().is_empty() {
+        return Err(AppError::BadRequest("New password is required".to_string()));
+    }
+
+    // Step 1: Check signature, expiry and that the code was issued for this email - an expired
+    // code comes back as `token_expired`
+    let claims = read_token_claims(&payload.one_time_password_token, true)?;
+
+    if claims.email != payload.email.trim()
+        || claims.purpose.as_deref() != Some("one_time_password")
+    {
+        return Err(rejected("Invalid password reset code"));
+    }
+
+    let hashed_password = hashing_handler(&payload.new_password)
+        .await
+        .map_err(|e| AppError::internal("Failed to hash new password", e))?;
+
+    // Step 2: Swap the password, consuming the stored code in the same statement so it only
+    // works once. Existing tokens are cleared, which also ends legacy logins.
+    let user_id = sqlx::query_scalar::<_, i64>(
+        r#"
+        UPDATE users
+        SET
+            password = $1,
+            one_time_password_token = NULL,
+            access_token = NULL,
+            refresh_token = NULL,
+            is_logged_out = TRUE,
+            updated_at = NOW()
+        WHERE email = $2 AND one_time_password_token = $3
+        RETURNING id
+        "#,
+    )
+    .bind(&hashed_password)
+    .bind(payload.email.trim())
+    .bind(&payload.one_time_password_token)
+    .fetch_optional(&state.db)
+    .await
+    .context("Failed to reset password")?
+    .ok_or_else(|| rejected("Password reset code has already been used or replaced"))?;
+
+    // Step 3: Sign every device out
+    sqlx::query(
+        r#"
+        UPDATE sessions
+        SET revoked_at = NOW(), revoked_reason = 'password_reset', updated_at = NOW()
+        WHERE user_id = $1 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .execute(&state.db)
+    .await
+    .context("Password reset but failed to sign out existing sessions")?;
+
+    state.hub.revoke(AccessRevocation::Sessions {
+        user_id,
+        except: None,
+    });
+
+    Ok(ApiResponse::message(
+        "Password reset successfully, please log in with your new password",
+    ))
+}
@@ -0,0 +1,47 @@
+use crate::AppState;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::ErrorContext;
+use crate::utils::realtime_hub::AccessRevocation;
+use axum::extract::{Extension, State};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    revoked_count: u64,
+    current_session_id: Option<i64>,
+}
+
+pub async fn revoke_other_sessions(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+) -> ApiResult<ResponseCore> {
+    // a token issued before sessions existed has no session of its own to keep
+    let revoked = sqlx::query(
+        r#"
+        UPDATE sessions
+        SET revoked_at = NOW(), revoked_reason = 'revoked_by_user', updated_at = NOW()
+        WHERE user_id = $1
+            AND revoked_at IS NULL
+            AND ($2::BIGINT IS NULL OR id <> $2)
+        "#,
+    )
+    .bind(session.user.id)
+    .bind(session.session_id)
+    .execute(&state.db)
+    .await
+    .context("Failed to revoke other sessions")?;
+
+    state.hub.revoke(AccessRevocation::Sessions {
+        user_id: session.user.id,
+        except: session.session_id,
+    });
+
+    Ok(ApiResponse::ok(
+        "Other sessions revoked successfully",
+        ResponseCore {
+            revoked_count: revoked.rows_affected(),
+            current_session_id: session.session_id,
+        },
+    ))
+}
@@ -0,0 +1,46 @@
+use crate::AppState;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use crate::utils::realtime_hub::AccessRevocation;
+use axum::extract::{Extension, Path, State};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RevokedSession {
+    pub id: i64,
+    pub device_name: Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub revoked_reason: Option<String>,
+}
+
+pub async fn revoke_user_session(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Path(session_id): Path<i64>,
+) -> ApiResult<RevokedSession> {
+    // scoped to the caller, so someone else's session id reads as not found
+    let revoked = sqlx::query_as::<_, RevokedSession>(
+        r#"
+        UPDATE sessions
+        SET revoked_at = NOW(), revoked_reason = 'revoked_by_user', updated_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()
+        RETURNING id, device_name, user_agent, ip_address, revoked_at, revoked_reason
+        "#,
+    )
+    .bind(session_id)
+    .bind(session.user.id)
+    .fetch_optional(&state.db)
+    .await
+    .context("Failed to revoke session")?
+    .ok_or_else(|| AppError::NotFound("Session not found or no longer active".to_string()))?;
+
+    state.hub.revoke(AccessRevocation::Session {
+        session_id: revoked.id,
+    });
+
+    Ok(ApiResponse::ok("Session revoked successfully", revoked))
+}
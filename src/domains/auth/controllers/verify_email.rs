@@ -0,0 +1,51 @@
+use crate::AppState;
+use crate::domains::auth::email_verification::VERIFICATION_TOKEN_TTL_HOURS;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::{Json, extract::State};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailRequest {
+    verification_token: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct VerifiedUser {
+    id: i64,
+    email: String,
+    email_verified: bool,
+    updated_at: NaiveDateTime,
+}
+
+pub async fn verify_email(
+    State(state): State<AppState>,
+    Json(payload): Json<VerifyEmailRequest>,
+) -> ApiResult<VerifiedUser> {
+    // the token is cleared on success, so a verification link only works once
+    let user = sqlx::query_as::<_, VerifiedUser>(
+        r#"
+        UPDATE users
+        SET
+            email_verified = TRUE,
+            email_verification_token = NULL,
+            updated_at = NOW()
+        WHERE email_verification_token = $1
+            AND email_verification_sent_at > NOW() - make_interval(hours => $2)
+        RETURNING id, email, email_verified, updated_at
+        "#,
+    )
+    .bind(payload.verification_token.trim())
+    .bind(VERIFICATION_TOKEN_TTL_HOURS)
+    .fetch_optional(&state.db)
+    .await
+    .context("Failed to verify email address")?
+    .ok_or_else(|| {
+        AppError::BadRequest(
+            "Verification token is invalid or has expired, please request a new one".to_string(),
+        )
+    })?;
+
+    Ok(ApiResponse::ok("Email address verified successfully", user))
+}
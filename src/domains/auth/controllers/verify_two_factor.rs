@@ -0,0 +1,174 @@
+use crate::AppState;
+use crate::domains::auth::login_protection::{
+    find_active_lockout, reject_login, too_many_attempts,
+};
+use crate::domains::auth::sign_in::sign_in;
+use crate::domains::auth::two_factor::{
+    consume_challenge, redeem_second_factor, take_challenge_attempt,
+};
+use crate::utils::generate_tokens::User;
+use crate::utils::metrics::LOGINS;
+use crate::utils::session_manager::{ClientMeta, read_token_claims};
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::State;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use tower_cookies::Cookies;
+use tracing::error;
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyTwoFactorRequest {
+    challenge_token: String,
+    code: Option<String>,
+    recovery_code: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct TwoFactorUser {
+    id: i64,
+    email: String,
+    totp_secret: Option<String>,
+    totp_enabled: bool,
+    is_active: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    session_id: i64,
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    used_recovery_code: bool,
+}
+
+fn rejected() -> AppError {
+    AppError::Unauthorized(
+        "Two-factor challenge is invalid or expired, please log in again".to_string(),
+    )
+}
+
+pub async fn verify_two_factor(
+    cookies: Cookies,
+    State(state): State<AppState>,
+    meta: ClientMeta,
+    Json(payload): Json<VerifyTwoFactorRequest>,
+) -> ApiResult<ResponseCore> {
+    // Step 1: The challenge proves the password step was passed a moment ago
+    let (claims, challenge_id) = match read_token_claims(&payload.challenge_token, true) {
+        Ok(claims) if claims.purpose.as_deref() == Some("two_factor_challenge") => {
+            match claims.jti.clone() {
+                Some(challenge_id) => (claims, challenge_id),
+                None => {
+                    error!("TWO-FACTOR CHALLENGE WITHOUT AN ID!");
+
+                    return Err(rejected());
+                }
+            }
+        }
+        _ => {
+            error!("INVALID TWO-FACTOR CHALLENGE!");
+
+            return Err(rejected());
+        }
+    };
+
+    let user = match sqlx::query_as::<_, TwoFactorUser>(
+        "SELECT id, email, totp_secret, totp_enabled, is_active FROM users WHERE id = $1",
+    )
+    .bind(claims.id)
+    .fetch_optional(&state.db)
+    .await
+    .context("Failed to verify two-factor code")?
+    {
+        Some(user) if user.is_active && user.totp_enabled && user.email == claims.email => user,
+        _ => {
+            error!("TWO-FACTOR CHALLENGE USER MISMATCH!");
+
+            return Err(rejected());
+        }
+    };
+
+    if payload.code.is_none() && payload.recovery_code.is_none() {
+        return Err(AppError::BadRequest(
+            "Provide a code from your authenticator app or a recovery code".to_string(),
+        ));
+    }
+
+    // Step 2: Wrong codes count towards the same lockouts as wrong passwords
+    if let Some(lockout) = find_active_lockout(&state, &user.email, meta.ip_address.as_deref())
+        .await
+        .context("Failed to verify two-factor code")?
+    {
+        error!("TWO-FACTOR VERIFICATION BLOCKED BY LOCKOUT!");
+
+        return Err(too_many_attempts(&lockout));
+    }
+
+    // each challenge only gets a handful of tries, counted before the code is even looked at
+    if !take_challenge_attempt(&state, &challenge_id, user.id)
+        .await
+        .context("Failed to verify two-factor code")?
+    {
+        error!("TWO-FACTOR CHALLENGE USED UP!");
+
+        return Err(rejected());
+    }
+
+    // Step 3: Either a current TOTP code or one of the recovery codes
+    let used_recovery_code = payload.code.is_none();
+
+    let code_accepted = redeem_second_factor(
+        &state,
+        user.id,
+        &user.email,
+        user.totp_secret.as_deref().unwrap_or(""),
+        payload.code.as_deref(),
+        payload.recovery_code.as_deref(),
+    )
+    .await
+    .context("Failed to verify two-factor code")?;
+
+    if !code_accepted {
+        error!("INVALID TWO-FACTOR CODE!");
+
+        return Err(reject_login(
+            &state,
+            &user.email,
+            meta.ip_address.as_deref(),
+            "Invalid or already used code",
+        )
+        .await);
+    }
+
+    // a challenge signs in once - a second request racing this one gets turned away
+    if !consume_challenge(&state, &challenge_id)
+        .await
+        .context("Failed to verify two-factor code")?
+    {
+        return Err(rejected());
+    }
+
+    // Step 4: Issue the real tokens
+    let signed_in = sign_in(
+        &state,
+        cookies,
+        User {
+            id: user.id,
+            email: user.email,
+        },
+        &meta,
+    )
+    .await?;
+
+    state.metrics.increment(LOGINS, &[("outcome", "succeeded")]);
+
+    Ok(ApiResponse::ok(
+        "Login successful",
+        ResponseCore {
+            session_id: signed_in.session_id,
+            access_token: signed_in.access_token,
+            refresh_token: signed_in.refresh_token,
+            used_recovery_code,
+        },
+    ))
+}
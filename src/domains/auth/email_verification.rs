@@ -0,0 +1,43 @@
+use crate::AppState;
+use crate::utils::mail_sender::OutgoingMail;
+use anyhow::{Context, Result};
+
+// how long an emailed verification token stays usable
+pub const VERIFICATION_TOKEN_TTL_HOURS: i32 = 24;
+
+/// Issues a fresh verification token for the user, replacing any earlier one, and mails it out.
+pub async fn send_verification_email(
+    state: &AppState,
+    user_id: i64,
+    email: &str,
+    full_name: &str,
+) -> Result<()> {
+    let verification_token = uuid::Uuid::new_v4().simple().to_string();
+
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET
+            email_verification_token = $1,
+            email_verification_sent_at = NOW(),
+            updated_at = NOW()
+        WHERE id = $2
+        "#,
+    )
+    .bind(&verification_token)
+    .bind(user_id)
+    .execute(&state.db)
+    .await
+    .context("Failed to store email verification token")?;
+
+    let mail = OutgoingMail {
+        to: email.to_string(),
+        subject: "Verify your Rusty Chat email address".to_string(),
+        body: format!(
+            "Hi {},\n\nUse the code below to verify your email address. It expires in {} hours.\n\n{}\n\nIf you didn't create a Rusty Chat account, you can ignore this email.",
+            full_name, VERIFICATION_TOKEN_TTL_HOURS, verification_token
+        ),
+    };
+
+    state.mailer.send(&mail).await
+}
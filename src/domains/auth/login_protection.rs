@@ -0,0 +1,215 @@
+use crate::AppState;
+use crate::utils::app_error::AppError;
+use crate::utils::load_config::SecuritySection;
+use crate::utils::metrics::LOGINS;
+use std::time::Duration;
+use tracing::error;
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct ActiveLockout {
+    pub scope: String, // "account" or "ip"
+    pub retry_after_secs: i64,
+}
+
+impl ActiveLockout {
+    pub fn message(&self) -> String {
+        let subject = match self.scope.as_str() {
+            "ip" => "from this address",
+            _ => "for this account",
+        };
+
+        format!(
+            "Too many failed login attempts {}, please try again in {} seconds",
+            subject,
+            self.retry_after_secs.max(1)
+        )
+    }
+}
+
+pub fn security_settings(state: &AppState) -> SecuritySection {
+    state.config.security.clone().unwrap_or_default()
+}
+
+/// The lockout with the longest time left on either the account or the client IP, if any.
+pub async fn find_active_lockout(
+    state: &AppState,
+    email: &str,
+    ip_address: Option<&str>,
+) -> Result<Option<ActiveLockout>, sqlx::Error> {
+    sqlx::query_as::<_, ActiveLockout>(
+        r#"
+        SELECT
+            scope,
+            CEIL(EXTRACT(EPOCH FROM (locked_until - NOW())))::BIGINT AS retry_after_secs
+        FROM login_lockouts
+        WHERE cleared_at IS NULL
+            AND locked_until > NOW()
+            AND (
+                (scope = 'account' AND subject = $1)
+                OR (scope = 'ip' AND subject = $2)
+            )
+        ORDER BY locked_until DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(email)
+    .bind(ip_address)
+    .fetch_optional(&state.db)
+    .await
+}
+
+/// Records a failed attempt and locks the account and/or IP once they pass their threshold.
+/// Returns the account's failure count inside the window and the lockout, if one was started.
+pub async fn record_failed_login(
+    state: &AppState,
+    security: &SecuritySection,
+    email: &str,
+    ip_address: Option<&str>,
+) -> Result<(i64, Option<ActiveLockout>), sqlx::Error> {
+    sqlx::query("INSERT INTO failed_login_attempts (email, ip_address) VALUES ($1, $2)")
+        .bind(email)
+        .bind(ip_address)
+        .execute(&state.db)
+        .await?;
+
+    let (account_failures, ip_failures) = sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        SELECT
+            (
+                SELECT COUNT(*) FROM failed_login_attempts
+                WHERE email = $1
+                    AND counts_for_account
+                    AND attempted_at > NOW() - make_interval(secs => $3)
+            ),
+            (
+                SELECT COUNT(*) FROM failed_login_attempts
+                WHERE ip_address = $2
+                    AND counts_for_ip
+                    AND attempted_at > NOW() - make_interval(secs => $3)
+            )
+        "#,
+    )
+    .bind(email)
+    .bind(ip_address)
+    .bind(security.failed_login_window_secs as f64)
+    .fetch_one(&state.db)
+    .await?;
+
+    let mut lockout = None;
+
+    if let Some(ip_address) = ip_address
+        && ip_failures >= i64::from(security.max_failed_logins_per_ip)
+    {
+        lockout = Some(lock_out(state, security, "ip", ip_address, ip_failures).await?);
+    }
+
+    if account_failures >= i64::from(security.max_failed_logins_per_account) {
+        lockout = Some(lock_out(state, security, "account", email, account_failures).await?);
+    }
+
+    Ok((account_failures, lockout))
+}
+
+async fn lock_out(
+    state: &AppState,
+    security: &SecuritySection,
+    scope: &str,
+    subject: &str,
+    failed_attempts: i64,
+) -> Result<ActiveLockout, sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO login_lockouts (scope, subject, failed_attempts, locked_until)
+        VALUES ($1, $2, $3, NOW() + make_interval(secs => $4))
+        "#,
+    )
+    .bind(scope)
+    .bind(subject)
+    .bind(failed_attempts as i32)
+    .bind(security.lockout_duration_secs as f64)
+    .execute(&state.db)
+    .await?;
+
+    // the count starts over once the lockout has run its course - the other scope's count is
+    // left alone, so an account lockout doesn't let an address off
+    clear_failed_logins(state, scope, subject).await?;
+
+    Ok(ActiveLockout {
+        scope: scope.to_string(),
+        retry_after_secs: security.lockout_duration_secs as i64,
+    })
+}
+
+/// Resets the failure count of one account or IP. The attempts are kept, they may still count
+/// towards the other scope.
+pub async fn clear_failed_logins(
+    state: &AppState,
+    scope: &str,
+    subject: &str,
+) -> Result<(), sqlx::Error> {
+    let query = match scope {
+        "ip" => {
+            "UPDATE failed_login_attempts SET counts_for_ip = FALSE WHERE ip_address = $1 AND counts_for_ip"
+        }
+        _ => {
+            "UPDATE failed_login_attempts SET counts_for_account = FALSE WHERE email = $1 AND counts_for_account"
+        }
+    };
+
+    sqlx::query(query)
+        .bind(subject)
+        .execute(&state.db)
+        .await
+        .map(|_| ())
+}
+
+/// Delay before answering a failed attempt: the base delay, doubled for every earlier failure.
+pub fn failed_login_delay(security: &SecuritySection, failures: i64) -> Duration {
+    if failures <= 0 {
+        return Duration::ZERO;
+    }
+
+    let doublings = (failures - 1).min(16) as u32;
+    let delay_ms = security
+        .failed_login_base_delay_ms
+        .saturating_mul(2u64.pow(doublings))
+        .min(security.failed_login_max_delay_ms);
+
+    Duration::from_millis(delay_ms)
+}
+
+pub fn too_many_attempts(lockout: &ActiveLockout) -> AppError {
+    AppError::TooManyRequests {
+        message: lockout.message(),
+        retry_after_secs: lockout.retry_after_secs.max(1) as u64,
+    }
+}
+
+/// Records the failure, holds the response back for the progressive delay and reports a lockout
+/// if this attempt triggered one.
+pub async fn reject_login(
+    state: &AppState,
+    email: &str,
+    ip_address: Option<&str>,
+    message: &str,
+) -> AppError {
+    let security = security_settings(state);
+
+    state.metrics.increment(LOGINS, &[("outcome", "failed")]);
+
+    match record_failed_login(state, &security, email, ip_address).await {
+        Ok((_, Some(lockout))) => {
+            error!("LOGIN LOCKOUT STARTED!");
+
+            return too_many_attempts(&lockout);
+        }
+        Ok((failures, None)) => {
+            tokio::time::sleep(failed_login_delay(&security, failures)).await;
+        }
+        Err(_) => {
+            error!("FAILED TO RECORD FAILED LOGIN!");
+        }
+    }
+
+    AppError::Unauthorized(message.to_string())
+}
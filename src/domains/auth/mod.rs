@@ -1,2 +1,6 @@
 pub mod controllers;
+pub mod email_verification;
+pub mod login_protection;
 pub mod router;
+pub mod sign_in;
+pub mod two_factor;
@@ -1,14 +1,59 @@
 use crate::AppState;
+use crate::domains::auth::controllers::confirm_two_factor::confirm_two_factor;
+use crate::domains::auth::controllers::disable_two_factor::disable_two_factor;
+use crate::domains::auth::controllers::enroll_two_factor::enroll_two_factor;
+use crate::domains::auth::controllers::get_user_sessions::get_user_sessions;
 use crate::domains::auth::controllers::login_user::login_user;
 use crate::domains::auth::controllers::logout_user::logout_user;
+use crate::domains::auth::controllers::refresh_session::refresh_session;
+use crate::domains::auth::controllers::regenerate_recovery_codes::regenerate_recovery_codes;
 use crate::domains::auth::controllers::register_user::register_user;
+use crate::domains::auth::controllers::request_password_reset::request_password_reset;
+use crate::domains::auth::controllers::resend_verification_email::resend_verification_email;
+use crate::domains::auth::controllers::reset_password::reset_password;
+use crate::domains::auth::controllers::revoke_other_sessions::revoke_other_sessions;
+use crate::domains::auth::controllers::revoke_user_session::revoke_user_session;
+use crate::domains::auth::controllers::verify_email::verify_email;
+use crate::domains::auth::controllers::verify_two_factor::verify_two_factor;
+use crate::middlewares::middleware_stack::{with_auth_layers, with_rate_limit};
+use axum::routing::{get, patch};
 use axum::{Router, routing::post};
 use tower_cookies::CookieManagerLayer;
 
-pub fn auth_routes(_state: &AppState) -> Router<AppState> {
-    Router::new()
+pub fn auth_routes(state: &AppState) -> Router<AppState> {
+    let protected_routes = Router::new()
+        .route("/get-user-sessions", get(get_user_sessions))
+        .route("/revoke-session/{session_id}", patch(revoke_user_session))
+        .route("/revoke-other-sessions", patch(revoke_other_sessions))
+        .route("/enroll-two-factor", post(enroll_two_factor))
+        .route("/confirm-two-factor", post(confirm_two_factor))
+        // codes are guessable, so everything that checks one shares the login limit
+        .route(
+            "/disable-two-factor",
+            with_rate_limit(post(disable_two_factor), state, "login"),
+        )
+        .route(
+            "/regenerate-recovery-codes",
+            with_rate_limit(post(regenerate_recovery_codes), state, "login"),
+        );
+
+    with_auth_layers(protected_routes, state)
+        // login, registration, logout, refresh, password resets and email verification happen without a usable session, so these
+        // routes are added after the auth layers
         .route("/register", post(register_user))
-        .route("/login", post(login_user))
+        .route("/login", with_rate_limit(post(login_user), state, "login"))
+        .route(
+            "/verify-two-factor",
+            with_rate_limit(post(verify_two_factor), state, "login"),
+        )
         .route("/logout", post(logout_user))
+        .route("/refresh", post(refresh_session))
+        .route("/forgot-password", post(request_password_reset))
+        .route("/reset-password", post(reset_password))
+        .route("/verify-email", post(verify_email))
+        .route(
+            "/resend-verification-email",
+            post(resend_verification_email),
+        )
         .layer(CookieManagerLayer::new())
 }
@@ -0,0 +1,64 @@
+use crate::AppState;
+use crate::domains::auth::login_protection::clear_failed_logins;
+use crate::utils::cookie_deploy_handler::deploy_auth_cookie;
+use crate::utils::generate_tokens::{User, generate_session_tokens};
+use crate::utils::app_error::{AppError, ErrorContext};
+use crate::utils::session_manager::{ClientMeta, create_session};
+use tower_cookies::Cookies;
+use tracing::error;
+
+pub struct SignedIn {
+    pub session_id: i64,
+    pub access_token: Option<String>,
+    pub refresh_token: Option<String>,
+}
+
+/// Opens a new session for a user whose credentials were already checked, issues its tokens and
+/// sets the auth cookie. Every sign-in gets its own session, so other devices stay logged in.
+pub async fn sign_in(
+    state: &AppState,
+    cookies: Cookies,
+    user: User,
+    meta: &ClientMeta,
+) -> Result<SignedIn, AppError> {
+    // only a finished sign-in resets the account's failures - a right password alone doesn't
+    // while a second factor is still owed, or guessing codes would never lock the account
+    if clear_failed_logins(state, "account", &user.email)
+        .await
+        .is_err()
+    {
+        error!("FAILED TO CLEAR FAILED LOGINS!");
+    }
+
+    let session = create_session(state, user.id, meta)
+        .await
+        .context("Failed to create session")?;
+
+    let user_id = user.id;
+
+    let tokens = generate_session_tokens(&state.config, user, session.id, &session.refresh_jti)
+        .await
+        .map_err(|e| AppError::internal("Failed to generate tokens", e))?;
+
+    deploy_auth_cookie(cookies, tokens.auth_cookie.unwrap()).await;
+
+    // the single token columns are still kept up to date for clients on the legacy flow
+    if let Err(e) = state
+        .repos
+        .users
+        .store_legacy_tokens(
+            user_id,
+            tokens.access_token.as_deref(),
+            tokens.refresh_token.as_deref(),
+        )
+        .await
+    {
+        error!("FAILED TO UPDATE TOKENS: {}", e);
+    }
+
+    Ok(SignedIn {
+        session_id: session.id,
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+    })
+}
@@ -0,0 +1,238 @@
+use crate::AppState;
+use crate::utils::hashing_handler::hashing_handler;
+use crate::utils::verification_handler::verification_handler;
+use anyhow::{Result, anyhow};
+use rand::Rng;
+use std::time::{SystemTime, UNIX_EPOCH};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const TOTP_ISSUER: &str = "RustyChat";
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP_SECS: u64 = 30;
+// codes from one step either side of now are accepted to allow for clock drift
+const TOTP_SKEW_STEPS: u64 = 1;
+
+// codes tried against one challenge before the user has to go through the password step again
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+pub fn new_totp_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+pub fn build_totp(secret: &str, email: &str) -> Result<TOTP> {
+    let secret_bytes = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| anyhow!("Invalid TOTP secret: {}", e))?;
+
+    TOTP::new(
+        Algorithm::SHA1,
+        TOTP_DIGITS,
+        TOTP_SKEW_STEPS as u8,
+        TOTP_STEP_SECS,
+        secret_bytes,
+        Some(TOTP_ISSUER.to_string()),
+        email.to_string(),
+    )
+    .map_err(|e| anyhow!("Failed to build TOTP: {}", e))
+}
+
+/// Returns the time step the code belongs to, if it matches any step inside the allowed skew.
+/// Callers store the step so the same code can't be replayed.
+pub fn matching_totp_step(totp: &TOTP, code: &str) -> Option<i64> {
+    let code = code.trim();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let current_step = now / TOTP_STEP_SECS;
+
+    (current_step.saturating_sub(TOTP_SKEW_STEPS)..=current_step + TOTP_SKEW_STEPS)
+        .find(|step| totp.generate(step * TOTP_STEP_SECS) == code)
+        .map(|step| step as i64)
+}
+
+/// Accepts a TOTP code for an enrolled user, at most once per time step.
+pub async fn redeem_totp_code(
+    state: &AppState,
+    user_id: i64,
+    totp: &TOTP,
+    code: &str,
+) -> Result<bool, sqlx::Error> {
+    let Some(step) = matching_totp_step(totp, code) else {
+        return Ok(false);
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE users
+        SET totp_last_used_step = $1
+        WHERE id = $2 AND (totp_last_used_step IS NULL OR totp_last_used_step < $1)
+        "#,
+    )
+    .bind(step)
+    .bind(user_id)
+    .execute(&state.db)
+    .await
+    .map(|res| res.rows_affected() == 1)
+}
+
+fn new_recovery_code() -> String {
+    let mut rng = rand::thread_rng();
+    let mut part = || -> String {
+        (0..5)
+            .map(|_| RECOVERY_CODE_ALPHABET[rng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+            .collect()
+    };
+
+    format!("{}-{}", part(), part())
+}
+
+/// Replaces the user's recovery codes with a fresh set. Only hashes are stored, the plain codes
+/// are returned so they can be shown to the user once.
+pub async fn replace_recovery_codes(state: &AppState, user_id: i64) -> Result<Vec<String>> {
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| new_recovery_code())
+        .collect();
+
+    let mut hashes = Vec::with_capacity(codes.len());
+    for code in &codes {
+        hashes.push(
+            hashing_handler(code)
+                .await
+                .map_err(|e| anyhow!("Failed to hash recovery code: {}", e))?,
+        );
+    }
+
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO totp_recovery_codes (user_id, code_hash)
+        SELECT $1, UNNEST($2::VARCHAR[])
+        "#,
+    )
+    .bind(user_id)
+    .bind(&hashes)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(codes)
+}
+
+/// Checks the code against the user's unused recovery codes and burns the one that matches.
+pub async fn redeem_recovery_code(state: &AppState, user_id: i64, code: &str) -> Result<bool> {
+    let candidates = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, code_hash FROM totp_recovery_codes WHERE user_id = $1 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let code = code.trim().to_lowercase();
+
+    for (id, code_hash) in candidates {
+        if !verification_handler(&code, &code_hash)
+            .await
+            .unwrap_or(false)
+        {
+            continue;
+        }
+
+        // the used_at guard makes two concurrent redemptions of the same code fail for one of them
+        let burned = sqlx::query(
+            "UPDATE totp_recovery_codes SET used_at = NOW() WHERE id = $1 AND used_at IS NULL",
+        )
+        .bind(id)
+        .execute(&state.db)
+        .await?;
+
+        return Ok(burned.rows_affected() == 1);
+    }
+
+    Ok(false)
+}
+
+/// Checks a TOTP code, or a recovery code when no TOTP code was given, and burns whichever
+/// matched.
+pub async fn redeem_second_factor(
+    state: &AppState,
+    user_id: i64,
+    email: &str,
+    totp_secret: &str,
+    code: Option<&str>,
+    recovery_code: Option<&str>,
+) -> Result<bool> {
+    match (code, recovery_code) {
+        (Some(code), _) => {
+            let totp = build_totp(totp_secret, email)?;
+
+            Ok(redeem_totp_code(state, user_id, &totp, code).await?)
+        }
+        (None, Some(recovery_code)) => redeem_recovery_code(state, user_id, recovery_code).await,
+        (None, None) => Ok(false),
+    }
+}
+
+/// Opens a challenge for a user who got past the password step. The id goes into the challenge
+/// token as its jti.
+pub async fn create_challenge(state: &AppState, user_id: i64) -> Result<String, sqlx::Error> {
+    let challenge_id = uuid::Uuid::new_v4().simple().to_string();
+
+    sqlx::query(
+        r#"
+        INSERT INTO two_factor_challenges (id, user_id, expires_at)
+        VALUES ($1, $2, NOW() + make_interval(secs => $3))
+        "#,
+    )
+    .bind(&challenge_id)
+    .bind(user_id)
+    .bind(state.config.token_ttls().two_factor_challenge_secs as f64)
+    .execute(&state.db)
+    .await?;
+
+    Ok(challenge_id)
+}
+
+/// Counts an attempt against the challenge before the code is checked. `false` once the challenge
+/// is expired, already answered or out of attempts.
+pub async fn take_challenge_attempt(
+    state: &AppState,
+    challenge_id: &str,
+    user_id: i64,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE two_factor_challenges
+        SET attempts = attempts + 1
+        WHERE id = $1
+            AND user_id = $2
+            AND consumed_at IS NULL
+            AND expires_at > NOW()
+            AND attempts < $3
+        "#,
+    )
+    .bind(challenge_id)
+    .bind(user_id)
+    .bind(MAX_CHALLENGE_ATTEMPTS)
+    .execute(&state.db)
+    .await
+    .map(|res| res.rows_affected() == 1)
+}
+
+/// Burns an answered challenge so its token can't be exchanged a second time.
+pub async fn consume_challenge(state: &AppState, challenge_id: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query(
+        "UPDATE two_factor_challenges SET consumed_at = NOW() WHERE id = $1 AND consumed_at IS NULL",
+    )
+    .bind(challenge_id)
+    .execute(&state.db)
+    .await
+    .map(|res| res.rows_affected() == 1)
+}
@@ -0,0 +1,174 @@
+use crate::AppState;
+use crate::utils::current_time_in_milliseconds::current_time_millis;
+use crate::utils::realtime_hub::RealtimeEventType;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::time::Duration;
+use tracing::error;
+
+/// How long a call may ring before it is recorded as missed.
+pub const RING_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// How often the sweeper looks for calls that rang out.
+const RING_SWEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CallLog {
+    pub id: i64,
+    pub room_id: Option<i64>,
+    pub caller_id: Option<i64>,
+    pub callee_id: Option<i64>,
+    pub call_type: String,
+    pub status: String,
+    pub started_at: String,
+    pub answered_at: Option<String>,
+    pub ended_at: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CallMessage {
+    pub id: i64,
+    pub room_id: i64,
+    pub sender_id: Option<i64>,
+    #[sqlx(rename = "type")]
+    pub message_type: String,
+    pub text_content: Option<String>,
+    pub status: String,
+    pub sent_at: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+pub async fn is_room_member(
+    state: &AppState,
+    room_id: i64,
+    user_id: i64,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>("SELECT id FROM room_members WHERE room_id = $1 AND user_id = $2")
+        .bind(room_id)
+        .bind(user_id)
+        .fetch_optional(&state.db)
+        .await
+        .map(|member| member.is_some())
+}
+
+pub fn call_label(call_type: &str) -> &'static str {
+    match call_type {
+        "video_call" => "Video call",
+        _ => "Voice call",
+    }
+}
+
+/// Human readable duration between two millisecond timestamps, e.g. "2m 13s".
+pub fn format_call_duration(from_millis: &str, to_millis: &str) -> Option<String> {
+    let from = from_millis.parse::<u128>().ok()?;
+    let to = to_millis.parse::<u128>().ok()?;
+    let total_secs = to.saturating_sub(from) / 1000;
+
+    Some(match (total_secs / 3600, (total_secs % 3600) / 60, total_secs % 60) {
+        (0, 0, secs) => format!("{}s", secs),
+        (0, mins, secs) => format!("{}m {}s", mins, secs),
+        (hours, mins, secs) => format!("{}h {}m {}s", hours, mins, secs),
+    })
+}
+
+/// Records a call transition as a system message in the call's room and pushes both the message
+/// and the updated call log to connected room members. Failures are logged only - the call log
+/// stays the source of truth for the call itself.
+pub async fn emit_call_event(
+    state: &AppState,
+    call: &CallLog,
+    sender_id: Option<i64>,
+    text: String,
+) {
+    let Some(room_id) = call.room_id else {
+        return;
+    };
+
+    let sent_at = current_time_millis();
+
+    let message_res = sqlx::query_as::<_, CallMessage>(
+        r#"
+        INSERT INTO messages (room_id, sender_id, type, text_content, status, sent_at)
+        VALUES ($1, $2, $3, $4, 'sent', $5)
+        RETURNING id, room_id, sender_id, type, text_content, status, sent_at, created_at, updated_at
+        "#,
+    )
+    .bind(room_id)
+    .bind(sender_id)
+    .bind(&call.call_type)
+    .bind(text)
+    .bind(sent_at.to_string())
+    .fetch_one(&state.db)
+    .await;
+
+    match message_res {
+        Ok(message) => {
+            state
+                .hub
+                .publish(room_id, RealtimeEventType::MessageCreated, &message);
+        }
+        Err(e) => {
+            error!("FAILED TO CREATE CALL SYSTEM MESSAGE: {}", e);
+        }
+    }
+
+    state
+        .hub
+        .publish(room_id, RealtimeEventType::CallUpdated, call);
+}
+
+/// Ends the calls that rang for longer than [`RING_TIMEOUT`] without being answered, limited to
+/// one room when `room_id` is given, and announces each of them as missed.
+pub async fn miss_unanswered_calls(
+    state: &AppState,
+    room_id: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    let now = current_time_millis();
+    let rang_out_before = now.saturating_sub(RING_TIMEOUT.as_millis());
+
+    let calls = sqlx::query_as::<_, CallLog>(
+        r#"
+        UPDATE call_logs
+        SET status = 'missed', ended_at = $1, updated_at = NOW()
+        WHERE ended_at IS NULL
+            AND answered_at IS NULL
+            AND started_at::BIGINT < $2
+            AND ($3::BIGINT IS NULL OR room_id = $3)
+        RETURNING *
+        "#,
+    )
+    .bind(now.to_string())
+    .bind(rang_out_before as i64)
+    .bind(room_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    for call in calls {
+        let text = format!("Missed {}", call_label(&call.call_type).to_lowercase());
+        emit_call_event(state, &call, call.caller_id, text).await;
+    }
+
+    Ok(())
+}
+
+/// Periodically records calls nobody picked up as missed, so a ringing call never outlives
+/// [`RING_TIMEOUT`].
+pub fn spawn_ring_timeout_sweeper(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RING_SWEEP_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => (),
+                _ = state.shutdown.triggered() => break,
+            }
+
+            if let Err(e) = miss_unanswered_calls(&state, None).await {
+                error!("FAILED TO MISS UNANSWERED CALLS: {}", e);
+            }
+        }
+    });
+}
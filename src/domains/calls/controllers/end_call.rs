@@ -0,0 +1,75 @@
+use crate::AppState;
+use crate::domains::calls::call_events::{
+    CallLog, call_label, emit_call_event, format_call_duration, is_room_member,
+};
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use crate::utils::current_time_in_milliseconds::current_time_millis;
+use axum::extract::{Extension, Path, State};
+
+pub async fn end_call(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Path(call_id): Path<i64>,
+) -> ApiResult<CallLog> {
+    let user_id = session.user.id;
+
+    // 1. Fetch call
+    let call = sqlx::query_as::<_, CallLog>("SELECT * FROM call_logs WHERE id = $1")
+        .bind(call_id)
+        .fetch_optional(&state.db)
+        .await
+        .context("Failed to fetch call")?
+        .ok_or_else(|| AppError::NotFound("Call not found or does not exist".to_string()))?;
+
+    // 2. Any member of the call's room can hang up
+    let is_member = match call.room_id {
+        Some(room_id) => is_room_member(&state, room_id, user_id)
+            .await
+            .context("Failed to verify room membership")?,
+        None => false,
+    };
+
+    if !is_member {
+        return Err(AppError::Forbidden(
+            "You are not allowed to end this call".to_string(),
+        ));
+    }
+
+    // 3. End the call - calls that were never answered are recorded as missed
+    let ended_at = current_time_millis().to_string();
+
+    let call = sqlx::query_as::<_, CallLog>(
+        r#"
+        UPDATE call_logs
+        SET
+            status = CASE WHEN answered_at IS NULL THEN 'missed' ELSE 'completed' END,
+            ended_at = $1,
+            updated_at = NOW()
+        WHERE id = $2 AND ended_at IS NULL
+        RETURNING *
+        "#,
+    )
+    .bind(&ended_at)
+    .bind(call_id)
+    .fetch_optional(&state.db)
+    .await
+    .context("Failed to end call")?
+    .ok_or_else(|| AppError::Conflict("Call has already ended".to_string()))?;
+
+    let label = call_label(&call.call_type);
+
+    let text = match call
+        .answered_at
+        .as_deref()
+        .and_then(|answered_at| format_call_duration(answered_at, &ended_at))
+    {
+        Some(duration) => format!("{} ended ({})", label, duration),
+        None => format!("Missed {}", label.to_lowercase()),
+    };
+
+    emit_call_event(&state, &call, Some(user_id), text).await;
+
+    Ok(ApiResponse::ok("Call ended successfully", call))
+}
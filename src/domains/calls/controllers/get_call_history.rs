@@ -0,0 +1,113 @@
+use crate::AppState;
+use crate::domains::calls::call_events::CallLog;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::{Extension, Query, State};
+use serde::{Deserialize, Serialize};
+
+// default and maximum page sizes for call history listings
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct GetCallHistoryParams {
+    pub status: Option<String>,    // "missed", "completed" or "rejected"
+    pub direction: Option<String>, // "incoming" or "outgoing"
+    pub call_type: Option<String>, // "voice_call" or "video_call"
+    pub room_id: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    count: usize,
+    calls: Option<Vec<CallLog>>,
+}
+
+pub async fn get_call_history(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Query(params): Query<GetCallHistoryParams>,
+) -> ApiResult<ResponseCore> {
+    let user_id = session.user.id;
+
+    if let Some(status) = params.status.as_deref()
+        && !matches!(status, "missed" | "completed" | "rejected")
+    {
+        return Err(AppError::BadRequest(
+            "Status must be 'missed', 'completed' or 'rejected'".to_string(),
+        ));
+    }
+
+    if let Some(direction) = params.direction.as_deref()
+        && !matches!(direction, "incoming" | "outgoing")
+    {
+        return Err(AppError::BadRequest(
+            "Direction must be 'incoming' or 'outgoing'".to_string(),
+        ));
+    }
+
+    if let Some(call_type) = params.call_type.as_deref()
+        && !matches!(call_type, "voice_call" | "video_call")
+    {
+        return Err(AppError::BadRequest(
+            "Call type must be 'voice_call' or 'video_call'".to_string(),
+        ));
+    }
+
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .clamp(1, MAX_PAGE_LIMIT);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    // A call belongs to a user's history when they placed it, were rung directly, or it was a
+    // group call in one of their rooms. Missed calls are `status=missed&direction=incoming`.
+    let calls = sqlx::query_as::<_, CallLog>(
+        r#"
+        SELECT c.*
+        FROM call_logs c
+        WHERE (
+            c.caller_id = $1
+            OR c.callee_id = $1
+            OR (
+                c.callee_id IS NULL
+                AND EXISTS (
+                    SELECT 1 FROM room_members rm
+                    WHERE rm.room_id = c.room_id AND rm.user_id = $1
+                )
+            )
+        )
+        AND ($2::TEXT IS NULL OR c.status = $2)
+        AND (
+            $3::TEXT IS NULL
+            OR ($3 = 'outgoing' AND c.caller_id = $1)
+            OR ($3 = 'incoming' AND c.caller_id IS DISTINCT FROM $1)
+        )
+        AND ($4::TEXT IS NULL OR c.call_type = $4)
+        AND ($5::BIGINT IS NULL OR c.room_id = $5)
+        ORDER BY c.id DESC
+        LIMIT $6 OFFSET $7
+        "#,
+    )
+    .bind(user_id)
+    .bind(params.status.as_deref())
+    .bind(params.direction.as_deref())
+    .bind(params.call_type.as_deref())
+    .bind(params.room_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await
+    .context("Failed to fetch call history")?;
+
+    Ok(ApiResponse::ok(
+        "Call history fetched successfully",
+        ResponseCore {
+            count: calls.len(),
+            calls: Some(calls),
+        },
+    ))
+}
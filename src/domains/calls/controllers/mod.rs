@@ -0,0 +1,5 @@
+pub mod answer_call;
+pub mod end_call;
+pub mod get_call_history;
+pub mod reject_call;
+pub mod start_call;
@@ -0,0 +1,74 @@
+use crate::AppState;
+use crate::domains::calls::call_events::{
+    CallLog, call_label, emit_call_event, is_room_member, miss_unanswered_calls,
+};
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use crate::utils::current_time_in_milliseconds::current_time_millis;
+use axum::extract::{Extension, Path, State};
+
+pub async fn reject_call(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Path(call_id): Path<i64>,
+) -> ApiResult<CallLog> {
+    let user_id = session.user.id;
+
+    // 1. Fetch call
+    let call = sqlx::query_as::<_, CallLog>("SELECT * FROM call_logs WHERE id = $1")
+        .bind(call_id)
+        .fetch_optional(&state.db)
+        .await
+        .context("Failed to fetch call")?
+        .ok_or_else(|| AppError::NotFound("Call not found or does not exist".to_string()))?;
+
+    // 2. Only the callee (or, for group calls, any other room member) can reject
+    let is_member = match call.room_id {
+        Some(room_id) => is_room_member(&state, room_id, user_id)
+            .await
+            .context("Failed to verify room membership")?,
+        None => false,
+    };
+
+    let is_authorized = is_member
+        && call.caller_id != Some(user_id)
+        && call.callee_id.is_none_or(|callee_id| callee_id == user_id);
+
+    if !is_authorized {
+        return Err(AppError::Forbidden(
+            "You are not allowed to reject this call".to_string(),
+        ));
+    }
+
+    // a call that rang out has been missed, it can no longer be rejected
+    miss_unanswered_calls(&state, call.room_id)
+        .await
+        .context("Failed to expire unanswered calls")?;
+
+    // 3. Mark as rejected - only ringing calls can be rejected, and rejecting ends the call
+    let call = sqlx::query_as::<_, CallLog>(
+        r#"
+        UPDATE call_logs
+        SET status = 'rejected', ended_at = $1, updated_at = NOW()
+        WHERE id = $2 AND answered_at IS NULL AND ended_at IS NULL
+        RETURNING *
+        "#,
+    )
+    .bind(current_time_millis().to_string())
+    .bind(call_id)
+    .fetch_optional(&state.db)
+    .await
+    .context("Failed to reject call")?
+    .ok_or_else(|| AppError::Conflict("Call has already been answered or ended".to_string()))?;
+
+    emit_call_event(
+        &state,
+        &call,
+        Some(user_id),
+        format!("{} rejected", call_label(&call.call_type)),
+    )
+    .await;
+
+    Ok(ApiResponse::ok("Call rejected successfully", call))
+}
@@ -0,0 +1,127 @@
+use crate::AppState;
+use crate::domains::calls::call_events::{
+    CallLog, call_label, emit_call_event, is_room_member, miss_unanswered_calls,
+};
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use crate::utils::current_time_in_milliseconds::current_time_millis;
+use axum::{
+    Json,
+    extract::{Extension, State},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct StartCallPayload {
+    pub room_id: i64,
+    pub call_type: String, // "voice_call" or "video_call"
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct RoomLookUp {
+    pub id: i64,
+    pub is_group: bool,
+}
+
+pub async fn start_call(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Json(payload): Json<StartCallPayload>,
+) -> ApiResult<CallLog> {
+    let caller_id = session.user.id;
+
+    if payload.call_type != "voice_call" && payload.call_type != "video_call" {
+        return Err(AppError::BadRequest(
+            "Call type must be 'voice_call' or 'video_call'".to_string(),
+        ));
+    }
+
+    // 1. Get room
+    let room = sqlx::query_as::<_, RoomLookUp>("SELECT id, is_group FROM rooms WHERE id = $1")
+        .bind(payload.room_id)
+        .fetch_optional(&state.db)
+        .await
+        .context("Failed to get room")?
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "Room with id: '{}' not found or does not exist",
+                payload.room_id
+            ))
+        })?;
+
+    // 2. Only room members can start calls in a room
+    if !is_room_member(&state, room.id, caller_id)
+        .await
+        .context("Failed to verify room membership")?
+    {
+        return Err(AppError::Forbidden(
+            "You are not a member of this room".to_string(),
+        ));
+    }
+
+    // 3. One active call per room at a time - one that rang out doesn't count
+    miss_unanswered_calls(&state, Some(room.id))
+        .await
+        .context("Failed to expire unanswered calls")?;
+
+    let active_call_id = sqlx::query_scalar::<_, i64>(
+        "SELECT id FROM call_logs WHERE room_id = $1 AND ended_at IS NULL LIMIT 1",
+    )
+    .bind(room.id)
+    .fetch_optional(&state.db)
+    .await
+    .context("Failed to check active calls")?;
+
+    if let Some(active_call_id) = active_call_id {
+        return Err(AppError::Conflict(format!(
+            "Room already has an active call with id: '{}'",
+            active_call_id
+        )));
+    }
+
+    // 4. Private rooms have a single callee, group calls ring every member
+    let callee_id = if room.is_group {
+        None
+    } else {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT user_id FROM room_members WHERE room_id = $1 AND user_id <> $2 LIMIT 1",
+        )
+        .bind(room.id)
+        .bind(caller_id)
+        .fetch_optional(&state.db)
+        .await
+        .context("Failed to get callee")?
+    };
+
+    // 5. Create the call log - it stays 'missed' until somebody answers
+    let call = sqlx::query_as::<_, CallLog>(
+        r#"
+        INSERT INTO call_logs (room_id, caller_id, callee_id, call_type, status, started_at)
+        VALUES ($1, $2, $3, $4, 'missed', $5)
+        RETURNING *
+        "#,
+    )
+    .bind(room.id)
+    .bind(caller_id)
+    .bind(callee_id)
+    .bind(&payload.call_type)
+    .bind(current_time_millis().to_string())
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| match AppError::from(e) {
+        // another call was started in the room since the check above
+        AppError::Conflict(_) => AppError::Conflict("Room already has an active call".to_string()),
+        e => e.with_message("Failed to start call"),
+    })?;
+
+    emit_call_event(
+        &state,
+        &call,
+        Some(caller_id),
+        format!("{} started", call_label(&call.call_type)),
+    )
+    .await;
+
+    Ok(ApiResponse::created("Call started successfully", call))
+}
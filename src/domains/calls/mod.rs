@@ -0,0 +1,3 @@
+pub mod call_events;
+mod controllers;
+pub mod router;
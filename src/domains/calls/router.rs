@@ -0,0 +1,24 @@
+use crate::AppState;
+use crate::domains::calls::controllers::answer_call::answer_call;
+use crate::domains::calls::controllers::end_call::end_call;
+use crate::domains::calls::controllers::get_call_history::get_call_history;
+use crate::domains::calls::controllers::reject_call::reject_call;
+use crate::domains::calls::controllers::start_call::start_call;
+use crate::middlewares::middleware_stack::{with_auth_layers, with_verified_email};
+use axum::Router;
+use axum::routing::{get, patch, post};
+use tower_cookies::CookieManagerLayer;
+
+pub fn calls_routes(state: &AppState) -> Router<AppState> {
+    let protected_routes = Router::new()
+        .route("/start-call", with_verified_email(post(start_call), state))
+        .route(
+            "/answer-call/{call_id}",
+            with_verified_email(patch(answer_call), state),
+        )
+        .route("/reject-call/{call_id}", patch(reject_call))
+        .route("/end-call/{call_id}", patch(end_call))
+        .route("/get-call-history", get(get_call_history));
+
+    with_auth_layers(protected_routes, state).layer(CookieManagerLayer::new())
+}
@@ -0,0 +1,12 @@
+use crate::utils::api_response::ApiResponse;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    status: &'static str,
+}
+
+/// Answers as long as the process can serve requests - no dependencies are touched.
+pub async fn get_liveness() -> ApiResponse<ResponseCore> {
+    ApiResponse::ok("Server is alive", ResponseCore { status: "alive" })
+}
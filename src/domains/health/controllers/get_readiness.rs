@@ -0,0 +1,128 @@
+use crate::AppState;
+use crate::utils::api_response::ApiResponse;
+use axum::{extract::State, http::StatusCode};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::error;
+
+// a dependency that doesn't answer within this is reported as down
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize)]
+pub struct DependencyCheck {
+    name: &'static str,
+    status: &'static str, // "up" or "unavailable"
+    latency_ms: u128,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    status: &'static str, // "ready" or "not_ready"
+    checks: Vec<DependencyCheck>,
+}
+
+async fn run_check<F>(name: &'static str, check: F) -> DependencyCheck
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let started = Instant::now();
+
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(format!(
+            "No answer within {} seconds",
+            CHECK_TIMEOUT.as_secs()
+        )),
+    };
+
+    // the endpoint is unauthenticated, so what went wrong only goes to the logs
+    if let Err(detail) = &result {
+        error!("READINESS CHECK FAILED FOR {}: {}!", name, detail);
+    }
+
+    DependencyCheck {
+        name,
+        status: if result.is_ok() { "up" } else { "unavailable" },
+        latency_ms: started.elapsed().as_millis(),
+    }
+}
+
+async fn check_postgres(state: &AppState) -> Result<(), String> {
+    sqlx::query_scalar::<_, i32>("SELECT 1")
+        .fetch_one(&state.db)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+async fn check_storage(state: &AppState) -> Result<(), String> {
+    state.storage.check().await.map_err(|e| format!("{:#}", e))
+}
+
+/// Every migration this build ships with must have been applied successfully by `sqlx migrate run`.
+async fn check_migrations(state: &AppState) -> Result<(), String> {
+    let applied: HashSet<i64> =
+        sqlx::query_scalar::<_, i64>("SELECT version FROM _sqlx_migrations WHERE success = TRUE")
+            .fetch_all(&state.db)
+            .await
+            .map_err(|e| format!("Failed to read applied migrations: {}", e))?
+            .into_iter()
+            .collect();
+
+    let pending: Vec<String> = sqlx::migrate!("./migrations")
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .map(|migration| migration.version.to_string())
+        .collect();
+
+    if pending.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Pending migrations: {}", pending.join(", ")))
+    }
+}
+
+pub async fn get_readiness(State(state): State<AppState>) -> ApiResponse<ResponseCore> {
+    // a draining instance should be taken out of rotation even though its dependencies are fine
+    if state.shutdown.is_triggered() {
+        return ApiResponse::ok(
+            "Server is shutting down",
+            ResponseCore {
+                status: "not_ready",
+                checks: Vec::new(),
+            },
+        )
+        .with_status(StatusCode::SERVICE_UNAVAILABLE)
+        .with_error("Service Unavailable");
+    }
+
+    let (postgres, storage, migrations) = tokio::join!(
+        run_check("postgres", check_postgres(&state)),
+        run_check(state.storage.backend(), check_storage(&state)),
+        run_check("migrations", check_migrations(&state)),
+    );
+
+    let checks = vec![postgres, storage, migrations];
+
+    if checks.iter().all(|check| check.status == "up") {
+        return ApiResponse::ok(
+            "Server is ready",
+            ResponseCore {
+                status: "ready",
+                checks,
+            },
+        );
+    }
+
+    ApiResponse::ok(
+        "Server is not ready",
+        ResponseCore {
+            status: "not_ready",
+            checks,
+        },
+    )
+    .with_status(StatusCode::SERVICE_UNAVAILABLE)
+    .with_error("One or more dependencies are unavailable")
+}
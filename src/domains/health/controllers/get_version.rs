@@ -0,0 +1,33 @@
+use crate::AppState;
+use crate::utils::api_response::ApiResponse;
+use axum::extract::State;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    app_name: String,
+    environment: Option<String>,
+    version: &'static str,
+    // set by the build pipeline through the GIT_COMMIT_SHA and BUILD_TIMESTAMP env variables
+    git_commit: Option<&'static str>,
+    built_at: Option<&'static str>,
+    build_profile: &'static str,
+}
+
+pub async fn get_version(State(state): State<AppState>) -> ApiResponse<ResponseCore> {
+    ApiResponse::ok(
+        "Version fetched successfully",
+        ResponseCore {
+            app_name: state.config.app.name.clone(),
+            environment: state.config.app.environment.clone(),
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: option_env!("GIT_COMMIT_SHA"),
+            built_at: option_env!("BUILD_TIMESTAMP"),
+            build_profile: if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            },
+        },
+    )
+}
@@ -0,0 +1,4 @@
+pub mod get_liveness;
+pub mod get_metrics;
+pub mod get_readiness;
+pub mod get_version;
@@ -0,0 +1,2 @@
+mod controllers;
+pub mod router;
@@ -0,0 +1,21 @@
+use crate::AppState;
+use crate::domains::health::controllers::get_liveness::get_liveness;
+use crate::domains::health::controllers::get_metrics::get_metrics;
+use crate::domains::health::controllers::get_readiness::get_readiness;
+use crate::domains::health::controllers::get_version::get_version;
+use axum::{Router, routing::get};
+
+// probes and scrapes are public and sit outside the global layers, so they don't count against
+// rate limits, fill the request log or show up in the request metrics
+pub fn health_routes(state: &AppState) -> Router<AppState> {
+    let router = Router::new()
+        .route("/healthz", get(get_liveness))
+        .route("/readyz", get(get_readiness))
+        .route("/version", get(get_version));
+
+    if state.config.observability.enable_metrics {
+        return router.route("/metrics", get(get_metrics));
+    }
+
+    router
+}
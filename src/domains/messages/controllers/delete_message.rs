@@ -1,124 +1,108 @@
 use crate::AppState;
+use crate::domains::messages::service;
 use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
-use axum::{
-    extract::{Path, State, Extension},
-    http::StatusCode,
-    response::IntoResponse,
-    Json,
-};
-use chrono::NaiveDateTime;
-use serde::{Deserialize, Serialize};
+use crate::models::message::{Message, MessageStatus};
+use crate::models::receipt::{NewReceipt, ReceiptAction};
+use crate::utils::realtime_hub::RealtimeEventType;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use axum::extract::{Path, Query, State, Extension};
+use serde::Deserialize;
 use tracing::error;
 
-#[derive(Debug, Deserialize)]
-pub struct DeleteMessagePayload {
-    pub sender_id: i64,
-}
-
-#[derive(Debug, Serialize, sqlx::FromRow)]
-pub struct Message {
-    pub id: i64,
-    pub room_id: i64,
-    pub sender_id: Option<i64>,
-    #[sqlx(rename = "type")]
-    pub message_type: String,
-    pub text_content: Option<String>,
-    pub attachment_1: Option<String>,
-    pub attachment_2: Option<String>,
-    pub attachment_3: Option<String>,
-    pub attachment_4: Option<String>,
-    pub status: String,
-    pub sent_at: String,
-    pub created_at: NaiveDateTime,
-    pub updated_at: NaiveDateTime,
+#[derive(Debug, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteScope {
+    Me,
+    #[default]
+    Everyone,
 }
 
-#[derive(Debug, Serialize)]
-pub struct DeleteMessageResponse {
-    pub response_message: String,
-    pub response: Option<Message>,
-    pub error: Option<String>,
+#[derive(Debug, Deserialize)]
+pub struct DeleteMessageParams {
+    #[serde(default)]
+    pub scope: DeleteScope,
 }
 
 pub async fn delete_message(
     State(state): State<AppState>,
     Extension(session): Extension<SessionsMiddlewareOutput>,
-    Path((message_id, sender_id)): Path<(i64, i64)>,
-) -> impl IntoResponse {
-    // 1. Fetch message to check ownership and get room_id
-    let message_result = sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE id = $1")
-        .bind(message_id)
-        .fetch_optional(&state.db)
-        .await;
-
-    let message = match message_result {
-        Ok(Some(m)) => m,
-        Ok(None) => {
-            error!("MESSAGE NOT FOUND!");
-            
-            return (
-                StatusCode::NOT_FOUND,
-                Json(DeleteMessageResponse {
-                    response_message: "Message not found or does not exist".to_string(),
-                    response: None,
-                    error: Some("Message not found".to_string()),
-                }),
-            );
-        }
-        Err(e) => {
-            error!("DATABASE ERROR!");
-            
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(DeleteMessageResponse {
-                    response_message: "Database error".to_string(),
-                    response: None,
-                    error: Some(e.to_string()),
-                }),
-            );
+    Path(message_id): Path<i64>,
+    Query(params): Query<DeleteMessageParams>,
+) -> ApiResult<Message> {
+    let user_id = session.user.id;
+
+    match params.scope {
+        DeleteScope::Me => delete_for_me(&state, message_id, user_id).await,
+        DeleteScope::Everyone => {
+            delete_for_everyone(&state, message_id, user_id, session.user.is_admin).await
         }
+    }
+}
+
+async fn delete_for_me(state: &AppState, message_id: i64, user_id: i64) -> ApiResult<Message> {
+    let message = service::delete_for_me(&state.repos, message_id, user_id).await?;
+
+    // "delete" receipt addressed to the user themselves, so their other devices follow along
+    let receipt = NewReceipt {
+        message_id: message.id,
+        room_id: message.room_id,
+        sender_id: user_id,
+        action: ReceiptAction::Delete,
+        status: MessageStatus::Deleted,
+        updates_count_tracker: message.updates_counter,
     };
+    let receipt_res = state.repos.receipts.create(&receipt, user_id).await;
+
+    if receipt_res.is_err() {
+        error!("MESSAGE DELETED FOR USER, BUT FAILED TO CREATE MESSAGE STATUS RECEIPT!");
 
-    // 2. Check permissions (sender or admin)
-    if message.sender_id != Some(sender_id) && !session.user.is_admin {
-        error!("UNAUTHORIZED MESSAGE DELETE ATTEMPT!");
-        
-        return (
-            StatusCode::FORBIDDEN,
-            Json(DeleteMessageResponse {
-                response_message: "You don't have permission to delete this message".to_string(),
-                response: None,
-                error: Some("Forbidden".to_string()),
-            }),
-        );
+        return Ok(ApiResponse::ok(
+            "Message deleted for you but failed to create message status receipt",
+            message,
+        ));
     }
 
-    // 4. Delete message(status receipts are automatically deleted on message delivery).
-    let delete_res = sqlx::query("DELETE FROM messages WHERE id = $1")
-        .bind(message_id)
-        .execute(&state.db)
-        .await;
-
-    match delete_res {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(DeleteMessageResponse {
-                response_message: "Message deleted successfully".to_string(),
-                response: Some(message),
-                error: None,
-            }),
-        ),
-        Err(e) => {
-            error!("FAILED_TO_DELETE_MESSAGE!");
-            
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(DeleteMessageResponse {
-                    response_message: "Failed to delete message".to_string(),
-                    response: None,
-                    error: Some(e.to_string()),
-                }),
-            )
-        }
+    Ok(ApiResponse::ok("Message deleted for you successfully", message))
+}
+
+async fn delete_for_everyone(
+    state: &AppState,
+    message_id: i64,
+    sender_id: i64,
+    is_admin: bool,
+) -> ApiResult<Message> {
+    let window_secs = state
+        .config
+        .messages
+        .as_ref()
+        .map(|messages| messages.delete_for_everyone_window_secs)
+        .unwrap_or(service::DEFAULT_DELETE_FOR_EVERYONE_WINDOW_SECS);
+
+    let (room, deleted_message) =
+        service::delete_for_everyone(&state.repos, message_id, sender_id, is_admin, window_secs)
+            .await?;
+
+    state.hub.publish(deleted_message.room_id, RealtimeEventType::MessageDeleted, &deleted_message);
+
+    // "delete" receipts for every other room member
+    let receipt = NewReceipt {
+        message_id: deleted_message.id,
+        room_id: room.id,
+        sender_id,
+        action: ReceiptAction::Delete,
+        status: MessageStatus::Deleted,
+        updates_count_tracker: deleted_message.updates_counter,
+    };
+    let receipt_res = service::send_receipts(&state.repos, &room, &receipt).await;
+
+    if receipt_res.is_err() {
+        error!("MESSAGE DELETED SUCCESSFULLY, BUT FAILED TO CREATE MESSAGE STATUS RECEIPT!");
+
+        return Ok(ApiResponse::ok(
+            "Message deleted successfully but failed to create message status receipt",
+            deleted_message,
+        ));
     }
+
+    Ok(ApiResponse::ok("Message deleted successfully", deleted_message))
 }
@@ -0,0 +1,105 @@
+use crate::AppState;
+use crate::domains::messages::quoted_messages::{QuotedMessage, fetch_quoted_messages};
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::models::message::Message;
+use crate::utils::pagination_cursor::Cursor;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::{Extension, Path, Query, State};
+use serde::{Deserialize, Serialize};
+
+// default and maximum page sizes for thread listings
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 100;
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    parent: QuotedMessage,
+    count: usize,
+    replies: Option<Vec<Message>>,
+    has_more: bool,
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SearchParams {
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+pub async fn get_message_thread(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Path(message_id): Path<i64>,
+    Query(params): Query<SearchParams>,
+) -> ApiResult<ResponseCore> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .clamp(1, MAX_PAGE_LIMIT);
+
+    // threads read oldest-first, so pages only move forward
+    let after = match params.cursor.as_deref().map(Cursor::decode) {
+        None => None,
+        Some(Some(Cursor::After(after))) => Some(after),
+        Some(_) => {
+            return Err(AppError::BadRequest(
+                "Invalid pagination cursor".to_string(),
+            ));
+        }
+    };
+
+    // Step 1: Resolve the thread's room - from the parent, or from its replies once it was deleted
+    let room_id = state
+        .repos
+        .messages
+        .thread_room(message_id)
+        .await
+        .context("Failed to get message thread")?
+        .ok_or_else(|| AppError::NotFound("Message not found or does not exist".to_string()))?;
+
+    // Step 2: Only room members can read a thread
+    state
+        .repos
+        .rooms
+        .member(room_id, session.user.id)
+        .await
+        .context("Failed to verify room membership")?
+        .ok_or_else(|| AppError::Forbidden("You are not a member of this room".to_string()))?;
+
+    // Step 3: Parent preview - a deleted parent comes back as a placeholder
+    let parent = fetch_quoted_messages(&state.repos, &[message_id], session.user.id)
+        .await
+        .context("Failed to fetch thread parent")?
+        .remove(&message_id)
+        .unwrap_or_else(|| QuotedMessage::deleted(message_id));
+
+    // Step 4: Replies, fetching one row more than requested to know whether another page exists
+    let mut replies = state
+        .repos
+        .messages
+        .replies(message_id, session.user.id, after, limit + 1)
+        .await
+        .context("Failed to fetch message thread replies")?;
+
+    let has_more = replies.len() as i64 > limit;
+    replies.truncate(limit as usize);
+
+    // handed out even on the last page so clients can poll for new replies
+    let next_cursor = Cursor::settled_after(
+        replies.iter().map(|reply| (reply.id, reply.sent_at.as_str())),
+        after,
+    )
+    .map(|cursor| cursor.encode());
+
+    Ok(ApiResponse::ok(
+        "Message thread fetched successfully",
+        ResponseCore {
+            parent,
+            count: replies.len(),
+            replies: Some(replies),
+            has_more,
+            next_cursor,
+        },
+    ))
+}
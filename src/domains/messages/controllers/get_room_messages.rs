@@ -1,61 +1,43 @@
 use crate::AppState;
+use crate::domains::messages::quoted_messages::{QuotedMessage, fetch_quoted_messages};
+use crate::domains::messages::service::require_room_access;
 use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
-use axum::{
-    Json,
-    extract::{Extension, Path, State, Query},
-    http::StatusCode,
-    response::IntoResponse,
-};
-use chrono::NaiveDateTime;
+use crate::models::message::Message;
+use crate::utils::pagination_cursor::Cursor;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::{Extension, Path, State, Query};
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use tracing::error;
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
-pub struct Message {
-    pub id: i64,
-    pub room_id: i64,
-    pub sender_id: Option<i64>,
-    #[sqlx(rename = "type")]
-    pub message_type: String,
-    pub text_content: Option<String>,
-    pub attachment_1: Option<String>,
-    pub attachment_2: Option<String>,
-    pub attachment_3: Option<String>,
-    pub attachment_4: Option<String>,
-    pub status: String,
-    pub sent_at: String,
-    pub created_at: NaiveDateTime,
-    pub updated_at: NaiveDateTime,
+/// A room message with a preview of the message it replies to.
+#[derive(Debug, Serialize)]
+pub struct RoomMessage {
+    #[serde(flatten)]
+    message: Message,
+    reply_to: Option<QuotedMessage>,
 }
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
-pub struct MessageStatusReceipt {
-    pub id: i64,
-    pub message_id: i64,
-    pub sender_id: i64,
-    pub receiver_id: Option<i64>,
-    pub room_id: i64,
-    pub status: String,
-    pub action: String,
-    pub created_at: NaiveDateTime,
-}
+// default and maximum page sizes for room message listings
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 100;
 
 #[derive(Debug, Serialize)]
 pub struct ResponseCore {
     count: usize,
-    messages: Option<Vec<Message>>
-}
-
-#[derive(Debug, Serialize)]
-pub struct GetRoomMessagesResponse {
-    pub response_message: String,
-    pub response: Option<ResponseCore>,
-    pub error: Option<String>,
+    messages: Option<Vec<RoomMessage>>,
+    has_more: bool,
+    next_cursor: Option<String>,
+    prev_cursor: Option<String>,
 }
 
 #[derive(Deserialize)]
 pub struct SearchParams {
-    user_id: i64,
+    before: Option<i64>,
+    after: Option<i64>,
+    cursor: Option<String>,
+    limit: Option<i64>,
 }
 
 pub async fn get_room_messages(
@@ -63,45 +45,105 @@ pub async fn get_room_messages(
     Extension(session): Extension<SessionsMiddlewareOutput>,
     Query(params): Query<SearchParams>,
     Path(room_id): Path<i64>,
-) -> impl IntoResponse {
-    let user_id = params.user_id;
-
-    // Step 1: Fetch all the messages in this room
-    let messages_result = sqlx::query_as::<_, Message>(
-        r#"
-        SELECT * 
-        FROM messages 
-        WHERE room_id = $1 
-        ORDER BY created_at ASC
-        "#
-    )
-    .bind(room_id)
-    .fetch_all(&state.db)
-    .await;
-
-    match messages_result {
-        Ok(msgs) => {
-            return (
-            StatusCode::OK,
-            Json(GetRoomMessagesResponse {
-                response_message: "Room messages fetched successfully".to_string(),
-                response: Some(ResponseCore {
-                    count: msgs.len(),
-                    messages: Some(msgs),
-                }),
-                error: None,
-            }))
+) -> ApiResult<ResponseCore> {
+    let user_id = session.user.id;
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .clamp(1, MAX_PAGE_LIMIT);
+
+    // Step 1: Only room members can read its messages
+    require_room_access(&state.repos, room_id, user_id).await?;
+
+    // Step 2: Resolve the page anchor - an opaque cursor wins over raw message ids
+    let anchor = match (&params.cursor, params.before, params.after) {
+        (Some(cursor), _, _) => match Cursor::decode(cursor) {
+            Some(c) => Some(c),
+            None => {
+                return Err(AppError::BadRequest(
+                    "Invalid pagination cursor".to_string(),
+                ));
+            }
         },
+        (None, Some(_), Some(_)) => {
+            return Err(AppError::BadRequest(
+                "Provide either 'before' or 'after', not both".to_string(),
+            ));
+        }
+        (None, Some(before), None) => Some(Cursor::Before(before)),
+        (None, None, Some(after)) => Some(Cursor::After(after)),
+        (None, None, None) => None,
+    };
+
+    // Step 3: Fetch one row more than requested to know whether another page exists.
+    // Without an anchor, the latest page of the room is returned (scrollback starting point).
+    let mut msgs = state
+        .repos
+        .messages
+        .page_for_room(room_id, user_id, anchor, limit + 1)
+        .await
+        .context("Failed to fetch room messages")?;
+
+    let has_more = msgs.len() as i64 > limit;
+    msgs.truncate(limit as usize);
+
+    let is_forward = matches!(anchor, Some(Cursor::After(_)));
+
+    // pages are always returned oldest-first
+    if !is_forward {
+        msgs.reverse();
+    }
+
+    // embed a quoted preview of the message each reply points at
+    let mut parent_ids: Vec<i64> = msgs.iter().filter_map(|m| m.reply_to_message_id).collect();
+    parent_ids.sort_unstable();
+    parent_ids.dedup();
+
+    let quotes = match fetch_quoted_messages(&state.repos, &parent_ids, user_id).await {
+        Ok(quotes) => quotes,
         Err(e) => {
-            error!("FAILED TO FETCH ROOM MESSAGES!");
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(GetRoomMessagesResponse {
-                    response_message: "Failed to fetch room messages".to_string(),
-                    response: None,
-                    error: Some(e.to_string()),
-                }),
-            );
+            error!("FAILED TO FETCH QUOTED MESSAGES: {}", e);
+            HashMap::new()
         }
     };
-}
\ No newline at end of file
+
+    let msgs: Vec<RoomMessage> = msgs
+        .into_iter()
+        .map(|message| RoomMessage {
+            reply_to: message
+                .reply_to_message_id
+                .and_then(|parent_id| quotes.get(&parent_id).cloned()),
+            message,
+        })
+        .collect();
+
+    let first_id = msgs.first().map(|m| m.message.id);
+
+    // older messages: exhausted once a backward page comes up short
+    let prev_cursor = match first_id {
+        Some(id) if is_forward || has_more => Some(Cursor::Before(id).encode()),
+        _ => None,
+    };
+
+    // newer messages: always handed out so clients can keep polling for catch-up
+    let resume_from = match anchor {
+        Some(Cursor::After(after)) => Some(after),
+        _ => first_id.map(|id| id - 1),
+    };
+    let next_cursor = Cursor::settled_after(
+        msgs.iter().map(|m| (m.message.id, m.message.sent_at.as_str())),
+        resume_from,
+    )
+    .map(|cursor| cursor.encode());
+
+    Ok(ApiResponse::ok(
+        "Room messages fetched successfully",
+        ResponseCore {
+            count: msgs.len(),
+            messages: Some(msgs),
+            has_more,
+            next_cursor,
+            prev_cursor,
+        },
+    ))
+}
@@ -4,10 +4,12 @@ pub mod create_message;
 pub mod delete_message;
 pub mod get_message_edit_history;
 pub mod get_message_status_receipts;
+pub mod get_message_thread;
 pub mod get_room_messages;
 pub mod un_archive_message;
 pub mod un_bookmark_message;
 pub mod update_message;
 pub mod sync_room_messages_status_to_delivered;
 pub mod sync_messages_status_to_seen;
-pub mod react_to_message;
\ No newline at end of file
+pub mod react_to_message;
+pub mod search_messages;
\ No newline at end of file
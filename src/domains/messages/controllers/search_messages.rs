@@ -0,0 +1,87 @@
+use crate::AppState;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::models::message::{MessageSearch, MessageSearchHit};
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::{Extension, Query, State};
+use serde::{Deserialize, Serialize};
+
+// default and maximum page sizes for message search results
+const DEFAULT_PAGE_LIMIT: i64 = 20;
+const MAX_PAGE_LIMIT: i64 = 100;
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    count: usize,
+    hits: Option<Vec<MessageSearchHit>>,
+    has_more: bool,
+    next_offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    q: String,
+    room_id: Option<i64>,
+    sender_id: Option<i64>,
+    message_type: Option<String>,
+    sent_after: Option<i64>,  // milliseconds, inclusive
+    sent_before: Option<i64>, // milliseconds, inclusive
+    has_attachment: Option<bool>,
+    include_edits: Option<bool>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+pub async fn search_messages(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Query(params): Query<SearchParams>,
+) -> ApiResult<ResponseCore> {
+    let query = params.q.trim();
+
+    if query.is_empty() {
+        return Err(AppError::BadRequest("Search query is required".to_string()));
+    }
+
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .clamp(1, MAX_PAGE_LIMIT);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    // Results are restricted to rooms the caller is a member of.
+    let search = MessageSearch {
+        query: query.to_string(),
+        room_id: params.room_id,
+        sender_id: params.sender_id,
+        message_type: params.message_type,
+        sent_after: params.sent_after,
+        sent_before: params.sent_before,
+        has_attachment: params.has_attachment,
+        include_edits: params.include_edits.unwrap_or(false),
+        limit: limit + 1,
+        offset,
+    };
+
+    let mut hits = state
+        .repos
+        .messages
+        .search(session.user.id, &search)
+        .await
+        .context("Failed to search messages")?;
+
+    let has_more = hits.len() as i64 > limit;
+    hits.truncate(limit as usize);
+
+    let next_offset = has_more.then(|| offset + limit);
+
+    Ok(ApiResponse::ok(
+        "Message search completed successfully",
+        ResponseCore {
+            count: hits.len(),
+            hits: Some(hits),
+            has_more,
+            next_offset,
+        },
+    ))
+}
@@ -1,2 +1,4 @@
 pub mod controllers;
+pub mod quoted_messages;
 pub mod router;
+pub mod service;
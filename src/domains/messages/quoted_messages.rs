@@ -0,0 +1,128 @@
+use crate::repositories::Repositories;
+use crate::utils::app_error::AppError;
+use serde::Serialize;
+use std::collections::HashMap;
+
+// quoted previews only carry the start of the parent's text
+const QUOTE_PREVIEW_CHARS: usize = 120;
+
+/// Compact preview of the message a reply points at.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotedMessage {
+    pub id: i64,
+    pub sender_id: Option<i64>,
+    pub message_type: Option<String>,
+    pub text_preview: Option<String>,
+    pub has_attachment: bool,
+    pub is_deleted: bool,
+}
+
+impl QuotedMessage {
+    /// Stand-in for a parent that was deleted for everyone, deleted by the viewer for themselves,
+    /// or no longer exists.
+    pub fn deleted(id: i64) -> Self {
+        Self {
+            id,
+            sender_id: None,
+            message_type: None,
+            text_preview: Some("This message was deleted".to_string()),
+            has_attachment: false,
+            is_deleted: true,
+        }
+    }
+}
+
+/// Loads previews of the given parent ids as `viewer_id` sees them. Ids without a row, or that the
+/// viewer deleted for themselves, are returned as deleted placeholders.
+pub async fn fetch_quoted_messages(
+    repos: &Repositories,
+    parent_ids: &[i64],
+    viewer_id: i64,
+) -> Result<HashMap<i64, QuotedMessage>, AppError> {
+    if parent_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let messages = repos.messages.find_many(parent_ids, viewer_id).await?;
+
+    let mut quotes: HashMap<i64, QuotedMessage> = messages
+        .into_iter()
+        .map(|message| {
+            if message.is_deleted {
+                return (message.id, QuotedMessage::deleted(message.id));
+            }
+
+            let quote = QuotedMessage {
+                id: message.id,
+                sender_id: message.sender_id,
+                has_attachment: message.has_attachment(),
+                message_type: Some(message.message_type),
+                text_preview: message
+                    .text_content
+                    .map(|text| text.chars().take(QUOTE_PREVIEW_CHARS).collect()),
+                is_deleted: false,
+            };
+
+            (message.id, quote)
+        })
+        .collect();
+
+    for id in parent_ids {
+        quotes
+            .entry(*id)
+            .or_insert_with(|| QuotedMessage::deleted(*id));
+    }
+
+    Ok(quotes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::messages::service;
+    use crate::domains::rooms::service as rooms;
+    use crate::models::message::NewMessage;
+
+    #[tokio::test]
+    async fn parents_deleted_for_the_viewer_are_quoted_as_deleted() {
+        let (repos, store) = Repositories::in_memory();
+        let ada = store.insert_user("Ada").id;
+        let bob = store.insert_user("Bob").id;
+        let room = rooms::create_private_room(&repos, ada, bob).await.unwrap();
+
+        let (_, parent) = service::post_message(
+            &repos,
+            &NewMessage {
+                room_id: room.id,
+                sender_id: ada,
+                message_type: "regular".to_string(),
+                text_content: Some("meet at noon".to_string()),
+                reply_to_message_id: None,
+                sent_at: "0".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+
+        service::delete_for_me(&repos, parent.id, bob)
+            .await
+            .unwrap();
+
+        let for_bob = fetch_quoted_messages(&repos, &[parent.id], bob)
+            .await
+            .unwrap();
+        assert!(for_bob[&parent.id].is_deleted);
+        assert_eq!(
+            for_bob[&parent.id].text_preview.as_deref(),
+            Some("This message was deleted")
+        );
+
+        let for_ada = fetch_quoted_messages(&repos, &[parent.id], ada)
+            .await
+            .unwrap();
+        assert_eq!(
+            for_ada[&parent.id].text_preview.as_deref(),
+            Some("meet at noon")
+        );
+    }
+}
@@ -0,0 +1,842 @@
+use crate::models::message::{Message, MessageStatus, NewMessage, NewReaction};
+use crate::models::receipt::{NewReceipt, ReceiptAction};
+use crate::models::room::Room;
+use crate::repositories::Repositories;
+use crate::utils::app_error::{AppError, ErrorContext};
+use crate::utils::current_time_in_milliseconds::current_time_millis;
+use tracing::error;
+
+// used when the [messages] config section is not provided
+pub const DEFAULT_DELETE_FOR_EVERYONE_WINDOW_SECS: u64 = 3600;
+
+#[derive(Debug)]
+pub struct ReactionOutcome {
+    pub room: Room,
+    pub message: Message,
+    /// "added" or "changed"
+    pub action: &'static str,
+    pub updates_count_tracker: i32,
+}
+
+async fn find_room(repos: &Repositories, room_id: i64) -> Result<Room, AppError> {
+    repos
+        .rooms
+        .find(room_id)
+        .await
+        .context("Failed to get room")?
+        .ok_or_else(|| AppError::NotFound("Room not found".to_string()))
+}
+
+async fn find_message(
+    repos: &Repositories,
+    message_id: i64,
+    not_found: &str,
+) -> Result<Message, AppError> {
+    repos
+        .messages
+        .find(message_id)
+        .await
+        .context("Failed to fetch message")?
+        .ok_or_else(|| AppError::NotFound(not_found.to_string()))
+}
+
+async fn require_member(
+    repos: &Repositories,
+    room_id: i64,
+    user_id: i64,
+    forbidden: &str,
+) -> Result<(), AppError> {
+    repos
+        .rooms
+        .member(room_id, user_id)
+        .await
+        .context("Failed to verify room membership")?
+        .ok_or_else(|| AppError::Forbidden(forbidden.to_string()))?;
+
+    Ok(())
+}
+
+/// The room must exist and the user must belong to it to read anything posted there.
+pub async fn require_room_access(
+    repos: &Repositories,
+    room_id: i64,
+    user_id: i64,
+) -> Result<Room, AppError> {
+    let room = find_room(repos, room_id).await?;
+
+    require_member(repos, room.id, user_id, "You are not a member of this room").await?;
+
+    Ok(room)
+}
+
+/// A message the user is allowed to look at, because they belong to its room.
+pub async fn readable_message(
+    repos: &Repositories,
+    message_id: i64,
+    user_id: i64,
+) -> Result<Message, AppError> {
+    let message = find_message(repos, message_id, "Message not found or does not exist").await?;
+
+    require_member(
+        repos,
+        message.room_id,
+        user_id,
+        "You are not a member of this room",
+    )
+    .await?;
+
+    Ok(message)
+}
+
+/// Stores a new message once the room exists, the sender belongs to it and any quoted message
+/// comes from the same room. Attachments are added afterwards, once the message has an id.
+pub async fn post_message(
+    repos: &Repositories,
+    message: &NewMessage,
+) -> Result<(Room, Message), AppError> {
+    let room = find_room(repos, message.room_id).await?;
+
+    require_member(
+        repos,
+        room.id,
+        message.sender_id,
+        "Sender is not a member of this room",
+    )
+    .await?;
+
+    // replies can only quote a message from the same room
+    if let Some(parent_id) = message.reply_to_message_id {
+        let parent = repos
+            .messages
+            .find(parent_id)
+            .await
+            .context("Failed to get reply to message")?;
+
+        if parent.map(|parent| parent.room_id) != Some(room.id) {
+            return Err(AppError::BadRequest(format!(
+                "Message with id: '{}' not found in this room",
+                parent_id
+            )));
+        }
+    }
+
+    let message = repos
+        .messages
+        .create(message)
+        .await
+        .context("Failed to create message")?;
+
+    Ok((room, message))
+}
+
+/// Hands `receipt` to every room member except its sender. Spaces have no recipients to track.
+pub async fn send_receipts(
+    repos: &Repositories,
+    room: &Room,
+    receipt: &NewReceipt,
+) -> Result<(), AppError> {
+    if room.is_space {
+        return Ok(());
+    }
+
+    let members = repos.rooms.members(room.id).await?;
+
+    for member in members.iter().filter(|m| m.user_id != receipt.sender_id) {
+        repos.receipts.create(receipt, member.user_id).await?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the text of a message, keeping the previous version in its edit history. Only the
+/// sender may edit, and only while the message is not deleted.
+pub async fn edit_message(
+    repos: &Repositories,
+    message_id: i64,
+    editor_id: i64,
+    text_content: &str,
+) -> Result<(Room, Message), AppError> {
+    let message = find_message(repos, message_id, "Message not found").await?;
+
+    if message.sender_id != Some(editor_id) {
+        return Err(AppError::Forbidden(
+            "You can only update your own messages".to_string(),
+        ));
+    }
+
+    if message.is_deleted {
+        return Err(AppError::Conflict(
+            "Deleted messages cannot be updated".to_string(),
+        ));
+    }
+
+    let room = find_room(repos, message.room_id).await?;
+
+    repos
+        .messages
+        .record_edit(
+            message.id,
+            message.text_content.as_deref().unwrap_or_default(),
+            text_content,
+        )
+        .await
+        .context("Failed to save edit history")?;
+
+    let updated_message = repos
+        .messages
+        .update_text(message.id, text_content, message.updates_counter + 1)
+        .await
+        .context("Failed to update message")?;
+
+    Ok((room, updated_message))
+}
+
+/// Hides the message for a single room member. The message itself is left untouched.
+pub async fn delete_for_me(
+    repos: &Repositories,
+    message_id: i64,
+    user_id: i64,
+) -> Result<Message, AppError> {
+    let message = find_message(repos, message_id, "Message not found or does not exist").await?;
+
+    require_member(
+        repos,
+        message.room_id,
+        user_id,
+        "You are not a member of this room",
+    )
+    .await?;
+
+    repos
+        .messages
+        .hide_for(message.id, user_id)
+        .await
+        .context("Failed to delete message")?;
+
+    Ok(message)
+}
+
+/// Tombstones the message for the whole room - content and attachments are cleared but the row
+/// stays, so receipts, reactions and replies keep pointing at something. Senders only get
+/// `window_secs` to do so, admins can always remove a message.
+pub async fn delete_for_everyone(
+    repos: &Repositories,
+    message_id: i64,
+    user_id: i64,
+    is_admin: bool,
+    window_secs: u64,
+) -> Result<(Room, Message), AppError> {
+    let message = find_message(repos, message_id, "Message not found or does not exist").await?;
+
+    if message.sender_id != Some(user_id) && !is_admin {
+        return Err(AppError::Forbidden(
+            "You don't have permission to delete this message".to_string(),
+        ));
+    }
+
+    if message.is_deleted {
+        return Err(AppError::Conflict(
+            "Message has already been deleted".to_string(),
+        ));
+    }
+
+    let now = current_time_millis();
+    let sent_at = message.sent_at.parse::<u128>().unwrap_or(0);
+
+    if !is_admin && now.saturating_sub(sent_at) > u128::from(window_secs) * 1000 {
+        return Err(AppError::Forbidden(format!(
+            "Messages can only be deleted for everyone within {} seconds of sending",
+            window_secs
+        )));
+    }
+
+    let room = find_room(repos, message.room_id).await?;
+
+    let deleted_message = repos
+        .messages
+        .tombstone(message.id, &now.to_string())
+        .await
+        .context("Failed to delete message")?
+        .ok_or_else(|| AppError::Conflict("Message has already been deleted".to_string()))?;
+
+    Ok((room, deleted_message))
+}
+
+/// Adds the user's reaction to a message, or swaps the one they already left. Only room
+/// members can react, and deleted messages take no reactions.
+pub async fn react_to_message(
+    repos: &Repositories,
+    message_id: i64,
+    sender_id: i64,
+    reaction_type: &str,
+) -> Result<ReactionOutcome, AppError> {
+    let message = find_message(repos, message_id, "Message not found").await?;
+
+    if message.is_deleted {
+        return Err(AppError::Conflict(
+            "Deleted messages cannot be reacted to".to_string(),
+        ));
+    }
+
+    let room = find_room(repos, message.room_id).await?;
+
+    require_member(
+        repos,
+        room.id,
+        sender_id,
+        "You are not authorized to react to this message",
+    )
+    .await?;
+
+    let existing_reaction = repos
+        .messages
+        .find_reaction(message.id, sender_id)
+        .await
+        .context("Failed to check existing reaction")?;
+
+    let reaction = NewReaction {
+        message_id: message.id,
+        room_id: room.id,
+        sender_id,
+        reaction_type: reaction_type.to_string(),
+        message_updates_counter: message.updates_counter + 1,
+    };
+
+    let action = match existing_reaction {
+        Some(existing) => {
+            repos
+                .messages
+                .update_reaction(existing.id, &reaction)
+                .await
+                .context("Failed to save reaction")?;
+            "changed"
+        }
+        None => {
+            repos
+                .messages
+                .create_reaction(&reaction)
+                .await
+                .context("Failed to save reaction")?;
+            "added"
+        }
+    };
+
+    Ok(ReactionOutcome {
+        room,
+        message,
+        action,
+        updates_count_tracker: reaction.message_updates_counter,
+    })
+}
+
+pub async fn set_archived(
+    repos: &Repositories,
+    message_id: i64,
+    user_id: i64,
+    archived: bool,
+) -> Result<Message, AppError> {
+    let message = find_message(repos, message_id, "Message not found").await?;
+
+    repos
+        .messages
+        .set_archived(message.id, user_id, archived)
+        .await
+        .context(if archived {
+            "Failed to archive message"
+        } else {
+            "Failed to un-archive message"
+        })?;
+
+    Ok(message)
+}
+
+pub async fn set_bookmarked(
+    repos: &Repositories,
+    message_id: i64,
+    user_id: i64,
+    bookmarked: bool,
+) -> Result<Message, AppError> {
+    let message = find_message(repos, message_id, "Message not found").await?;
+
+    repos
+        .messages
+        .set_bookmarked(message.id, user_id, bookmarked)
+        .await
+        .context(if bookmarked {
+            "Failed to bookmark message"
+        } else {
+            "Failed to un-bookmark message"
+        })?;
+
+    Ok(message)
+}
+
+/// Records that `user_id` got every message of the room. A message only counts as delivered
+/// once every member other than its sender got the current revision of it.
+pub async fn mark_room_delivered(
+    repos: &Repositories,
+    room_id: i64,
+    user_id: i64,
+) -> Result<(), AppError> {
+    let room = find_room(repos, room_id).await?;
+
+    require_member(repos, room.id, user_id, "You are not a member of this room").await?;
+
+    let messages = repos
+        .messages
+        .list_for_room(room.id)
+        .await
+        .context("Failed to sync room messages statuses")?;
+
+    acknowledge_messages(repos, &room, &messages, user_id, MessageStatus::Delivered).await
+}
+
+/// Records that `user_id` saw every message of every room they are in, and returns the rooms
+/// that were synced. Rooms whose messages cannot be read are skipped.
+pub async fn mark_all_seen(repos: &Repositories, user_id: i64) -> Result<Vec<i64>, AppError> {
+    let rooms = repos
+        .rooms
+        .list_for_member(user_id)
+        .await
+        .context("Failed to get rooms")?;
+
+    let mut synced_rooms = Vec::with_capacity(rooms.len());
+
+    for room in rooms {
+        let messages = match repos.messages.list_for_room(room.id).await {
+            Ok(messages) => messages,
+            Err(e) => {
+                error!("FAILED TO GET MESSAGES FOR ROOM {}: {}", room.id, e);
+                continue;
+            }
+        };
+
+        acknowledge_messages(repos, &room, &messages, user_id, MessageStatus::Seen).await?;
+        synced_rooms.push(room.id);
+    }
+
+    Ok(synced_rooms)
+}
+
+async fn acknowledge_messages(
+    repos: &Repositories,
+    room: &Room,
+    messages: &[Message],
+    user_id: i64,
+    status: MessageStatus,
+) -> Result<(), AppError> {
+    let members: Vec<i64> = repos
+        .rooms
+        .members(room.id)
+        .await
+        .context("Failed to get room members")?
+        .into_iter()
+        .map(|member| member.user_id)
+        .collect();
+
+    for message in messages {
+        // nobody acknowledges their own messages, and a seen message can't go back to delivered
+        let Some(sender_id) = message.sender_id else {
+            continue;
+        };
+
+        if sender_id == user_id
+            || message.is_deleted
+            || message.status == MessageStatus::Seen.as_str()
+            || message.status == status.as_str()
+        {
+            continue;
+        }
+
+        let mut receivers = repos
+            .receipts
+            .receivers(message.id, status, message.updates_counter)
+            .await
+            .context("Failed to fetch message status receipts")?;
+
+        if !receivers.contains(&user_id) {
+            let receipt = NewReceipt {
+                message_id: message.id,
+                room_id: room.id,
+                sender_id,
+                action: ReceiptAction::System,
+                status,
+                updates_count_tracker: message.updates_counter,
+            };
+
+            repos
+                .receipts
+                .create(&receipt, user_id)
+                .await
+                .context("Failed to insert message status receipt")?;
+
+            receivers.push(user_id);
+        }
+
+        let acknowledged_by_all = members
+            .iter()
+            .filter(|member| **member != sender_id)
+            .all(|member| receivers.contains(member));
+
+        if acknowledged_by_all {
+            repos
+                .messages
+                .set_status(message.id, status)
+                .await
+                .context("Failed to update message status")?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domains::rooms::service as rooms;
+    use crate::repositories::memory::InMemoryStore;
+
+    fn new_message(room_id: i64, sender_id: i64, text: &str) -> NewMessage {
+        NewMessage {
+            room_id,
+            sender_id,
+            message_type: "regular".to_string(),
+            text_content: Some(text.to_string()),
+            reply_to_message_id: None,
+            sent_at: current_time_millis().to_string(),
+        }
+    }
+
+    fn sent_receipt(message: &Message) -> NewReceipt {
+        NewReceipt {
+            message_id: message.id,
+            room_id: message.room_id,
+            sender_id: message.sender_id.unwrap(),
+            action: ReceiptAction::OriginalSend,
+            status: MessageStatus::Sent,
+            updates_count_tracker: message.updates_counter,
+        }
+    }
+
+    /// Two users sharing a private room.
+    async fn private_chat() -> (Repositories, InMemoryStore, i64, i64, Room) {
+        let (repos, store) = Repositories::in_memory();
+        let ada = store.insert_user("Ada").id;
+        let bob = store.insert_user("Bob").id;
+        let room = rooms::create_private_room(&repos, ada, bob).await.unwrap();
+
+        (repos, store, ada, bob, room)
+    }
+
+    #[tokio::test]
+    async fn only_room_members_can_post() {
+        let (repos, store, _, _, room) = private_chat().await;
+        let eve = store.insert_user("Eve").id;
+
+        let err = post_message(&repos, &new_message(room.id, eve, "hi"))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, AppError::Forbidden(_)));
+    }
+
+    #[tokio::test]
+    async fn posting_to_a_missing_room_is_not_found() {
+        let (repos, _, ada, _, _) = private_chat().await;
+
+        let err = post_message(&repos, &new_message(999, ada, "hi"))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, AppError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn replies_must_quote_a_message_from_the_same_room() {
+        let (repos, store, ada, bob, room) = private_chat().await;
+        let cy = store.insert_user("Cy").id;
+        let other_room = rooms::create_private_room(&repos, ada, cy).await.unwrap();
+
+        let (_, elsewhere) = post_message(&repos, &new_message(other_room.id, ada, "psst"))
+            .await
+            .unwrap();
+        let (_, parent) = post_message(&repos, &new_message(room.id, ada, "question"))
+            .await
+            .unwrap();
+
+        let mut reply = new_message(room.id, bob, "answer");
+        reply.reply_to_message_id = Some(elsewhere.id);
+        let err = post_message(&repos, &reply).await.unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(_)));
+
+        reply.reply_to_message_id = Some(parent.id);
+        let (_, reply) = post_message(&repos, &reply).await.unwrap();
+        assert_eq!(reply.reply_to_message_id, Some(parent.id));
+    }
+
+    #[tokio::test]
+    async fn receipts_go_to_every_other_member() {
+        let (repos, store) = Repositories::in_memory();
+        let ada = store.insert_user("Ada").id;
+        let bob = store.insert_user("Bob").id;
+        let cy = store.insert_user("Cy").id;
+        let room = rooms::create_group_room(&repos, ada, "Team".to_string(), vec![bob, cy])
+            .await
+            .unwrap();
+
+        let (room, message) = post_message(&repos, &new_message(room.id, bob, "hello"))
+            .await
+            .unwrap();
+        send_receipts(&repos, &room, &sent_receipt(&message))
+            .await
+            .unwrap();
+
+        let mut receivers: Vec<i64> = store
+            .receipts()
+            .iter()
+            .filter_map(|receipt| receipt.receiver_id)
+            .collect();
+        receivers.sort_unstable();
+
+        assert_eq!(receivers, vec![ada, cy]);
+    }
+
+    #[tokio::test]
+    async fn spaces_get_no_receipts() {
+        let (repos, store, ada, _, mut room) = private_chat().await;
+        let (_, message) = post_message(&repos, &new_message(room.id, ada, "note"))
+            .await
+            .unwrap();
+
+        room.is_space = true;
+        send_receipts(&repos, &room, &sent_receipt(&message))
+            .await
+            .unwrap();
+
+        assert!(store.receipts().is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_the_sender_can_edit_and_edits_are_kept() {
+        let (repos, store, ada, bob, room) = private_chat().await;
+        let (_, message) = post_message(&repos, &new_message(room.id, ada, "helo"))
+            .await
+            .unwrap();
+
+        let err = edit_message(&repos, message.id, bob, "hijacked")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Forbidden(_)));
+
+        let (_, edited) = edit_message(&repos, message.id, ada, "hello")
+            .await
+            .unwrap();
+
+        assert_eq!(edited.text_content.as_deref(), Some("hello"));
+        assert_eq!(edited.updates_counter, 1);
+        assert_eq!(edited.status, MessageStatus::Updated.as_str());
+        assert_eq!(
+            store.edits(),
+            vec![(message.id, "helo".to_string(), "hello".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn only_room_members_can_read_a_message() {
+        let (repos, store, ada, bob, room) = private_chat().await;
+        let eve = store.insert_user("Eve").id;
+        let (_, message) = post_message(&repos, &new_message(room.id, ada, "hi"))
+            .await
+            .unwrap();
+
+        assert!(readable_message(&repos, message.id, bob).await.is_ok());
+
+        let err = readable_message(&repos, message.id, eve).await.unwrap_err();
+        assert!(matches!(err, AppError::Forbidden(_)));
+
+        let err = require_room_access(&repos, room.id, eve).await.unwrap_err();
+        assert!(matches!(err, AppError::Forbidden(_)));
+    }
+
+    #[tokio::test]
+    async fn deleting_for_everyone_drops_the_edit_history() {
+        let (repos, store, ada, _, room) = private_chat().await;
+        let (_, message) = post_message(&repos, &new_message(room.id, ada, "secret"))
+            .await
+            .unwrap();
+
+        edit_message(&repos, message.id, ada, "still secret")
+            .await
+            .unwrap();
+        assert_eq!(store.edits().len(), 1);
+
+        delete_for_everyone(&repos, message.id, ada, false, 60)
+            .await
+            .unwrap();
+
+        assert!(store.edits().is_empty());
+    }
+
+    #[tokio::test]
+    async fn deleted_messages_cannot_be_edited_or_reacted_to() {
+        let (repos, _, ada, bob, room) = private_chat().await;
+        let (_, message) = post_message(&repos, &new_message(room.id, ada, "oops"))
+            .await
+            .unwrap();
+
+        delete_for_everyone(&repos, message.id, ada, false, 60)
+            .await
+            .unwrap();
+
+        let err = edit_message(&repos, message.id, ada, "fixed")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Conflict(_)));
+
+        let err = react_to_message(&repos, message.id, bob, "👍")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Conflict(_)));
+    }
+
+    #[tokio::test]
+    async fn delete_for_everyone_honours_the_window_unless_admin() {
+        let (repos, store, ada, bob, room) = private_chat().await;
+        let (_, message) = post_message(&repos, &new_message(room.id, ada, "old news"))
+            .await
+            .unwrap();
+        store.backdate_message(message.id, "0");
+
+        let err = delete_for_everyone(&repos, message.id, bob, false, 60)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Forbidden(_)));
+
+        let err = delete_for_everyone(&repos, message.id, ada, false, 60)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Forbidden(_)));
+
+        let (_, deleted) = delete_for_everyone(&repos, message.id, bob, true, 60)
+            .await
+            .unwrap();
+        assert!(deleted.is_deleted);
+        assert_eq!(deleted.text_content, None);
+
+        let err = delete_for_everyone(&repos, message.id, bob, true, 60)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Conflict(_)));
+    }
+
+    #[tokio::test]
+    async fn delete_for_me_only_hides_the_message_for_that_member() {
+        let (repos, store, ada, bob, room) = private_chat().await;
+        let eve = store.insert_user("Eve").id;
+        let (_, message) = post_message(&repos, &new_message(room.id, ada, "hi"))
+            .await
+            .unwrap();
+
+        let err = delete_for_me(&repos, message.id, eve).await.unwrap_err();
+        assert!(matches!(err, AppError::Forbidden(_)));
+
+        let hidden = delete_for_me(&repos, message.id, bob).await.unwrap();
+
+        assert!(!hidden.is_deleted);
+        assert!(store.is_hidden_for(message.id, bob));
+        assert!(!store.is_hidden_for(message.id, ada));
+    }
+
+    #[tokio::test]
+    async fn reacting_twice_changes_the_reaction() {
+        let (repos, store, ada, bob, room) = private_chat().await;
+        let eve = store.insert_user("Eve").id;
+        let (_, message) = post_message(&repos, &new_message(room.id, ada, "lunch?"))
+            .await
+            .unwrap();
+
+        let err = react_to_message(&repos, message.id, eve, "👍")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Forbidden(_)));
+
+        let first = react_to_message(&repos, message.id, bob, "👍")
+            .await
+            .unwrap();
+        let second = react_to_message(&repos, message.id, bob, "❤️")
+            .await
+            .unwrap();
+
+        assert_eq!(first.action, "added");
+        assert_eq!(second.action, "changed");
+        assert_eq!(second.updates_count_tracker, message.updates_counter + 1);
+
+        let reaction = repos
+            .messages
+            .find_reaction(message.id, bob)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(reaction.reaction_type, "❤️");
+    }
+
+    #[tokio::test]
+    async fn the_creator_of_a_private_room_can_react_too() {
+        let (repos, _, ada, bob, room) = private_chat().await;
+        let (_, message) = post_message(&repos, &new_message(room.id, bob, "hey"))
+            .await
+            .unwrap();
+
+        let reaction = react_to_message(&repos, message.id, ada, "👋")
+            .await
+            .unwrap();
+
+        assert_eq!(reaction.action, "added");
+    }
+
+    #[tokio::test]
+    async fn private_messages_are_seen_once_the_other_member_syncs() {
+        let (repos, _, ada, bob, room) = private_chat().await;
+        let (_, message) = post_message(&repos, &new_message(room.id, ada, "hi"))
+            .await
+            .unwrap();
+
+        // the sender syncing changes nothing
+        assert_eq!(mark_all_seen(&repos, ada).await.unwrap(), vec![room.id]);
+        let unchanged = repos.messages.find(message.id).await.unwrap().unwrap();
+        assert_eq!(unchanged.status, MessageStatus::Sent.as_str());
+
+        mark_all_seen(&repos, bob).await.unwrap();
+        let seen = repos.messages.find(message.id).await.unwrap().unwrap();
+        assert_eq!(seen.status, MessageStatus::Seen.as_str());
+    }
+
+    #[tokio::test]
+    async fn group_messages_are_delivered_once_every_member_synced() {
+        let (repos, store) = Repositories::in_memory();
+        let ada = store.insert_user("Ada").id;
+        let bob = store.insert_user("Bob").id;
+        let cy = store.insert_user("Cy").id;
+        let eve = store.insert_user("Eve").id;
+        let room = rooms::create_group_room(&repos, ada, "Team".to_string(), vec![bob, cy])
+            .await
+            .unwrap();
+        let (_, message) = post_message(&repos, &new_message(room.id, ada, "standup"))
+            .await
+            .unwrap();
+
+        let err = mark_room_delivered(&repos, room.id, eve).await.unwrap_err();
+        assert!(matches!(err, AppError::Forbidden(_)));
+
+        mark_room_delivered(&repos, room.id, bob).await.unwrap();
+        // syncing again must not count bob twice
+        mark_room_delivered(&repos, room.id, bob).await.unwrap();
+        let pending = repos.messages.find(message.id).await.unwrap().unwrap();
+        assert_eq!(pending.status, MessageStatus::Sent.as_str());
+        assert_eq!(store.receipts().len(), 1);
+
+        mark_room_delivered(&repos, room.id, cy).await.unwrap();
+        let delivered = repos.messages.find(message.id).await.unwrap().unwrap();
+        assert_eq!(delivered.status, MessageStatus::Delivered.as_str());
+    }
+}
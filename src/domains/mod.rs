@@ -1,5 +1,10 @@
 pub mod admin;
 pub mod auth;
+pub mod calls;
+pub mod health;
 pub mod messages;
+pub mod realtime;
 pub mod rooms;
+pub mod spaces;
+pub mod storage;
 pub mod user;
@@ -0,0 +1,449 @@
+use crate::AppState;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::presence_tracker::{PresenceStatus, publish_presence};
+use crate::utils::realtime_hub::{AccessRevocation, RealtimeEvent, RealtimeEventType};
+use axum::{
+    extract::{
+        Extension, State,
+        ws::{CloseFrame, Message as WsMessage, WebSocket, WebSocketUpgrade, close_code},
+    },
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+// outbound events buffered per connection before the room forwarders start waiting
+const CONNECTION_BUFFER: usize = 256;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClientCommand {
+    Subscribe { room_id: i64 },
+    Unsubscribe { room_id: i64 },
+    Heartbeat { status: Option<PresenceStatus> },
+    Typing { room_id: i64, is_typing: bool },
+}
+
+#[derive(Debug, Serialize)]
+pub struct TypingSignal {
+    pub user_id: i64,
+    pub is_typing: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ServerNotice {
+    pub event: String,
+    pub room_id: Option<i64>,
+    pub response_message: String,
+}
+
+pub async fn connect_websocket(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    let user_id = session.user.id;
+    let session_id = session.session_id;
+
+    // axum stops tracking the connection once it's upgraded, so the shutdown tracks it instead
+    let shutdown = state.shutdown.clone();
+
+    ws.on_upgrade(move |socket| shutdown.track(handle_socket(state, user_id, session_id, socket)))
+}
+
+/// What a revocation means for one connection.
+enum RevocationOutcome {
+    Unaffected,
+    LeaveRoom(i64),
+    Close(&'static str),
+}
+
+fn revocation_outcome(
+    revocation: AccessRevocation,
+    user_id: i64,
+    session_id: Option<i64>,
+) -> RevocationOutcome {
+    match revocation {
+        AccessRevocation::RoomMembership {
+            room_id,
+            user_id: member_id,
+        } if member_id == user_id => RevocationOutcome::LeaveRoom(room_id),
+        AccessRevocation::User {
+            user_id: revoked_user,
+        } if revoked_user == user_id => RevocationOutcome::Close("Your account is deactivated"),
+        AccessRevocation::Session {
+            session_id: revoked,
+        } if Some(revoked) == session_id => RevocationOutcome::Close("Session has been revoked"),
+        // connections on a token issued before sessions existed have no session to revoke
+        AccessRevocation::Sessions {
+            user_id: revoked_user,
+            except,
+        } if revoked_user == user_id && session_id.is_some() && session_id != except => {
+            RevocationOutcome::Close("Session has been revoked")
+        }
+        _ => RevocationOutcome::Unaffected,
+    }
+}
+
+async fn handle_socket(
+    state: AppState,
+    user_id: i64,
+    session_id: Option<i64>,
+    mut socket: WebSocket,
+) {
+    let (tx, mut rx) = mpsc::channel::<RealtimeEvent>(CONNECTION_BUFFER);
+    let mut subscriptions: HashMap<i64, JoinHandle<()>> = HashMap::new();
+
+    // listen before reading memberships, so a removal in between isn't missed
+    let mut revocations = state.hub.revocations();
+
+    // Subscribe to every room the user currently belongs to
+    let room_ids = match sqlx::query_scalar::<_, i64>(
+        "SELECT room_id FROM room_members WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            error!("FAILED TO FETCH ROOMS FOR WEBSOCKET CONNECTION: {}", e);
+
+            let _ = socket.send(WsMessage::Close(None)).await;
+            return;
+        }
+    };
+
+    for room_id in room_ids {
+        subscriptions.insert(room_id, subscribe_room(&state, room_id, tx.clone()));
+    }
+
+    if let Some(status) = state.presence.connect(user_id) {
+        publish_presence(&state, user_id, status).await;
+    }
+
+    info!("WEBSOCKET CONNECTED: USER {}", user_id);
+
+    loop {
+        tokio::select! {
+            _ = state.shutdown.triggered() => {
+                // "going away" tells clients to reconnect, ideally to another instance
+                let _ = socket
+                    .send(WsMessage::Close(Some(CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "Server is shutting down".into(),
+                    })))
+                    .await;
+
+                break;
+            }
+
+            revocation = revocations.recv() => {
+                let outcome = match revocation {
+                    Ok(revocation) => revocation_outcome(revocation, user_id, session_id),
+                    // missed revocations can't be replayed, so the client reconnects and is
+                    // checked again from scratch
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        let _ = socket
+                            .send(WsMessage::Close(Some(CloseFrame {
+                                code: close_code::AGAIN,
+                                reason: "Connection fell behind, please reconnect".into(),
+                            })))
+                            .await;
+
+                        break;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                match outcome {
+                    RevocationOutcome::Unaffected => {}
+                    RevocationOutcome::LeaveRoom(room_id) => {
+                        if unsubscribe_room(&state, room_id, &mut subscriptions).await {
+                            let notice = ServerNotice {
+                                event: "unsubscribed".to_string(),
+                                room_id: Some(room_id),
+                                response_message: "You are no longer a member of this room".to_string(),
+                            };
+
+                            if let Ok(text) = serde_json::to_string(&notice)
+                                && socket.send(WsMessage::Text(text.into())).await.is_err()
+                            {
+                                break;
+                            }
+                        }
+                    }
+                    RevocationOutcome::Close(reason) => {
+                        let _ = socket
+                            .send(WsMessage::Close(Some(CloseFrame {
+                                code: close_code::POLICY,
+                                reason: reason.into(),
+                            })))
+                            .await;
+
+                        break;
+                    }
+                }
+            }
+
+            Some(event) = rx.recv() => {
+                let text = match serde_json::to_string(&event) {
+                    Ok(text) => text,
+                    Err(_) => continue,
+                };
+
+                if socket.send(WsMessage::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+
+            incoming = socket.recv() => {
+                let text = match incoming {
+                    Some(Ok(WsMessage::Text(text))) => text,
+                    Some(Ok(WsMessage::Close(_))) | None | Some(Err(_)) => break,
+                    // pings are answered by axum, binary frames are not part of the protocol
+                    Some(Ok(_)) => continue,
+                };
+
+                let notice = match serde_json::from_str::<ClientCommand>(text.as_str()) {
+                    Ok(ClientCommand::Subscribe { room_id }) => {
+                        Some(handle_subscribe(&state, user_id, room_id, &tx, &mut subscriptions).await)
+                    }
+                    Ok(ClientCommand::Unsubscribe { room_id }) => {
+                        unsubscribe_room(&state, room_id, &mut subscriptions).await;
+
+                        Some(ServerNotice {
+                            event: "unsubscribed".to_string(),
+                            room_id: Some(room_id),
+                            response_message: "Unsubscribed from room successfully".to_string(),
+                        })
+                    }
+                    Ok(ClientCommand::Heartbeat { status }) => {
+                        handle_heartbeat(&state, user_id, status.unwrap_or(PresenceStatus::Online)).await
+                    }
+                    Ok(ClientCommand::Typing { room_id, is_typing }) => {
+                        // typing signals are only relayed to rooms this connection is subscribed to
+                        if subscriptions.contains_key(&room_id) {
+                            state.hub.publish(
+                                room_id,
+                                RealtimeEventType::UserTyping,
+                                &TypingSignal { user_id, is_typing },
+                            );
+
+                            None
+                        } else {
+                            Some(ServerNotice {
+                                event: "error".to_string(),
+                                room_id: Some(room_id),
+                                response_message: "Subscribe to the room before sending typing signals".to_string(),
+                            })
+                        }
+                    }
+                    Err(e) => Some(ServerNotice {
+                        event: "error".to_string(),
+                        room_id: None,
+                        response_message: format!("Invalid command: {}", e),
+                    }),
+                };
+
+                if let Some(notice) = notice
+                    && let Ok(text) = serde_json::to_string(&notice)
+                    && socket.send(WsMessage::Text(text.into())).await.is_err()
+                {
+                    break;
+                }
+            }
+        }
+    }
+
+    for (room_id, handle) in subscriptions {
+        handle.abort();
+        // wait for the aborted forwarder so its receiver is dropped before releasing the room
+        let _ = handle.await;
+        state.hub.release(room_id);
+    }
+
+    if let Some(status) = state.presence.disconnect(user_id) {
+        publish_presence(&state, user_id, status).await;
+    }
+
+    info!("WEBSOCKET DISCONNECTED: USER {}", user_id);
+}
+
+async fn handle_heartbeat(
+    state: &AppState,
+    user_id: i64,
+    status: PresenceStatus,
+) -> Option<ServerNotice> {
+    // going offline is signalled by closing the connection, not by a heartbeat
+    if status == PresenceStatus::Offline {
+        return Some(ServerNotice {
+            event: "error".to_string(),
+            room_id: None,
+            response_message: "Heartbeat status must be 'online' or 'away'".to_string(),
+        });
+    }
+
+    if let Some(status) = state.presence.heartbeat(user_id, status) {
+        publish_presence(state, user_id, status).await;
+    }
+
+    None
+}
+
+async fn handle_subscribe(
+    state: &AppState,
+    user_id: i64,
+    room_id: i64,
+    tx: &mpsc::Sender<RealtimeEvent>,
+    subscriptions: &mut HashMap<i64, JoinHandle<()>>,
+) -> ServerNotice {
+    if subscriptions.contains_key(&room_id) {
+        return ServerNotice {
+            event: "subscribed".to_string(),
+            room_id: Some(room_id),
+            response_message: "Already subscribed to room".to_string(),
+        };
+    }
+
+    let membership = sqlx::query_scalar::<_, i64>(
+        "SELECT id FROM room_members WHERE room_id = $1 AND user_id = $2",
+    )
+    .bind(room_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await;
+
+    match membership {
+        Ok(Some(_)) => {
+            subscriptions.insert(room_id, subscribe_room(state, room_id, tx.clone()));
+
+            ServerNotice {
+                event: "subscribed".to_string(),
+                room_id: Some(room_id),
+                response_message: "Subscribed to room successfully".to_string(),
+            }
+        }
+        Ok(None) => {
+            error!("WEBSOCKET SUBSCRIPTION REJECTED: USER IS NOT A ROOM MEMBER!");
+
+            ServerNotice {
+                event: "error".to_string(),
+                room_id: Some(room_id),
+                response_message: "You are not a member of this room".to_string(),
+            }
+        }
+        Err(e) => {
+            error!("FAILED TO VERIFY ROOM MEMBERSHIP: {}", e);
+
+            ServerNotice {
+                event: "error".to_string(),
+                room_id: Some(room_id),
+                response_message: "Failed to verify room membership".to_string(),
+            }
+        }
+    }
+}
+
+/// Stops forwarding the room's events. Returns whether the connection was subscribed at all.
+async fn unsubscribe_room(
+    state: &AppState,
+    room_id: i64,
+    subscriptions: &mut HashMap<i64, JoinHandle<()>>,
+) -> bool {
+    let Some(handle) = subscriptions.remove(&room_id) else {
+        return false;
+    };
+
+    handle.abort();
+    // wait for the aborted forwarder so its receiver is dropped before releasing the room
+    let _ = handle.await;
+    state.hub.release(room_id);
+
+    true
+}
+
+fn subscribe_room(
+    state: &AppState,
+    room_id: i64,
+    tx: mpsc::Sender<RealtimeEvent>,
+) -> JoinHandle<()> {
+    let mut receiver = state.hub.subscribe(room_id);
+
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    if tx.send(event).await.is_err() {
+                        break;
+                    }
+                }
+                // a slow client misses the oldest events rather than stalling the room
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn closes(revocation: AccessRevocation, user_id: i64, session_id: Option<i64>) -> bool {
+        matches!(
+            revocation_outcome(revocation, user_id, session_id),
+            RevocationOutcome::Close(_)
+        )
+    }
+
+    #[test]
+    fn removed_members_only_leave_that_room() {
+        let removal = AccessRevocation::RoomMembership {
+            room_id: 7,
+            user_id: 1,
+        };
+
+        assert!(matches!(
+            revocation_outcome(removal, 1, Some(10)),
+            RevocationOutcome::LeaveRoom(7)
+        ));
+        assert!(matches!(
+            revocation_outcome(removal, 2, Some(20)),
+            RevocationOutcome::Unaffected
+        ));
+    }
+
+    #[test]
+    fn deactivation_closes_every_connection_of_the_user() {
+        let deactivation = AccessRevocation::User { user_id: 1 };
+
+        assert!(closes(deactivation, 1, Some(10)));
+        assert!(closes(deactivation, 1, None));
+        assert!(!closes(deactivation, 2, Some(20)));
+    }
+
+    #[test]
+    fn revoked_sessions_close_their_own_connections() {
+        let single = AccessRevocation::Session { session_id: 10 };
+        assert!(closes(single, 1, Some(10)));
+        assert!(!closes(single, 1, Some(11)));
+
+        let others = AccessRevocation::Sessions {
+            user_id: 1,
+            except: Some(10),
+        };
+        assert!(!closes(others, 1, Some(10)));
+        assert!(closes(others, 1, Some(11)));
+        assert!(!closes(others, 2, Some(20)));
+
+        let everywhere = AccessRevocation::Sessions {
+            user_id: 1,
+            except: None,
+        };
+        assert!(closes(everywhere, 1, Some(10)));
+    }
+}
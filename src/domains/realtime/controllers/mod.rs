@@ -0,0 +1 @@
+pub mod connect_websocket;
@@ -0,0 +1,14 @@
+use crate::AppState;
+use crate::domains::realtime::controllers::connect_websocket::connect_websocket;
+use crate::middlewares::middleware_stack::{with_auth_layers, with_verified_email};
+use axum::Router;
+use axum::routing::get;
+use tower_cookies::CookieManagerLayer;
+
+pub fn realtime_routes(state: &AppState) -> Router<AppState> {
+    // typing signals go over the socket, so it's messaging as much as the http routes are
+    let protected_routes =
+        Router::new().route("/", with_verified_email(get(connect_websocket), state));
+
+    with_auth_layers(protected_routes, state).layer(CookieManagerLayer::new())
+}
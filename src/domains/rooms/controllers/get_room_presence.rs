@@ -0,0 +1,43 @@
+use crate::AppState;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::models::user::MemberPresence;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::{Extension, Path, State};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    count: usize,
+    members: Vec<MemberPresence>,
+}
+
+pub async fn get_room_presence(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Path(room_id): Path<i64>,
+) -> ApiResult<ResponseCore> {
+    // Only members of the room can see who else is around
+    state
+        .repos
+        .rooms
+        .member(room_id, session.user.id)
+        .await
+        .context("Failed to verify room membership")?
+        .ok_or_else(|| AppError::Forbidden("You are not a member of this room".into()))?;
+
+    let members = state
+        .repos
+        .rooms
+        .presence(room_id)
+        .await
+        .context("Failed to retrieve room presence")?;
+
+    Ok(ApiResponse::ok(
+        "Room presence retrieved successfully",
+        ResponseCore {
+            count: members.len(),
+            members,
+        },
+    ))
+}
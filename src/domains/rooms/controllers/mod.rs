@@ -10,6 +10,7 @@ pub mod get_all_open_rooms;
 pub mod get_all_private_rooms;
 pub mod get_all_rooms;
 pub mod get_room;
+pub mod get_room_presence;
 pub mod get_user_rooms;
 pub mod remove_room_admin;
 pub mod remove_room_member;
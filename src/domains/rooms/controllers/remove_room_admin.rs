@@ -1,118 +1,23 @@
 use crate::AppState;
+use crate::domains::rooms::service;
+use crate::utils::api_response::{ApiResponse, ApiResult};
 use axum::{
     Json,
     extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
 };
-use serde::{Deserialize, Serialize};
-use tracing::error;
+use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
 pub struct RemoveAdminPayload {
     pub user_id: i64,
 }
 
-#[derive(Debug, Serialize)]
-pub struct Response {
-    response_message: String,
-    error: Option<String>,
-}
-
 pub async fn remove_room_admin(
     State(state): State<AppState>,
     Path(room_id): Path<i64>,
     Json(payload): Json<RemoveAdminPayload>,
-) -> impl IntoResponse {
-    let user_exists = sqlx::query(
-        r#"
-        SELECT 1
-        FROM users
-        WHERE id = $1
-        "#,
-    )
-    .bind(payload.user_id)
-    .fetch_one(&state.db)
-    .await;
-
-    if user_exists.is_err() {
-        return (
-            StatusCode::NOT_FOUND,
-            Json(Response {
-                response_message: format!(
-                    "User with id: '{}' not found od does not exist",
-                    payload.user_id
-                ),
-                error: Some("Member does not exist or room not found".into()),
-            }),
-        );
-    }
-
-    let room_exists = sqlx::query(
-        r#"
-        SELECT 1
-        FROM rooms
-        WHERE id = $1
-        "#,
-    )
-    .bind(room_id)
-    .fetch_one(&state.db)
-    .await;
-
-    if room_exists.is_err() {
-        return (
-            StatusCode::NOT_FOUND,
-            Json(Response {
-                response_message: format!(
-                    "Room with id: '{}' not found or does not exist",
-                    room_id
-                ),
-                error: Some("Room not found".into()),
-            }),
-        );
-    }
-
-    let result = sqlx::query(
-        r#"
-        UPDATE room_members 
-        SET role = 'member' 
-        WHERE room_id = $1 AND user_id = $2
-        "#,
-    )
-    .bind(room_id)
-    .bind(payload.user_id)
-    .execute(&state.db)
-    .await;
+) -> ApiResult<()> {
+    service::set_admin(&state.repos, room_id, payload.user_id, false).await?;
 
-    match result {
-        Ok(query_result) => {
-            if query_result.rows_affected() == 0 {
-                (
-                    StatusCode::NOT_FOUND,
-                    Json(Response {
-                        response_message: "User not found in this room".into(),
-                        error: Some("Member does not exist or room not found".into()),
-                    }),
-                )
-            } else {
-                (
-                    StatusCode::OK,
-                    Json(Response {
-                        response_message: "Room admin removed successfully".into(),
-                        error: None,
-                    }),
-                )
-            }
-        }
-        Err(e) => {
-            error!("REMOVE ROOM ADMIN REQUEST FAILED");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(Response {
-                    response_message: "Failed to remove room admin".into(),
-                    error: Some(format!("Database error: {}", e)),
-                }),
-            )
-        }
-    }
+    Ok(ApiResponse::message("Room admin removed successfully"))
 }
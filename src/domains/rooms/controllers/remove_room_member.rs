@@ -1,137 +1,30 @@
 use crate::AppState;
+use crate::domains::rooms::service;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::realtime_hub::AccessRevocation;
 use axum::{
     Json,
     extract::{Path, State},
-    http::StatusCode,
-    response::IntoResponse,
 };
-use serde::{Deserialize, Serialize};
-use tracing::error;
+use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
 pub struct RemoveMemberPayload {
     pub user_id: i64,
 }
 
-#[derive(Debug, Serialize)]
-pub struct Response {
-    response_message: String,
-    error: Option<String>,
-}
-
 pub async fn remove_room_member(
     State(state): State<AppState>,
     Path(room_id): Path<i64>,
     Json(payload): Json<RemoveMemberPayload>,
-) -> impl IntoResponse {
-    let user_exists = sqlx::query(
-        r#"
-        SELECT 1
-        FROM users
-        WHERE id = $1
-        "#,
-    )
-    .bind(payload.user_id)
-    .fetch_one(&state.db)
-    .await;
-
-    if user_exists.is_err() {
-        return (
-            StatusCode::NOT_FOUND,
-            Json(Response {
-                response_message: format!(
-                    "User with id: '{}' not found od does not exist",
-                    payload.user_id
-                ),
-                error: Some("Member does not exist or room not found".into()),
-            }),
-        );
-    }
-
-    let room_exists = sqlx::query(
-        r#"
-        SELECT 1
-        FROM rooms
-        WHERE id = $1
-        "#,
-    )
-    .bind(room_id)
-    .fetch_one(&state.db)
-    .await;
-
-    if room_exists.is_err() {
-        return (
-            StatusCode::NOT_FOUND,
-            Json(Response {
-                response_message: format!(
-                    "Room with id: '{}' not found or does not exist",
-                    room_id
-                ),
-                error: Some("Room not found".into()),
-            }),
-        );
-    }
-
-    let result = sqlx::query(
-        r#"
-        DELETE FROM room_members 
-        WHERE room_id = $1 AND user_id = $2
-        "#,
-    )
-    .bind(room_id)
-    .bind(payload.user_id)
-    .execute(&state.db)
-    .await;
-
-    match result {
-        Ok(query_result) => {
-            if query_result.rows_affected() == 0 {
-                (
-                    StatusCode::NOT_FOUND,
-                    Json(Response {
-                        response_message: "Member not found in this room".into(),
-                        error: None,
-                    }),
-                )
-            } else {
-                // Update the rooms table to remove the member from co_members array
-                let update_result = sqlx::query(
-                    "UPDATE rooms SET co_members = array_remove(co_members, $1) WHERE id = $2",
-                )
-                .bind(payload.user_id)
-                .bind(room_id)
-                .execute(&state.db)
-                .await;
+) -> ApiResult<()> {
+    service::remove_member(&state.repos, room_id, payload.user_id).await?;
 
-                if let Err(e) = update_result {
-                    error!("Failed to update rooms co_members: {}", e);
-                    return (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(Response {
-                            response_message: "Failed to update room details".into(),
-                            error: Some(format!("Database error: {}", e)),
-                        }),
-                    );
-                }
+    // the removed member's open websockets stop receiving the room's events
+    state.hub.revoke(AccessRevocation::RoomMembership {
+        room_id,
+        user_id: payload.user_id,
+    });
 
-                (
-                    StatusCode::OK,
-                    Json(Response {
-                        response_message: "Member removed successfully".into(),
-                        error: None,
-                    }),
-                )
-            }
-        }
-        Err(e) => {
-            error!("REMOVE ROOM MEMBER REQUEST FAILED");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(Response {
-                    response_message: "Failed to remove room member".into(),
-                    error: Some(format!("Database error: {}", e)),
-                }),
-            )
-        }
-    }
+    Ok(ApiResponse::message("Member removed successfully"))
 }
@@ -1,2 +1,3 @@
 mod controllers;
 pub mod router;
+pub mod service;
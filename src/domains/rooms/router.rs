@@ -12,6 +12,7 @@ use crate::domains::rooms::controllers::get_all_open_rooms::get_all_open_rooms;
 use crate::domains::rooms::controllers::get_all_private_rooms::get_all_private_rooms;
 use crate::domains::rooms::controllers::get_all_rooms::get_all_rooms;
 use crate::domains::rooms::controllers::get_room::get_room;
+use crate::domains::rooms::controllers::get_room_presence::get_room_presence;
 use crate::domains::rooms::controllers::get_user_rooms::get_user_rooms;
 use crate::domains::rooms::controllers::unarchive_room::unarchive_room;
 use crate::domains::rooms::controllers::unbookmark_room::unbookmark_room;
@@ -22,14 +23,13 @@ use crate::domains::rooms::controllers::unpin_room::unpin_room;
 use crate::domains::rooms::controllers::add_room_admin::add_room_admin;
 use crate::domains::rooms::controllers::remove_room_admin::remove_room_admin;
 use crate::domains::rooms::controllers::remove_room_member::remove_room_member;
-use crate::middlewares::auth_access_middleware::access_middleware;
-use crate::middlewares::auth_sessions_middleware::sessions_middleware;
+use crate::middlewares::middleware_stack::with_auth_layers;
 use axum::routing::{get, patch, post};
-use axum::{Router, middleware};
+use axum::Router;
 use tower_cookies::CookieManagerLayer;
 
 pub fn rooms_routes(state: &AppState) -> Router<AppState> {
-    Router::new()
+    let protected_routes = Router::new()
         .route("/create-private-chat-room", post(create_room))
         .route("/create-group-chat-room", post(create_group))
         .route("/update-room/{room_id}", patch(update_room))
@@ -38,6 +38,7 @@ pub fn rooms_routes(state: &AppState) -> Router<AppState> {
             patch(update_room_profile_image),
         )
         .route("/get-room/{room_id}", get(get_room))
+        .route("/get-room-presence/{room_id}", get(get_room_presence))
         .route("/get-all-rooms", get(get_all_rooms))
         .route("/get-user-rooms/{user_id}", get(get_user_rooms))
         .route("/get-all-group-rooms", get(get_all_group_rooms))
@@ -53,14 +54,8 @@ pub fn rooms_routes(state: &AppState) -> Router<AppState> {
         .route("/add-room-member/{room_id}", post(add_room_member))
         .route("/remove-room-member/{room_id}", post(remove_room_member))
         .route("/add-room-admin/{room_id}", patch(add_room_admin))
-        .route("/remove-room-admin/{room_id}", patch(remove_room_admin))
-        .route_layer(middleware::from_fn_with_state(
-            state.clone(),
-            access_middleware,
-        ))
-        .route_layer(middleware::from_fn_with_state(
-            state.clone(),
-            sessions_middleware,
-        ))
+        .route("/remove-room-admin/{room_id}", patch(remove_room_admin));
+
+    with_auth_layers(protected_routes, state)
         .layer(CookieManagerLayer::new())
 }
@@ -0,0 +1,384 @@
+use crate::models::room::{NewRoom, Room, RoomRole};
+use crate::models::user::UserProfile;
+use crate::repositories::Repositories;
+use crate::utils::app_error::{AppError, ErrorContext};
+use crate::utils::current_time_in_milliseconds::current_time_millis;
+
+async fn find_user(
+    repos: &Repositories,
+    user_id: i64,
+    context: &str,
+) -> Result<UserProfile, AppError> {
+    repos
+        .users
+        .find(user_id)
+        .await
+        .context(context)?
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "User with id: '{}' not found or does not exist",
+                user_id
+            ))
+        })
+}
+
+async fn find_room(repos: &Repositories, room_id: i64, context: &str) -> Result<Room, AppError> {
+    repos
+        .rooms
+        .find(room_id)
+        .await
+        .context(context)?
+        .ok_or_else(|| {
+            AppError::NotFound(format!(
+                "Room with id: '{}' not found or does not exist",
+                room_id
+            ))
+        })
+}
+
+/// Opens a 1-on-1 room named after the co-member. Self rooms are what spaces are for, and
+/// each pair only gets one room.
+pub async fn create_private_room(
+    repos: &Repositories,
+    created_by: i64,
+    co_member_id: i64,
+) -> Result<Room, AppError> {
+    if co_member_id == created_by {
+        return Err(AppError::BadRequest(
+            "Self room creation not permitted! Use spaces for self messaging".into(),
+        ));
+    }
+
+    let co_member = repos
+        .users
+        .find(co_member_id)
+        .await
+        .context("Room creation error")?
+        .ok_or_else(|| {
+            AppError::NotFound(format!("Co-member with id: '{}' not found", co_member_id))
+        })?;
+
+    let existing_room = repos
+        .rooms
+        .find_private(created_by, co_member.id)
+        .await
+        .context("Room creation error")?;
+
+    if existing_room.is_some() {
+        return Err(AppError::Conflict(
+            "Room already exists: cannot create duplicate 1-on-1 room".into(),
+        ));
+    }
+
+    let room = repos
+        .rooms
+        .create(&NewRoom {
+            room_name: co_member.full_name,
+            is_group: false,
+            created_by,
+            co_member: Some(co_member.id),
+            co_members: Vec::new(),
+        })
+        .await
+        .context("Room creation error")?;
+
+    let joined_at = current_time_millis().to_string();
+
+    repos
+        .rooms
+        .add_member(room.id, created_by, RoomRole::Admin, &joined_at)
+        .await
+        .context("Failed to create admin room member")?;
+
+    repos
+        .rooms
+        .add_member(room.id, co_member.id, RoomRole::Member, &joined_at)
+        .await
+        .context("Failed to create room co_member")?;
+
+    Ok(room)
+}
+
+/// Opens a group room with the creator as its admin. Every co-member has to exist, and the
+/// creator can't list themselves.
+pub async fn create_group_room(
+    repos: &Repositories,
+    created_by: i64,
+    room_name: String,
+    co_members: Vec<i64>,
+) -> Result<Room, AppError> {
+    let mut unique_members = Vec::with_capacity(co_members.len());
+
+    for member in co_members {
+        if member == created_by {
+            return Err(AppError::BadRequest("Self in co-members array!".into()));
+        }
+
+        repos
+            .users
+            .find(member)
+            .await
+            .context("Room creation error")?
+            .ok_or_else(|| {
+                AppError::NotFound(format!("Co-member with id: '{}' not found", member))
+            })?;
+
+        if !unique_members.contains(&member) {
+            unique_members.push(member);
+        }
+    }
+
+    let room = repos
+        .rooms
+        .create(&NewRoom {
+            room_name,
+            is_group: true,
+            created_by,
+            co_member: None,
+            co_members: unique_members.clone(),
+        })
+        .await
+        .context("Room creation error")?;
+
+    let joined_at = current_time_millis().to_string();
+
+    repos
+        .rooms
+        .add_member(room.id, created_by, RoomRole::Admin, &joined_at)
+        .await
+        .context("Failed to create room member")?;
+
+    for member in unique_members {
+        repos
+            .rooms
+            .add_member(room.id, member, RoomRole::Member, &joined_at)
+            .await
+            .context("Failed to create room member")?;
+    }
+
+    Ok(room)
+}
+
+/// Adds a user to a room and to its co-members list. `role` defaults to "member".
+pub async fn add_member(
+    repos: &Repositories,
+    room_id: i64,
+    user_id: i64,
+    role: Option<&str>,
+) -> Result<(), AppError> {
+    let role = match role {
+        None => RoomRole::Member,
+        Some(role) => RoomRole::parse(role).ok_or_else(|| {
+            AppError::BadRequest("Invalid role: role must be 'admin' or 'member'".into())
+        })?,
+    };
+
+    find_user(repos, user_id, "Failed to add room member").await?;
+    let room = find_room(repos, room_id, "Failed to add room member").await?;
+
+    let joined_at = current_time_millis().to_string();
+    let added = repos
+        .rooms
+        .add_member(room.id, user_id, role, &joined_at)
+        .await
+        .context("Failed to add room member")?;
+
+    if !added {
+        return Err(AppError::Conflict(format!(
+            "User with id: '{}' is already a member of this room",
+            user_id
+        )));
+    }
+
+    repos
+        .rooms
+        .add_co_member(room.id, user_id)
+        .await
+        .context("Failed to update room details")?;
+
+    Ok(())
+}
+
+/// Removes a user from a room and from its co-members list.
+pub async fn remove_member(
+    repos: &Repositories,
+    room_id: i64,
+    user_id: i64,
+) -> Result<(), AppError> {
+    find_user(repos, user_id, "Failed to remove room member").await?;
+    let room = find_room(repos, room_id, "Failed to remove room member").await?;
+
+    let removed = repos
+        .rooms
+        .remove_member(room.id, user_id)
+        .await
+        .context("Failed to remove room member")?;
+
+    if !removed {
+        return Err(AppError::NotFound("Member not found in this room".into()));
+    }
+
+    repos
+        .rooms
+        .remove_co_member(room.id, user_id)
+        .await
+        .context("Failed to update room details")?;
+
+    Ok(())
+}
+
+/// Promotes a member to room admin, or demotes them back to a plain member.
+pub async fn set_admin(
+    repos: &Repositories,
+    room_id: i64,
+    user_id: i64,
+    is_admin: bool,
+) -> Result<(), AppError> {
+    let context = if is_admin {
+        "Failed to add room admin"
+    } else {
+        "Failed to remove room admin"
+    };
+    let role = if is_admin {
+        RoomRole::Admin
+    } else {
+        RoomRole::Member
+    };
+
+    find_user(repos, user_id, context).await?;
+    let room = find_room(repos, room_id, context).await?;
+
+    let updated = repos
+        .rooms
+        .set_member_role(room.id, user_id, role)
+        .await
+        .context(context)?;
+
+    if !updated {
+        return Err(AppError::NotFound("User not found in this room".into()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn private_rooms_are_named_after_the_co_member() {
+        let (repos, store) = Repositories::in_memory();
+        let ada = store.insert_user("Ada").id;
+        let bob = store.insert_user("Bob").id;
+
+        let room = create_private_room(&repos, ada, bob).await.unwrap();
+
+        assert_eq!(room.room_name.as_deref(), Some("Bob"));
+        assert_eq!(room.co_member, Some(bob));
+
+        let roles: Vec<(i64, String)> = repos
+            .rooms
+            .members(room.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|member| (member.user_id, member.role))
+            .collect();
+        assert_eq!(
+            roles,
+            vec![(ada, "admin".to_string()), (bob, "member".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn private_rooms_reject_self_missing_and_duplicate_co_members() {
+        let (repos, store) = Repositories::in_memory();
+        let ada = store.insert_user("Ada").id;
+        let bob = store.insert_user("Bob").id;
+
+        let err = create_private_room(&repos, ada, ada).await.unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(_)));
+
+        let err = create_private_room(&repos, ada, 999).await.unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+
+        create_private_room(&repos, ada, bob).await.unwrap();
+        let err = create_private_room(&repos, ada, bob).await.unwrap_err();
+        assert!(matches!(err, AppError::Conflict(_)));
+    }
+
+    #[tokio::test]
+    async fn group_rooms_validate_their_co_members() {
+        let (repos, store) = Repositories::in_memory();
+        let ada = store.insert_user("Ada").id;
+        let bob = store.insert_user("Bob").id;
+
+        let err = create_group_room(&repos, ada, "Team".to_string(), vec![bob, ada])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(_)));
+
+        let err = create_group_room(&repos, ada, "Team".to_string(), vec![bob, 999])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+
+        let room = create_group_room(&repos, ada, "Team".to_string(), vec![bob, bob])
+            .await
+            .unwrap();
+        assert_eq!(room.co_members, Some(vec![bob]));
+        assert_eq!(repos.rooms.members(room.id).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn adding_and_removing_members_keeps_co_members_in_sync() {
+        let (repos, store) = Repositories::in_memory();
+        let ada = store.insert_user("Ada").id;
+        let bob = store.insert_user("Bob").id;
+        let cy = store.insert_user("Cy").id;
+        let room = create_group_room(&repos, ada, "Team".to_string(), vec![bob])
+            .await
+            .unwrap();
+
+        let err = add_member(&repos, room.id, cy, Some("owner"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::BadRequest(_)));
+
+        add_member(&repos, room.id, cy, None).await.unwrap();
+        let err = add_member(&repos, room.id, cy, None).await.unwrap_err();
+        assert!(matches!(err, AppError::Conflict(_)));
+
+        let room_after_add = repos.rooms.find(room.id).await.unwrap().unwrap();
+        assert_eq!(room_after_add.co_members, Some(vec![bob, cy]));
+
+        remove_member(&repos, room.id, bob).await.unwrap();
+        let err = remove_member(&repos, room.id, bob).await.unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+
+        let room_after_remove = repos.rooms.find(room.id).await.unwrap().unwrap();
+        assert_eq!(room_after_remove.co_members, Some(vec![cy]));
+    }
+
+    #[tokio::test]
+    async fn only_members_can_be_made_room_admins() {
+        let (repos, store) = Repositories::in_memory();
+        let ada = store.insert_user("Ada").id;
+        let bob = store.insert_user("Bob").id;
+        let cy = store.insert_user("Cy").id;
+        let room = create_group_room(&repos, ada, "Team".to_string(), vec![bob])
+            .await
+            .unwrap();
+
+        let err = set_admin(&repos, room.id, cy, true).await.unwrap_err();
+        assert!(matches!(err, AppError::NotFound(_)));
+
+        set_admin(&repos, room.id, bob, true).await.unwrap();
+        let member = repos.rooms.member(room.id, bob).await.unwrap().unwrap();
+        assert_eq!(member.role, "admin");
+
+        set_admin(&repos, room.id, bob, false).await.unwrap();
+        let member = repos.rooms.member(room.id, bob).await.unwrap().unwrap();
+        assert_eq!(member.role, "member");
+    }
+}
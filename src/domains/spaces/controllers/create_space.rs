@@ -0,0 +1,72 @@
+use crate::AppState;
+use crate::domains::spaces::space_records::Space;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use crate::utils::current_time_in_milliseconds::current_time_millis;
+use axum::{
+    Json,
+    extract::{Extension, State},
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSpacePayload {
+    pub space_name: String,
+    pub space_description: Option<String>,
+    pub space_theme: Option<String>,
+}
+
+pub async fn create_space(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Json(payload): Json<CreateSpacePayload>,
+) -> ApiResult<Space> {
+    let owner_id = session.user.id;
+    let space_name = payload.space_name.trim();
+
+    if space_name.is_empty() {
+        return Err(AppError::BadRequest("Space name is required".to_string()));
+    }
+
+    // 1. The space and its owner's membership go in together, a space its owner can't post into
+    // is no use to anyone
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .context("Failed to create space")?;
+
+    let space = sqlx::query_as::<_, Space>(
+        r#"
+        INSERT INTO rooms (room_name, is_group, is_space, created_by, space_description, space_theme)
+        VALUES ($1, FALSE, TRUE, $2, $3, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(space_name)
+    .bind(owner_id)
+    .bind(&payload.space_description)
+    .bind(&payload.space_theme)
+    .fetch_one(&mut *tx)
+    .await
+    .context("Failed to create space")?;
+
+    // 2. The owner is the only member, which lets the messages domain accept their posts
+    sqlx::query(
+        r#"
+        INSERT INTO room_members (room_id, user_id, role, joined_at)
+        VALUES ($1, $2, 'admin', $3)
+        "#,
+    )
+    .bind(space.id)
+    .bind(owner_id)
+    .bind(current_time_millis().to_string())
+    .execute(&mut *tx)
+    .await
+    .context("Failed to create space owner membership")?;
+
+    tx.commit().await.context("Failed to create space")?;
+
+    Ok(ApiResponse::created("Space created successfully", space))
+}
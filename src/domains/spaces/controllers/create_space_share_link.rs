@@ -0,0 +1,48 @@
+use crate::AppState;
+use crate::domains::spaces::space_records::Space;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::{Extension, Path, State};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    share_token: String,
+    share_path: String,
+    space: Space,
+}
+
+pub async fn create_space_share_link(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Path(space_id): Path<i64>,
+) -> ApiResult<ResponseCore> {
+    // a fresh token on every call, so re-sharing also invalidates any previously handed out link
+    let share_token = uuid::Uuid::new_v4().simple().to_string();
+
+    let space = sqlx::query_as::<_, Space>(
+        r#"
+        UPDATE rooms
+        SET share_token = $1, updated_at = NOW()
+        WHERE id = $2 AND created_by = $3 AND is_space = TRUE
+        RETURNING *
+        "#,
+    )
+    .bind(&share_token)
+    .bind(space_id)
+    .bind(session.user.id)
+    .fetch_optional(&state.db)
+    .await
+    .context("Failed to create space share link")?
+    .ok_or_else(|| AppError::NotFound("Space not found".to_string()))?;
+
+    Ok(ApiResponse::ok(
+        "Space share link created successfully",
+        ResponseCore {
+            share_path: format!("/api/v1/spaces/get-shared-space/{}", share_token),
+            share_token,
+            space,
+        },
+    ))
+}
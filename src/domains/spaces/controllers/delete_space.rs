@@ -0,0 +1,26 @@
+use crate::AppState;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::{Extension, Path, State};
+
+pub async fn delete_space(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Path(space_id): Path<i64>,
+) -> ApiResult<()> {
+    // messages, receipts and memberships of the space are removed by cascade
+    let result =
+        sqlx::query("DELETE FROM rooms WHERE id = $1 AND created_by = $2 AND is_space = TRUE")
+            .bind(space_id)
+            .bind(session.user.id)
+            .execute(&state.db)
+            .await
+            .context("Failed to delete space")?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Space not found".to_string()));
+    }
+
+    Ok(ApiResponse::message("Space deleted successfully"))
+}
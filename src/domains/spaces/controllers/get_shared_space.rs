@@ -0,0 +1,134 @@
+use crate::AppState;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use crate::utils::pagination_cursor::Cursor;
+use axum::extract::{Path, Query, State};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+// default and maximum page sizes for shared space listings
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 100;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SharedSpace {
+    pub id: i64,
+    #[serde(rename = "space_name")]
+    pub room_name: Option<String>,
+    pub space_description: Option<String>,
+    pub space_theme: Option<String>,
+    pub room_profile_image: Option<String>,
+    pub updated_at: NaiveDateTime,
+    #[serde(skip_serializing)]
+    pub created_by: Option<i64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SharedMessage {
+    pub id: i64,
+    #[sqlx(rename = "type")]
+    pub message_type: String,
+    pub text_content: Option<String>,
+    pub attachment_1: Option<String>,
+    pub attachment_2: Option<String>,
+    pub attachment_3: Option<String>,
+    pub attachment_4: Option<String>,
+    pub sent_at: String,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    space: SharedSpace,
+    count: usize,
+    messages: Option<Vec<SharedMessage>>,
+    has_more: bool,
+    prev_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SearchParams {
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+/// Read-only view of a space for anyone holding its share token - no session required.
+pub async fn get_shared_space(
+    State(state): State<AppState>,
+    Path(share_token): Path<String>,
+    Query(params): Query<SearchParams>,
+) -> ApiResult<ResponseCore> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_PAGE_LIMIT)
+        .clamp(1, MAX_PAGE_LIMIT);
+
+    // shared spaces only scroll back in time
+    let before = match params.cursor.as_deref().map(Cursor::decode) {
+        None => None,
+        Some(Some(Cursor::Before(before))) => Some(before),
+        Some(_) => {
+            return Err(AppError::BadRequest(
+                "Invalid pagination cursor".to_string(),
+            ));
+        }
+    };
+
+    // 1. Resolve the space from its share token
+    let space = sqlx::query_as::<_, SharedSpace>(
+        "SELECT * FROM rooms WHERE share_token = $1 AND is_space = TRUE",
+    )
+    .bind(&share_token)
+    .fetch_optional(&state.db)
+    .await
+    .context("Failed to fetch shared space")?
+    .ok_or_else(|| {
+        AppError::NotFound("Shared space not found or link has been revoked".to_string())
+    })?;
+
+    // 2. Latest page of the space, fetching one extra row to know whether more exist. Messages the
+    // owner deleted for themselves stay hidden from everyone they share the space with
+    let mut msgs = sqlx::query_as::<_, SharedMessage>(
+        r#"
+        SELECT *
+        FROM messages
+        WHERE room_id = $1
+            AND ($2::BIGINT IS NULL OR id < $2)
+            AND NOT EXISTS (
+                SELECT 1 FROM message_deletions md
+                WHERE md.message_id = messages.id AND md.user_id = $4
+            )
+        ORDER BY id DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(space.id)
+    .bind(before)
+    .bind(limit + 1)
+    .bind(space.created_by)
+    .fetch_all(&state.db)
+    .await
+    .context("Failed to fetch shared space messages")?;
+
+    let has_more = msgs.len() as i64 > limit;
+    msgs.truncate(limit as usize);
+
+    // pages are always returned oldest-first
+    msgs.reverse();
+
+    let prev_cursor = match msgs.first() {
+        Some(msg) if has_more => Some(Cursor::Before(msg.id).encode()),
+        _ => None,
+    };
+
+    Ok(ApiResponse::ok(
+        "Shared space fetched successfully",
+        ResponseCore {
+            space,
+            count: msgs.len(),
+            messages: Some(msgs),
+            has_more,
+            prev_cursor,
+        },
+    ))
+}
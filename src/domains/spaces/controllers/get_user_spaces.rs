@@ -0,0 +1,81 @@
+use crate::AppState;
+use crate::domains::spaces::space_records::Space;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::ErrorContext;
+use axum::extract::{Extension, Query, State};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct GetUserSpacesParams {
+    pub search: Option<String>, // matches space name, description or message text
+    pub pinned: Option<bool>,
+    pub archived: Option<bool>, // archived spaces are hidden unless explicitly requested
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseCore {
+    count: usize,
+    spaces: Option<Vec<Space>>,
+}
+
+pub async fn get_user_spaces(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Query(params): Query<GetUserSpacesParams>,
+) -> ApiResult<ResponseCore> {
+    let search = params
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|search| !search.is_empty())
+        // a literal % or _ in the search shouldn't turn into a wildcard
+        .map(|search| {
+            let escaped = search
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{}%", escaped)
+        });
+
+    let spaces = sqlx::query_as::<_, Space>(
+        r#"
+        SELECT r.*
+        FROM rooms r
+        WHERE r.created_by = $1
+            AND r.is_space = TRUE
+            AND ($1 = ANY(r.archived_by)) = $2
+            AND ($3::BOOLEAN IS NULL OR ($1 = ANY(r.pinned_by)) = $3)
+            AND (
+                $4::TEXT IS NULL
+                OR r.room_name ILIKE $4 ESCAPE '\'
+                OR r.space_description ILIKE $4 ESCAPE '\'
+                OR EXISTS (
+                    SELECT 1 FROM messages m
+                    WHERE m.room_id = r.id
+                        AND m.text_content ILIKE $4 ESCAPE '\'
+                        AND NOT EXISTS (
+                            SELECT 1 FROM message_deletions md
+                            WHERE md.message_id = m.id AND md.user_id = $1
+                        )
+                )
+            )
+        ORDER BY ($1 = ANY(r.pinned_by)) DESC, r.updated_at DESC
+        "#,
+    )
+    .bind(session.user.id)
+    .bind(params.archived.unwrap_or(false))
+    .bind(params.pinned)
+    .bind(search)
+    .fetch_all(&state.db)
+    .await
+    .context("Failed to retrieve user spaces")?;
+
+    Ok(ApiResponse::ok(
+        "User spaces retrieved successfully",
+        ResponseCore {
+            count: spaces.len(),
+            spaces: Some(spaces),
+        },
+    ))
+}
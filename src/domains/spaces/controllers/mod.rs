@@ -0,0 +1,11 @@
+pub mod archive_space;
+pub mod create_space;
+pub mod create_space_share_link;
+pub mod delete_space;
+pub mod get_shared_space;
+pub mod get_user_spaces;
+pub mod pin_space;
+pub mod revoke_space_share_link;
+pub mod unarchive_space;
+pub mod unpin_space;
+pub mod update_space;
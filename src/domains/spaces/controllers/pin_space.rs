@@ -0,0 +1,34 @@
+use crate::AppState;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::{Extension, Path, State};
+
+pub async fn pin_space(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Path(space_id): Path<i64>,
+) -> ApiResult<()> {
+    let user_id = session.user.id;
+
+    // Use array_append to add user_id to pinned_by if not already present
+    sqlx::query_scalar::<_, i64>(
+        r#"
+        UPDATE rooms
+        SET pinned_by = CASE
+            WHEN $1 = ANY(pinned_by) THEN pinned_by
+            ELSE array_append(pinned_by, $1)
+        END
+        WHERE id = $2 AND created_by = $1 AND is_space = TRUE
+        RETURNING id
+        "#,
+    )
+    .bind(user_id)
+    .bind(space_id)
+    .fetch_optional(&state.db)
+    .await
+    .context("Failed to pin space")?
+    .ok_or_else(|| AppError::NotFound("Space not found".to_string()))?;
+
+    Ok(ApiResponse::message("Space pinned successfully"))
+}
@@ -0,0 +1,32 @@
+use crate::AppState;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::extract::{Extension, Path, State};
+
+pub async fn revoke_space_share_link(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Path(space_id): Path<i64>,
+) -> ApiResult<()> {
+    let result = sqlx::query(
+        r#"
+        UPDATE rooms
+        SET share_token = NULL, updated_at = NOW()
+        WHERE id = $1 AND created_by = $2 AND is_space = TRUE
+        "#,
+    )
+    .bind(space_id)
+    .bind(session.user.id)
+    .execute(&state.db)
+    .await
+    .context("Failed to revoke space share link")?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Space not found".to_string()));
+    }
+
+    Ok(ApiResponse::message(
+        "Space share link revoked successfully",
+    ))
+}
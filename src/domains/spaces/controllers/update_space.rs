@@ -0,0 +1,70 @@
+use crate::AppState;
+use crate::domains::spaces::space_records::{Space, fetch_owned_space};
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::api_response::{ApiResponse, ApiResult};
+use crate::utils::app_error::{AppError, ErrorContext};
+use axum::{
+    Json,
+    extract::{Extension, Path, State},
+};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSpacePayload {
+    pub space_name: Option<String>,
+    pub space_description: Option<String>,
+    pub space_theme: Option<String>,
+}
+
+pub async fn update_space(
+    State(state): State<AppState>,
+    Extension(session): Extension<SessionsMiddlewareOutput>,
+    Path(space_id): Path<i64>,
+    Json(payload): Json<UpdateSpacePayload>,
+) -> ApiResult<Space> {
+    if payload.space_name.is_none()
+        && payload.space_description.is_none()
+        && payload.space_theme.is_none()
+    {
+        return Err(AppError::BadRequest(
+            "No fields provided to update".to_string(),
+        ));
+    }
+
+    let space_name = payload.space_name.as_deref().map(str::trim);
+
+    if space_name.is_some_and(str::is_empty) {
+        return Err(AppError::BadRequest(
+            "Space name cannot be empty".to_string(),
+        ));
+    }
+
+    // 1. Only the owner can update a space
+    fetch_owned_space(&state, space_id, session.user.id)
+        .await
+        .context("Space update failed")?
+        .ok_or_else(|| AppError::NotFound("Space not found".to_string()))?;
+
+    // 2. Update only the provided fields
+    let space = sqlx::query_as::<_, Space>(
+        r#"
+        UPDATE rooms
+        SET
+            room_name = COALESCE($1, room_name),
+            space_description = COALESCE($2, space_description),
+            space_theme = COALESCE($3, space_theme),
+            updated_at = NOW()
+        WHERE id = $4
+        RETURNING *
+        "#,
+    )
+    .bind(space_name)
+    .bind(&payload.space_description)
+    .bind(&payload.space_theme)
+    .bind(space_id)
+    .fetch_one(&state.db)
+    .await
+    .context("Failed to update space")?;
+
+    Ok(ApiResponse::ok("Space updated successfully", space))
+}
@@ -0,0 +1,3 @@
+mod controllers;
+pub mod router;
+pub mod space_records;
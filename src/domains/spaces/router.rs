@@ -0,0 +1,41 @@
+use crate::AppState;
+use crate::domains::spaces::controllers::archive_space::archive_space;
+use crate::domains::spaces::controllers::create_space::create_space;
+use crate::domains::spaces::controllers::create_space_share_link::create_space_share_link;
+use crate::domains::spaces::controllers::delete_space::delete_space;
+use crate::domains::spaces::controllers::get_shared_space::get_shared_space;
+use crate::domains::spaces::controllers::get_user_spaces::get_user_spaces;
+use crate::domains::spaces::controllers::pin_space::pin_space;
+use crate::domains::spaces::controllers::revoke_space_share_link::revoke_space_share_link;
+use crate::domains::spaces::controllers::unarchive_space::unarchive_space;
+use crate::domains::spaces::controllers::unpin_space::unpin_space;
+use crate::domains::spaces::controllers::update_space::update_space;
+use crate::middlewares::middleware_stack::with_auth_layers;
+use axum::Router;
+use axum::routing::{delete, get, patch, post};
+use tower_cookies::CookieManagerLayer;
+
+pub fn spaces_routes(state: &AppState) -> Router<AppState> {
+    let protected_routes = Router::new()
+        .route("/create-space", post(create_space))
+        .route("/update-space/{space_id}", patch(update_space))
+        .route("/delete-space/{space_id}", delete(delete_space))
+        .route("/get-user-spaces", get(get_user_spaces))
+        .route("/pin-space/{space_id}", patch(pin_space))
+        .route("/unpin-space/{space_id}", patch(unpin_space))
+        .route("/archive-space/{space_id}", patch(archive_space))
+        .route("/unarchive-space/{space_id}", patch(unarchive_space))
+        .route(
+            "/create-space-share-link/{space_id}",
+            patch(create_space_share_link),
+        )
+        .route(
+            "/revoke-space-share-link/{space_id}",
+            patch(revoke_space_share_link),
+        );
+
+    with_auth_layers(protected_routes, state)
+        // read-only share links are public, so this route is added after the auth layers
+        .route("/get-shared-space/{share_token}", get(get_shared_space))
+        .layer(CookieManagerLayer::new())
+}
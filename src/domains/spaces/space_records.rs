@@ -0,0 +1,35 @@
+use crate::AppState;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// A space is a room flagged with `is_space` that only its creator is a member of, so messages and
+/// attachments go through the regular messages pipeline.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Space {
+    pub id: i64,
+    #[serde(rename = "space_name")]
+    pub room_name: Option<String>,
+    pub space_description: Option<String>,
+    pub space_theme: Option<String>,
+    pub room_profile_image: Option<String>,
+    pub created_by: Option<i64>,
+    pub pinned_by: Vec<i64>,
+    pub archived_by: Vec<i64>,
+    pub share_token: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+pub async fn fetch_owned_space(
+    state: &AppState,
+    space_id: i64,
+    owner_id: i64,
+) -> Result<Option<Space>, sqlx::Error> {
+    sqlx::query_as::<_, Space>(
+        "SELECT * FROM rooms WHERE id = $1 AND created_by = $2 AND is_space = TRUE",
+    )
+    .bind(space_id)
+    .bind(owner_id)
+    .fetch_optional(&state.db)
+    .await
+}
@@ -0,0 +1,39 @@
+use crate::AppState;
+use crate::utils::app_error::AppError;
+use axum::extract::{Path, State};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+
+/// Serves a stored file back for the backends that don't have a url of their own.
+pub async fn get_upload(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+) -> Result<Response, AppError> {
+    let object = state
+        .storage
+        .get(&key)
+        .await
+        .map_err(|e| AppError::Storage {
+            message: "Failed to read file".to_string(),
+            source: format!("{:#}", e),
+        })?
+        .ok_or_else(|| AppError::NotFound("File not found".to_string()))?;
+
+    // anything but an image is downloaded rather than rendered, so an uploaded html or script
+    // file can't run on this origin
+    let disposition = if object.content_type.starts_with("image/") {
+        "inline"
+    } else {
+        "attachment"
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, object.content_type),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            (header::CONTENT_DISPOSITION, disposition.to_string()),
+        ],
+        object.bytes,
+    )
+        .into_response())
+}
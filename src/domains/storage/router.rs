@@ -0,0 +1,16 @@
+use crate::AppState;
+use crate::domains::storage::controllers::get_upload::get_upload;
+use crate::utils::load_config::StorageBackendKind;
+use crate::utils::object_store::UPLOADS_ROUTE;
+use axum::{Router, routing::get};
+
+// S3 hands out its own urls, the local and in-memory backends are served from here. Uploaded
+// files are public, like objects in the bucket.
+pub fn storage_routes(state: &AppState) -> Router<AppState> {
+    match state.config.storage_backend() {
+        StorageBackendKind::S3 => Router::new(),
+        StorageBackendKind::Local | StorageBackendKind::Memory => {
+            Router::new().route(&format!("{}/{{key}}", UPLOADS_ROUTE), get(get_upload))
+        }
+    }
+}
@@ -4,14 +4,13 @@ use crate::domains::user::controllers::get_user::get_user;
 use crate::domains::user::controllers::update_password::update_password;
 use crate::domains::user::controllers::update_profile_image::update_profile_image;
 use crate::domains::user::controllers::update_user::update_user;
-use crate::middlewares::auth_access_middleware::access_middleware;
-use crate::middlewares::auth_sessions_middleware::sessions_middleware;
+use crate::middlewares::middleware_stack::with_auth_layers;
 use axum::routing::patch;
-use axum::{Router, middleware, routing::get};
+use axum::{Router, routing::get};
 use tower_cookies::CookieManagerLayer;
 
 pub fn user_routes(state: &AppState) -> Router<AppState> {
-    Router::new()
+    let protected_routes = Router::new()
         .route("/get-user/{user_id}", get(get_user))
         .route("/update-user/{user_id}", patch(update_user))
         .route("/update-password/{user_id}", patch(update_password))
@@ -19,14 +18,7 @@ pub fn user_routes(state: &AppState) -> Router<AppState> {
             "/update-profile-image/{user_id}",
             patch(update_profile_image),
         )
-        .route("/get-all-users", get(get_all_users))
-        .route_layer(middleware::from_fn_with_state(
-            state.clone(),
-            access_middleware,
-        ))
-        .route_layer(middleware::from_fn_with_state(
-            state.clone(),
-            sessions_middleware,
-        ))
-        .layer(CookieManagerLayer::new())
+        .route("/get-all-users", get(get_all_users));
+
+    with_auth_layers(protected_routes, state).layer(CookieManagerLayer::new())
 }
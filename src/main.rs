@@ -1,76 +1,146 @@
-use axum::{Router, middleware};
+use axum::Router;
 use sqlx::PgPool;
-use std::sync::Arc;
-
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 // environmental variables...
 use std::env;
 
-// AWS S3
-use crate::utils::file_upload_handler::S3AppState;
-use aws_config::Region;
-use aws_credential_types::Credentials;
-use aws_sdk_s3::Client;
-
 // logging init with the tracing crate
 use tracing::error;
 use tracing::info;
+use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::fmt::time::SystemTime;
+use tracing_subscriber::prelude::*;
 
 // utils import
 mod utils;
 use crate::utils::load_config::{AppConfig, load_config};
 use crate::utils::load_env::load_env;
+use crate::utils::mail_sender::{MailSender, build_mail_sender};
+use crate::utils::metrics::Metrics;
+use crate::utils::object_store::{ObjectStore, build_object_store};
+use crate::utils::presence_tracker::{PresenceTracker, spawn_presence_sweeper};
+use crate::utils::rate_limiter::RateLimiter;
+use crate::utils::realtime_hub::RealtimeHub;
+use crate::utils::shutdown::{Shutdown, wait_for_signal};
+use crate::utils::trace_exporter::{TraceExporter, build_trace_exporter};
 // db import
 mod db;
 use db::connect_postgres::connect_pg;
 
+// domain models and the storage behind them
+mod models;
+mod repositories;
+use crate::repositories::Repositories;
+
 // controllers import
 mod domains;
 use crate::domains::admin::router::admin_routes;
 use crate::domains::auth::router::auth_routes;
+use crate::domains::calls::call_events::spawn_ring_timeout_sweeper;
+use crate::domains::calls::router::calls_routes;
+use crate::domains::health::router::health_routes;
 use crate::domains::messages::router::messages_routes;
+use crate::domains::realtime::router::realtime_routes;
 use crate::domains::rooms::router::rooms_routes;
+use crate::domains::spaces::router::spaces_routes;
+use crate::domains::storage::router::storage_routes;
 use crate::domains::user::router::user_routes;
 
 mod middlewares;
-use crate::middlewares::logging_middleware::logging_middleware;
-use crate::middlewares::request_timeout_middleware::timeout_middleware;
+use crate::middlewares::middleware_stack::{log_middleware_stack, with_global_layers};
+
+#[cfg(test)]
+mod tests;
 
 #[derive(Clone, Debug)]
 pub struct AppState {
     pub config: Arc<AppConfig>,
     pub db: PgPool,
-    pub s3: S3AppState,
+    pub repos: Repositories,
+    pub storage: Arc<dyn ObjectStore>,
+    pub hub: RealtimeHub,
+    pub presence: PresenceTracker,
+    pub mailer: Arc<dyn MailSender>,
+    pub rate_limiter: RateLimiter,
+    pub shutdown: Shutdown,
+    pub metrics: Metrics,
 }
 
-fn initialize_logging() {
-    tracing_subscriber::fmt()
+/// JSON logs on stdout, plus span export when `observability.enable_tracing` is on. Log lines carry
+/// the fields of the spans they were written in, so everything logged while handling a request
+/// has its request id.
+fn initialize_logging(config: Option<&AppConfig>) -> Option<TraceExporter> {
+    let trace_exporter = config
+        .filter(|config| config.observability.enable_tracing)
+        .map(build_trace_exporter);
+
+    let log_layer = tracing_subscriber::fmt::layer()
         .json()
         .with_timer(SystemTime)
         // .with_thread_ids(true)
         .with_level(true)
+        .with_filter(LevelFilter::INFO);
+
+    tracing_subscriber::registry()
+        .with(log_layer)
+        .with(
+            trace_exporter
+                .as_ref()
+                .and_then(|exporter| exporter.as_ref().ok())
+                .map(|exporter| exporter.layer()),
+        )
         .init();
+
+    // only loggable once the subscriber is up - the server still runs, just without span export
+    match trace_exporter {
+        Some(Err(e)) => {
+            error!("FAILED TO SET UP TRACE EXPORT: {:#}!", e);
+            None
+        }
+        trace_exporter => trace_exporter.and_then(Result::ok),
+    }
+}
+
+/// Every route the server answers, with the global middleware stack applied.
+pub fn build_app(state: AppState) -> Router {
+    let routes = Router::new()
+        .nest("/api/v1/auth", auth_routes(&state))
+        .nest("/api/v1/user", user_routes(&state))
+        .nest("/api/v1/admin", admin_routes(&state))
+        .nest("/api/v1/rooms", rooms_routes(&state))
+        .nest("/api/v1/messages", messages_routes(&state))
+        .nest("/api/v1/calls", calls_routes(&state))
+        .nest("/api/v1/spaces", spaces_routes(&state))
+        .nest("/api/v1/ws", realtime_routes(&state))
+        .merge(storage_routes(&state));
+
+    with_global_layers(routes, &state)
+        .merge(health_routes(&state))
+        .with_state(state)
 }
 
 #[tokio::main]
 async fn main() {
     load_env();
-    initialize_logging();
 
     let app_config = load_config();
 
+    let trace_exporter = initialize_logging(app_config.as_ref().ok());
+
     let clean_config = match app_config {
         Ok(config) => {
             // println!("Configuration loaded successfully: {}", config.app.name);
 
-            if config.validate().is_err() {
-                error!("SERVER START-UP ERROR: FAILED TO LOAD SERVER CONFIGURATIONS!");
+            if let Err(e) = config.validate() {
+                error!("SERVER START-UP ERROR: INVALID CONFIGURATION: {}!", e);
 
                 return;
             }
 
+            log_middleware_stack(&config);
+
             config
         }
         Err(_e) => {
@@ -79,50 +149,52 @@ async fn main() {
         }
     };
 
-    let access_key_id = env::var("AWS_ACCESS_KEY").unwrap();
-    let secret_access_key = env::var("AWS_SECRET_ACCESS_KEY").unwrap();
-    // let aws_url = env::var("AWS_BUCKET_URL").unwrap();
-    let aws_region = env::var("AWS_S3_BUCKET_REGION").expect("AWS_S3_BUCKET_REGION must be set");
-
-    // note here that the "None" is in place of a session token
-    let s3_credentials = Credentials::from_keys(access_key_id, secret_access_key, None);
-
-    let s3_config = aws_config::from_env()
-        // .endpoint_url(aws_url)
-        .region(Region::new(aws_region))
-        .credentials_provider(s3_credentials)
-        .load()
-        .await;
-
-    // Initialize AWS S3 config
-    // let config = aws_config::load_from_env().await;
-    let s3_client = Client::new(&s3_config);
-
-    let bucket_name = std::env::var("AWS_S3_BUCKET_NAME").expect("AWS_S3_BUCKET_NAME must be set");
-
-    let s3_state = S3AppState {
-        s3_client,
-        bucket_name,
+    let storage = match build_object_store(&clean_config).await {
+        Ok(storage) => storage,
+        Err(e) => {
+            error!("SERVER START-UP ERROR: {:#}!", e);
+            return;
+        }
     };
 
     // let port = env::var("PORT").unwrap_or("8000".to_string());
     let environment = env::var("DEPLOY_ENV").unwrap_or("development".to_string());
     let user = env::var("POSTGRES_USER").unwrap();
     let pass = env::var("POSTGRES_PASSWORD").unwrap();
-    let host = env::var("POSTGRES_HOST").unwrap();
-    let db_port = env::var("POSTGRES_PORT").unwrap();
-    let db = env::var("POSTGRES_DB").unwrap();
+    // [database] host, port and name win over the env variables when they are set
+    let db_config = clean_config.database.as_ref();
+    let host = db_config
+        .and_then(|db| db.host.clone())
+        .unwrap_or_else(|| env::var("POSTGRES_HOST").unwrap());
+    let db_port = db_config
+        .and_then(|db| db.port.map(|port| port.to_string()))
+        .unwrap_or_else(|| env::var("POSTGRES_PORT").unwrap());
+    let db = db_config
+        .and_then(|db| db.name.clone())
+        .unwrap_or_else(|| env::var("POSTGRES_DB").unwrap());
 
     let database_url = format!("postgres://{}:{}@{}:{}/{}", user, pass, host, db_port, db);
 
-    let db_pool = connect_pg(database_url.clone()).await;
+    let db_pool = connect_pg(database_url.clone(), db_config).await;
+
+    let mailer = build_mail_sender(clean_config.mail.as_ref());
 
     let state = AppState {
         config: Arc::new(clean_config),
+        repos: Repositories::postgres(&db_pool),
         db: db_pool,
-        s3: s3_state,
+        storage,
+        hub: RealtimeHub::new(),
+        presence: PresenceTracker::new(),
+        mailer,
+        rate_limiter: RateLimiter::new(),
+        shutdown: Shutdown::new(),
+        metrics: Metrics::new(),
     };
 
+    spawn_presence_sweeper(state.clone());
+    spawn_ring_timeout_sweeper(state.clone());
+
     // fn verify_config_loading(state: &AppState) {
     //     println!(
     //         "Config loaded successfully: {} is running on {}:{}",
@@ -134,37 +206,36 @@ async fn main() {
 
     // verify_config_loading(&state);
 
-    let app = Router::new()
-        .nest("/api/v1/auth", auth_routes(&state))
-        .nest("/api/v1/user", user_routes(&state))
-        .nest("/api/v1/admin", admin_routes(&state))
-        .nest("/api/v1/rooms", rooms_routes(&state))
-        .nest("/api/v1/messages", messages_routes(&state))
-        .layer(middleware::from_fn(logging_middleware))
-        .layer(middleware::from_fn(timeout_middleware))
-        .with_state(state);
+    // Server address
+    let (server_host, server_port) = state.config.bind_address();
 
-    // .layer(Extension(db_pool));
+    // kept for the shutdown sequence - the state itself moves into the router
+    let shutdown = state.shutdown.clone();
+    let shutdown_timeout = state.config.graceful_shutdown_timeout();
+    let storage_backend = state.storage.backend();
+    let db_pool = state.db.clone();
 
-    // Server address
-    let addr = SocketAddr::from(([127, 0, 0, 1], 8000));
+    let app = build_app(state);
+
+    // .layer(Extension(db_pool));
 
     let slice_db_url = format!("{}...", &database_url[0..25]);
 
     // Start server
-    let listener = match tokio::net::TcpListener::bind(addr).await {
+    let listener = match tokio::net::TcpListener::bind((server_host.as_str(), server_port)).await {
         Ok(listener) => {
             print!(
                 "
                 .................................................
                 Connected to DB: {}
                 Environment: {}
+                Storage: {}
                 Status: DB connected successfully
                 .................................................
 
-                Server running on http://{}
+                Server running on http://{}:{}
                 ",
-                slice_db_url, environment, addr
+                slice_db_url, environment, storage_backend, server_host, server_port
             );
 
             listener
@@ -176,7 +247,49 @@ async fn main() {
         }
     };
 
-    let server_result = axum::serve(listener, app).await;
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+
+        async move {
+            wait_for_signal().await;
+            shutdown.trigger();
+        }
+    });
+
+    // Once triggered, the listener stops accepting connections and in-flight requests get until
+    // the deadline to finish - whatever is still running after that is dropped.
+    // Connect info gives the middlewares the peer address to key rate limits and lockouts on.
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown({
+        let shutdown = shutdown.clone();
+
+        async move { shutdown.triggered().await }
+    });
+
+    let server_result = tokio::select! {
+        result = server => result,
+        _ = async {
+            shutdown.triggered().await;
+            tokio::time::sleep_until(shutdown.deadline(shutdown_timeout)).await;
+        } => {
+            error!("GRACEFUL SHUTDOWN DEADLINE REACHED: DROPPING IN-FLIGHT REQUESTS!");
+            Ok(())
+        }
+    };
+
+    // a server that stopped on an error still has websocket connections to close
+    shutdown.trigger();
+
+    // websocket connections were told to close when the shutdown started, and only get what is
+    // left of the same grace period
+    if !shutdown.drain_connections(shutdown.deadline(shutdown_timeout)).await {
+        error!("GRACEFUL SHUTDOWN DEADLINE REACHED: DROPPING OPEN WEBSOCKET CONNECTIONS!");
+    }
+
+    db_pool.close().await;
 
     match server_result {
         Ok(_) => {
@@ -186,4 +299,9 @@ async fn main() {
             error!("SERVER SHUTDOWN ERROR: {}!", e);
         }
     }
+
+    // spans still sitting in the export buffer would be lost with the process
+    if let Some(trace_exporter) = trace_exporter {
+        trace_exporter.flush().await;
+    }
 }
@@ -1,30 +1,15 @@
-use crate::middlewares::auth_sessions_middleware::{SessionsMiddlewareOutput, UserProfile};
-use crate::utils::generate_tokens::{User, generate_tokens};
-use axum::{
-    Json,
-    extract::Request,
-    http::{StatusCode, header},
-    middleware::Next,
-    response::IntoResponse,
-};
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::models::user::UserProfile;
+use crate::utils::app_error::AppError;
+use crate::utils::generate_tokens::{Claims, User};
+use axum::{extract::Request, http::header, middleware::Next, response::Response};
 use jsonwebtoken::{DecodingKey, Validation, decode};
-use serde::{Deserialize, Serialize};
-use sqlx;
 use tower_cookies::Cookies;
-use tracing::error;
 
 // ============================================================================
 // Types/Structures
 // ============================================================================
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct JwtClaims {
-    pub id: i64,
-    pub email: String,
-    pub exp: usize,
-    pub iat: usize,
-}
-
 #[derive(Clone)]
 pub struct MiddlewareState {
     pub jwt_secret: String,
@@ -55,43 +40,35 @@ pub struct SessionInfo {
 //     pub is_active: bool,
 // }
 
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub response_message: String,
-}
-
 pub struct AuthTokens {
     pub access_token: String,
     pub refresh_token: String,
     pub auth_cookie: String,
 }
 
-enum TokenStatus {
-    Valid,
-    Expired,
-    Invalid(String),
-}
-
-fn verify_access_token(token: &str, secret: &str, user: &UserProfile) -> TokenStatus {
+fn verify_access_token(token: &str, secret: &str, user: &UserProfile) -> Result<(), AppError> {
     let validation = Validation::default();
     let decoding_key = DecodingKey::from_secret(secret.as_bytes());
 
-    match decode::<JwtClaims>(token, &decoding_key, &validation) {
-        Ok(token_data) => {
-            if token_data.claims.email != user.email {
-                return TokenStatus::Invalid("User credentials do not match".to_string());
-            }
-            TokenStatus::Valid
-        }
-        Err(err) => {
-            use jsonwebtoken::errors::ErrorKind;
-            match err.kind() {
-                ErrorKind::ExpiredSignature => TokenStatus::Expired,
-                _ => TokenStatus::Invalid(format!("Token verification failed: {}", err)),
-            }
-        }
+    // renewal is explicit now - an expired token tells clients to trade their refresh token at
+    // /auth/refresh
+    let token_data = decode::<Claims>(token, &decoding_key, &validation)?;
+
+    // a refresh or single-purpose token is signed with the same secret, but only ever gets
+    // exchanged at its own endpoint
+    if !token_data.claims.is_access_token() {
+        return Err(AppError::Unauthorized(
+            "Token cannot be used to access this resource".to_string(),
+        ));
+    }
+
+    if token_data.claims.email != user.email {
+        return Err(AppError::Unauthorized(
+            "User credentials do not match".to_string(),
+        ));
     }
+
+    Ok(())
 }
 
 // ============================================================================
@@ -103,7 +80,7 @@ pub async fn access_middleware(
     cookies: Cookies,
     req: Request,
     next: Next,
-) -> impl IntoResponse {
+) -> Result<Response, AppError> {
     let session_state = MiddlewareState {
         jwt_secret: std::env::var("JWT_SECRET").expect("JWT_SECRET must be set"),
         cookie_name: "rusty_chat_auth_cookie".to_string(),
@@ -113,14 +90,7 @@ pub async fn access_middleware(
     // AUTH COOKIE CHECK
     // ----------------------------------------------------------
     let _auth_cookie = cookies.get(&session_state.cookie_name).ok_or_else(|| {
-        error!("MISSING AUTH COOKIE!");
-        (
-            StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse {
-                error: "Unauthorized".to_string(),
-                response_message: "Request rejected, please re-authenticate".to_string(),
-            }),
-        )
+        AppError::Unauthorized("Request rejected, please re-authenticate".to_string())
     })?;
 
     // ----------------------------------------------------------
@@ -130,14 +100,7 @@ pub async fn access_middleware(
         .extensions()
         .get::<SessionsMiddlewareOutput>()
         .ok_or_else(|| {
-            error!("SESSION MIDDLEWARE OUTPUT MISSING!");
-            (
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse {
-                    error: "Not Found".to_string(),
-                    response_message: "_ User not received from sessions middleware".to_string(),
-                }),
-            )
+            AppError::NotFound("_ User not received from sessions middleware".to_string())
         })?
         .clone();
 
@@ -148,86 +111,27 @@ pub async fn access_middleware(
         .headers()
         .get(header::AUTHORIZATION)
         .and_then(|h| h.to_str().ok())
-        .ok_or_else(|| {
-            error!("AUTHORIZATION HEADER MISSING!");
-            (
-                StatusCode::FORBIDDEN,
-                Json(ErrorResponse {
-                    error: "Forbidden".to_string(),
-                    response_message: "Authorization header missing".to_string(),
-                }),
-            )
-        })?;
+        .ok_or_else(|| AppError::Forbidden("Authorization header missing".to_string()))?;
 
     // ----------------------------------------------------------
     // BEARER FORMAT CHECK
     // ----------------------------------------------------------
     if !auth_header.starts_with("Bearer ") {
-        error!("INVALID BEARER FORMAT!");
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(ErrorResponse {
-                error: "Forbidden".to_string(),
-                response_message:
-                    "Authorization string does not match expected (Bearer Token) format".to_string(),
-            }),
+        return Err(AppError::Forbidden(
+            "Authorization string does not match expected (Bearer Token) format".to_string(),
         ));
     }
 
     let access_token = auth_header.trim_start_matches("Bearer ");
 
-    // ----------------------------------------------------------
-    // TOKEN GENERATION (FOR RENEWAL)
-    // ----------------------------------------------------------
-    let _tokens = match generate_tokens(
-        "auth",
-        User {
-            id: sessions_middleware_output.user.id,
-            email: sessions_middleware_output.user.email.clone(),
-        },
-    )
-    .await
-    {
-        Ok(tokens) => tokens,
-        Err(_) => {
-            error!("TOKEN GENERATION ERROR!");
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    response_message: "Failed to generate tokens".to_string(),
-                    error: "Token generation error".to_string(),
-                }),
-            ));
-        }
-    };
-
     // ----------------------------------------------------------
     // ACCESS TOKEN VERIFICATION
     // ----------------------------------------------------------
-    match verify_access_token(
+    verify_access_token(
         access_token,
         &session_state.jwt_secret,
         &sessions_middleware_output.user,
-    ) {
-        TokenStatus::Valid => {
-            // normal path (no log)
-        }
-
-        TokenStatus::Expired => {
-            // normal path (no log)
-        }
-
-        TokenStatus::Invalid(msg) => {
-            error!("INVALID ACCESS TOKEN!");
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "Unauthorized".to_string(),
-                    response_message: msg,
-                }),
-            ));
-        }
-    }
+    )?;
 
     // ----------------------------------------------------------
     // NORMAL FLOW (NO LOGGING)
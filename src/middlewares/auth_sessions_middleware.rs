@@ -1,27 +1,18 @@
 use axum::extract::State;
-use axum::{
-    Json,
-    extract::Request,
-    http::{StatusCode, header},
-    middleware::Next,
-    response::IntoResponse,
-};
-use chrono::NaiveDateTime;
+use axum::{extract::Request, http::header, middleware::Next, response::Response};
 use jsonwebtoken::{DecodingKey, Validation, decode, errors::ErrorKind};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use tower_cookies::Cookies;
 use tracing::error;
 
+use crate::models::user::UserProfile;
+use crate::utils::app_error::{AppError, ErrorContext};
+use crate::utils::session_manager::{fetch_session, read_token_claims, touch_session};
+
 // ============================================================================
 // Types
 // ============================================================================
 
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub response_message: String,
-}
-
 #[derive(Debug, Deserialize)]
 pub struct JwtClaims {
     pub id: i64,
@@ -30,23 +21,6 @@ pub struct JwtClaims {
     pub iat: usize,
 }
 
-#[derive(Debug, Serialize, sqlx::FromRow, Clone)]
-pub struct UserProfile {
-    pub id: i64,
-    pub full_name: String,
-    pub email: String,
-    pub profile_image: Option<String>,
-    pub access_token: Option<String>,
-    pub refresh_token: Option<String>,
-    pub status: String,
-    pub last_seen: Option<String>,
-    pub password: String,
-    pub is_admin: bool,
-    pub is_active: bool,
-    pub created_at: NaiveDateTime,
-    pub updated_at: NaiveDateTime,
-}
-
 #[derive(Clone)]
 pub struct MiddlewareState {
     pub jwt_secret: String,
@@ -56,6 +30,7 @@ pub struct MiddlewareState {
 #[derive(Clone, Debug)]
 pub struct SessionsMiddlewareOutput {
     pub user: UserProfile,
+    pub session_id: Option<i64>, // None for tokens issued before the sessions table existed
     pub session_status: String,
 }
 
@@ -69,7 +44,7 @@ pub async fn sessions_middleware(
     cookies: Cookies,
     mut req: Request,
     next: Next,
-) -> impl IntoResponse {
+) -> Result<Response, AppError> {
     let session_state = MiddlewareState {
         jwt_secret: std::env::var("JWT_SECRET").expect("JWT_SECRET must be set"),
         cookie_name: "rusty_chat_auth_cookie".to_string(),
@@ -82,204 +57,164 @@ pub async fn sessions_middleware(
         .headers()
         .get("email")
         .and_then(|v| v.to_str().ok())
-        .ok_or_else(|| {
-            error!("EMAIL HEADER MISSING!");
-
-            (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "Unauthorized".to_string(),
-                    response_message: "Email header missing".to_string(),
-                }),
-            )
-        })?;
+        .ok_or_else(|| AppError::Unauthorized("Email header missing".to_string()))?;
 
-    let _authorization = req
+    let authorization = req
         .headers()
         .get(header::AUTHORIZATION)
         .and_then(|v| v.to_str().ok())
-        .ok_or_else(|| {
-            error!("AUTHORIZATION HEADER MISSING!");
-
-            (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "Unauthorized".to_string(),
-                    response_message: "Authorization header missing".to_string(),
-                }),
-            )
-        })?;
+        .map(|v| v.to_string())
+        .ok_or_else(|| AppError::Unauthorized("Authorization header missing".to_string()))?;
 
     // ------------------------------------------------------------------------
     // Validate cookie presence
     // ------------------------------------------------------------------------
     if cookies.get(&session_state.cookie_name).is_none() {
-        error!("AUTH COOKIE NOT FOUND!");
-
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse {
-                error: "Unauthorized".to_string(),
-                response_message: "Request rejected, please re-authenticate".to_string(),
-            }),
+        return Err(AppError::Unauthorized(
+            "Request rejected, please re-authenticate".to_string(),
         ));
     }
 
     // ------------------------------------------------------------------------
     // Fetch user from database
     // ------------------------------------------------------------------------
-    let user = match sqlx::query_as::<_, UserProfile>(
-        r#"
-        SELECT
-            id,
-            full_name,
-            email,
-            refresh_token,
-            profile_image,
-            is_admin,
-            is_active,
-            access_token,
-            refresh_token,
-            status,
-            last_seen,
-            password,
-            created_at,
-            updated_at
-        FROM users
-        WHERE email = $1
-        "#,
-    )
-    .bind(email)
-    .fetch_optional(&state.db)
-    .await
-    {
-        Ok(Some(u)) => u,
-
-        Ok(None) => {
-            error!("USER NOT FOUND!");
-
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse {
-                    error: "Not Found".to_string(),
-                    response_message: format!("User '{}' not found", email),
-                }),
-            ));
-        }
-
-        Err(e) => {
-            error!("USER FETCH FAILED!");
-
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "DB Error".to_string(),
-                    response_message: e.to_string(),
-                }),
-            ));
-        }
-    };
+    let user = state
+        .repos
+        .users
+        .find_by_email(email)
+        .await
+        .context("Failed to fetch user")?
+        .ok_or_else(|| AppError::NotFound(format!("User '{}' not found", email)))?;
 
     // ------------------------------------------------------------------------
     // Check active status
     // ------------------------------------------------------------------------
     if !user.is_active {
-        error!("INACTIVE USER ACCESS BLOCKED!");
-
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(ErrorResponse {
-                error: "Forbidden".to_string(),
-                response_message: "Your account is deactivated".to_string(),
-            }),
+        return Err(AppError::Forbidden(
+            "Your account is deactivated".to_string(),
         ));
     }
 
     // ------------------------------------------------------------------------
-    // Ensure refresh/session token exists
+    // Resolve the session the bearer token was issued for
     // ------------------------------------------------------------------------
-    let refresh = match &user.refresh_token {
-        Some(t) => t.clone(),
-        None => {
-            error!("REFRESH TOKEN MISSING!");
-
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse {
-                    error: "Not Found".to_string(),
-                    response_message: "Refresh token missing".to_string(),
-                }),
+    // exp is ignored here - an expired access token is turned away by the access middleware,
+    // this only needs to know which session it belongs to
+    let token_claims = authorization
+        .strip_prefix("Bearer ")
+        .and_then(|token| read_token_claims(token, false).ok());
+
+    let session_id = match token_claims {
+        Some(claims) if !claims.is_access_token() => {
+            return Err(AppError::Unauthorized(
+                "Token cannot be used to access this resource".to_string(),
             ));
         }
-    };
-
-    // ------------------------------------------------------------------------
-    // Validate refresh/session JWT
-    // ------------------------------------------------------------------------
-    let decoding_key = DecodingKey::from_secret(session_state.jwt_secret.as_bytes());
 
-    match decode::<JwtClaims>(&refresh, &decoding_key, &Validation::default()) {
-        Ok(token_data) => {
-            if token_data.claims.email != user.email {
-                error!("USER EMAIL CLAIM MISMATCH!");
-
-                return Err((
-                    StatusCode::UNAUTHORIZED,
-                    Json(ErrorResponse {
-                        error: "Unauthorized".to_string(),
-                        response_message: "User credentials do not match".to_string(),
-                    }),
+        Some(claims) if claims.sid.is_some() => {
+            if claims.id != user.id || claims.email != user.email {
+                return Err(AppError::Unauthorized(
+                    "User credentials do not match".to_string(),
                 ));
             }
 
-            // Insert session data
-            req.extensions_mut().insert(SessionsMiddlewareOutput {
-                user: UserProfile {
-                    id: user.id,
-                    full_name: user.full_name,
-                    email: user.email.clone(),
-                    profile_image: user.profile_image,
-                    access_token: user.access_token,
-                    refresh_token: user.refresh_token,
-                    status: user.status,
-                    last_seen: user.last_seen,
-                    password: user.password,
-                    is_admin: user.is_admin,
-                    is_active: user.is_active,
-                    created_at: user.created_at,
-                    updated_at: user.updated_at,
-                },
-                session_status: "USER SESSION IS ACTIVE".to_string(),
-            });
+            validate_session(&state, claims.sid.unwrap_or_default(), user.id).await?;
+            claims.sid
         }
 
-        Err(err) => match err.kind() {
-            ErrorKind::ExpiredSignature => {
-                error!("SESSION EXPIRED!");
-
-                return Err((
-                    StatusCode::FORBIDDEN,
-                    Json(ErrorResponse {
-                        error: "Forbidden".to_string(),
-                        response_message: "User session expired, please re-authenticate"
-                            .to_string(),
-                    }),
-                ));
-            }
+        _ => {
+            let refresh_token = state
+                .repos
+                .users
+                .legacy_refresh_token(user.id)
+                .await
+                .context("Failed to fetch user")?;
+
+            validate_legacy_session(&session_state, &user, refresh_token.as_deref())?;
+            None
+        }
+    };
 
-            _ => {
-                error!("SESSION VERIFICATION FAILED!");
+    // the request span was opened before we knew who is calling
+    tracing::Span::current().record("user.id", user.id);
 
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        error: "Session Verification Failed".to_string(),
-                        response_message: err.to_string(),
-                    }),
-                ));
-            }
+    // Insert session data
+    req.extensions_mut().insert(SessionsMiddlewareOutput {
+        user,
+        session_id,
+        session_status: "USER SESSION IS ACTIVE".to_string(),
+    });
+
+    Ok(next.run(req).await)
+}
+
+// ============================================================================
+// Session Validation
+// ============================================================================
+
+/// Session-bound tokens are only as good as their row in the sessions table.
+async fn validate_session(
+    state: &crate::AppState,
+    session_id: i64,
+    user_id: i64,
+) -> Result<(), AppError> {
+    let session = fetch_session(state, session_id, user_id)
+        .await
+        .context("Failed to fetch session")?
+        .ok_or_else(|| {
+            AppError::Unauthorized("Session not found, please re-authenticate".to_string())
+        })?;
+
+    if session.revoked_at.is_some() {
+        return Err(AppError::Unauthorized(
+            "Session has been revoked, please re-authenticate".to_string(),
+        ));
+    }
+
+    if session.is_expired {
+        return Err(session_expired());
+    }
+
+    if touch_session(state, session.id).await.is_err() {
+        error!("FAILED TO UPDATE SESSION LAST USED TIME!");
+    }
+
+    Ok(())
+}
+
+/// Tokens without a session id fall back to the single refresh token stored on the user.
+fn validate_legacy_session(
+    session_state: &MiddlewareState,
+    user: &UserProfile,
+    refresh_token: Option<&str>,
+) -> Result<(), AppError> {
+    // ------------------------------------------------------------------------
+    // Ensure refresh/session token exists
+    // ------------------------------------------------------------------------
+    let refresh =
+        refresh_token.ok_or_else(|| AppError::NotFound("Refresh token missing".to_string()))?;
+
+    // ------------------------------------------------------------------------
+    // Validate refresh/session JWT
+    // ------------------------------------------------------------------------
+    let decoding_key = DecodingKey::from_secret(session_state.jwt_secret.as_bytes());
+
+    let token_data = decode::<JwtClaims>(refresh, &decoding_key, &Validation::default()).map_err(
+        |err| match err.kind() {
+            ErrorKind::ExpiredSignature => session_expired(),
+            _ => AppError::InvalidToken,
         },
+    )?;
+
+    if token_data.claims.email != user.email {
+        return Err(AppError::Unauthorized(
+            "User credentials do not match".to_string(),
+        ));
     }
 
-    Ok(next.run(req).await)
+    Ok(())
+}
+
+fn session_expired() -> AppError {
+    AppError::Forbidden("User session expired, please re-authenticate".to_string())
 }
@@ -0,0 +1,37 @@
+use crate::AppState;
+use crate::middlewares::auth_sessions_middleware::SessionsMiddlewareOutput;
+use crate::utils::app_error::AppError;
+use crate::utils::load_config::EmailVerificationMode;
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+// ============================================================================
+// Email Verification Middleware
+// ============================================================================
+
+/// Turns away users who haven't verified their email when `[auth] email_verification` is
+/// `messaging`. Runs inside the sessions layer, which puts the signed-in user on the request.
+pub async fn email_verification_middleware(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if state.config.email_verification_mode() == EmailVerificationMode::Messaging {
+        let verified = req
+            .extensions()
+            .get::<SessionsMiddlewareOutput>()
+            .is_some_and(|session| session.user.email_verified);
+
+        if !verified {
+            return Err(AppError::Forbidden(
+                "Please verify your email address before sending messages or making calls"
+                    .to_string(),
+            ));
+        }
+    }
+
+    Ok(next.run(req).await)
+}
@@ -0,0 +1,45 @@
+use crate::AppState;
+use crate::utils::metrics::{HTTP_REQUEST_DURATION, HTTP_REQUESTS};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
+
+// ============================================================================
+// Metrics Middleware
+// ============================================================================
+
+pub async fn metrics_middleware(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    // the route template rather than the raw path, so ids don't blow up the number of series
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().to_string();
+    let start_time = Instant::now();
+
+    let response = next.run(req).await;
+
+    let status = response.status().as_u16().to_string();
+    let labels = [
+        ("route", route.as_str()),
+        ("method", method.as_str()),
+        ("status", status.as_str()),
+    ];
+
+    state.metrics.increment(HTTP_REQUESTS, &labels);
+    state.metrics.observe(
+        HTTP_REQUEST_DURATION,
+        &labels,
+        start_time.elapsed().as_secs_f64(),
+    );
+
+    response
+}
@@ -0,0 +1,141 @@
+use crate::AppState;
+use crate::middlewares::admin_routes_protector::admin_routes_protector;
+use crate::middlewares::auth_access_middleware::access_middleware;
+use crate::middlewares::auth_sessions_middleware::sessions_middleware;
+use crate::middlewares::email_verification_middleware::email_verification_middleware;
+use crate::middlewares::logging_middleware::logging_middleware;
+use crate::middlewares::metrics_middleware::metrics_middleware;
+use crate::middlewares::rate_limit_middleware::{RateLimitState, rate_limit_middleware};
+use crate::middlewares::request_id_middleware::request_id_middleware;
+use crate::middlewares::request_timeout_middleware::timeout_middleware;
+use crate::utils::load_config::AppConfig;
+use axum::routing::MethodRouter;
+use axum::{Router, middleware};
+use tracing::info;
+
+// ============================================================================
+// Middleware Stack
+// ============================================================================
+//
+// Every layer is switched on or off by its `[client_integrations]` flag when the routers are
+// built, so a disabled middleware costs nothing at request time.
+
+/// Adds the access and sessions layers to the routes registered so far. Routes added to the
+/// returned router afterwards stay public.
+pub fn with_auth_layers(router: Router<AppState>, state: &AppState) -> Router<AppState> {
+    let integrations = &state.config.client_integrations;
+    let mut router = router;
+
+    if integrations.allow_access_middleware {
+        router = router.route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            access_middleware,
+        ));
+    }
+
+    if integrations.allow_sessions_middleware {
+        router = router.route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            sessions_middleware,
+        ));
+    }
+
+    router
+}
+
+/// The auth layers plus the admin check, for the admin domain.
+pub fn with_admin_layers(router: Router<AppState>, state: &AppState) -> Router<AppState> {
+    let router = with_auth_layers(router, state);
+
+    if state
+        .config
+        .client_integrations
+        .allow_admin_routes_protector_middleware
+    {
+        return router.route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            admin_routes_protector,
+        ));
+    }
+
+    router
+}
+
+/// Puts a single route under the named rate limit policy.
+pub fn with_rate_limit(
+    route: MethodRouter<AppState>,
+    state: &AppState,
+    policy: &'static str,
+) -> MethodRouter<AppState> {
+    if !state.config.client_integrations.allow_rate_limit_middleware {
+        return route;
+    }
+
+    route.layer(middleware::from_fn_with_state(
+        RateLimitState::new(state, policy),
+        rate_limit_middleware,
+    ))
+}
+
+/// Puts a single route behind the `[auth] email_verification = "messaging"` check. The route
+/// has to sit under the auth layers, which is where the signed-in user comes from.
+pub fn with_verified_email(
+    route: MethodRouter<AppState>,
+    state: &AppState,
+) -> MethodRouter<AppState> {
+    route.layer(middleware::from_fn_with_state(
+        state.clone(),
+        email_verification_middleware,
+    ))
+}
+
+/// The app-wide layers: the default rate limit, request logging, the request timeout, request
+/// metrics and request ids. Metrics go on after the others so they also count requests those
+/// turned away, and the request id is outermost so every layer runs inside the request span.
+pub fn with_global_layers(router: Router<AppState>, state: &AppState) -> Router<AppState> {
+    let integrations = &state.config.client_integrations;
+    let mut router = router;
+
+    if integrations.allow_rate_limit_middleware {
+        router = router.layer(middleware::from_fn_with_state(
+            RateLimitState::new(state, "default"),
+            rate_limit_middleware,
+        ));
+    }
+
+    if integrations.allow_logging_middleware {
+        router = router.layer(middleware::from_fn(logging_middleware));
+    }
+
+    if integrations.allow_request_timeout_middleware {
+        router = router.layer(middleware::from_fn_with_state(
+            state.clone(),
+            timeout_middleware,
+        ));
+    }
+
+    if state.config.observability.enable_metrics {
+        router = router.layer(middleware::from_fn_with_state(
+            state.clone(),
+            metrics_middleware,
+        ));
+    }
+
+    router.layer(middleware::from_fn(request_id_middleware))
+}
+
+pub fn log_middleware_stack(config: &AppConfig) {
+    let integrations = &config.client_integrations;
+    let toggle = |enabled: bool| if enabled { "on" } else { "off" };
+
+    info!(
+        access = toggle(integrations.allow_access_middleware),
+        sessions = toggle(integrations.allow_sessions_middleware),
+        admin_routes_protector = toggle(integrations.allow_admin_routes_protector_middleware),
+        rate_limit = toggle(integrations.allow_rate_limit_middleware),
+        logging = toggle(integrations.allow_logging_middleware),
+        request_timeout = toggle(integrations.allow_request_timeout_middleware),
+        metrics = toggle(config.observability.enable_metrics),
+        "Middleware stack"
+    );
+}
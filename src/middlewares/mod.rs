@@ -1,5 +1,10 @@
 pub mod admin_routes_protector;
 pub mod auth_access_middleware;
 pub mod auth_sessions_middleware;
+pub mod email_verification_middleware;
 pub mod logging_middleware;
+pub mod metrics_middleware;
+pub mod middleware_stack;
+pub mod rate_limit_middleware;
+pub mod request_id_middleware;
 pub mod request_timeout_middleware;
@@ -0,0 +1,83 @@
+use crate::AppState;
+use crate::utils::app_error::AppError;
+use crate::utils::load_config::AppConfig;
+use crate::utils::rate_limiter::RateLimitDecision;
+use crate::utils::session_manager::{client_ip, read_token_claims};
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// State for one rate limit layer. The policy names the bucket set and picks the limit: a policy
+/// listed under `[security.rate_limit_overrides]` gets its own limit, the rest use
+/// `rate_limit_per_minute`.
+#[derive(Clone, Debug)]
+pub struct RateLimitState {
+    pub app: AppState,
+    pub policy: &'static str,
+}
+
+impl RateLimitState {
+    pub fn new(state: &AppState, policy: &'static str) -> Self {
+        Self {
+            app: state.clone(),
+            policy,
+        }
+    }
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Signed-in clients are limited per user, everyone else per IP address.
+fn client_key(req: &Request, config: &AppConfig) -> String {
+    let user_id = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        // an expired token still tells us who the client is, the auth middlewares reject it later
+        .and_then(|token| read_token_claims(token, false).ok())
+        .map(|claims| claims.id);
+
+    if let Some(user_id) = user_id {
+        return format!("user:{}", user_id);
+    }
+
+    client_ip(req.headers(), req.extensions(), config)
+        .map(|ip| format!("ip:{}", ip))
+        .unwrap_or_else(|| "anonymous".to_string())
+}
+
+// ============================================================================
+// Rate Limit Middleware
+// ============================================================================
+
+pub async fn rate_limit_middleware(
+    State(limit): State<RateLimitState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let per_minute = limit.app.config.rate_limit_for(limit.policy);
+
+    let key = client_key(&req, &limit.app.config);
+
+    match limit.app.rate_limiter.check(limit.policy, &key, per_minute) {
+        RateLimitDecision::Allowed => next.run(req).await,
+        RateLimitDecision::Limited { retry_after_secs } => AppError::TooManyRequests {
+            message: format!(
+                "Rate limit exceeded, please try again in {} seconds",
+                retry_after_secs
+            ),
+            retry_after_secs,
+        }
+        .into_response(),
+    }
+}
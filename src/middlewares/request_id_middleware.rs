@@ -0,0 +1,84 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{Instrument, field::Empty, info_span};
+
+// ============================================================================
+// Types
+// ============================================================================
+
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+// longer ids sent by clients are replaced rather than trusted
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Keeps the id a client or proxy sent so its logs line up with ours, as long as it is printable
+/// ascii of a sane length.
+fn request_id(req: &Request) -> String {
+    req.headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LENGTH
+                && id.chars().all(|c| c.is_ascii_graphic())
+        })
+        .map(|id| id.to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string())
+}
+
+// ============================================================================
+// Request Id Middleware
+// ============================================================================
+
+/// Opens the root span for the request. Everything logged while handling it, and every sqlx
+/// statement and S3 upload, nests under this span. The sessions middleware fills in `user.id` once
+/// it knows who is calling.
+pub async fn request_id_middleware(req: Request, next: Next) -> Response {
+    let request_id = request_id(&req);
+
+    // the route template rather than the raw path, so traces group by endpoint
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().to_string();
+
+    let span = info_span!(
+        "http_request",
+        otel.name = %format!("{} {}", method, route),
+        otel.kind = "server",
+        otel.status_code = Empty,
+        request_id = %request_id,
+        http.request.method = %method,
+        http.route = %route,
+        url.path = %req.uri().path(),
+        user.id = Empty,
+        http.response.status_code = Empty,
+    );
+
+    let mut response = next.run(req).instrument(span.clone()).await;
+
+    let status = response.status();
+    span.record("http.response.status_code", status.as_u16());
+
+    if status.is_server_error() {
+        span.record("otel.status_code", "error");
+    }
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER.clone(), value);
+    }
+
+    response
+}
@@ -1,31 +1,27 @@
-use axum::{Json, extract::Request, http::StatusCode, middleware::Next, response::Response};
-use serde::Serialize;
-use std::time::{Duration, Instant};
+use crate::AppState;
+use crate::utils::app_error::AppError;
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::time::Instant;
 use tokio::time::timeout;
 
-// ============================================================================
-// Types
-// ============================================================================
-
-#[derive(Debug, Serialize)]
-pub struct TimeoutErrorResponse {
-    pub error: String,
-    pub response_message: String,
-}
-
 // ============================================================================
 // Timeout Middleware
 // ============================================================================
 
 pub async fn timeout_middleware(
+    State(state): State<AppState>,
     req: Request,
     next: Next,
-) -> Result<Response, (StatusCode, Json<TimeoutErrorResponse>)> {
+) -> Result<Response, AppError> {
     let path = req.uri().path().to_string();
     let start_time = Instant::now();
     let start_timestamp = chrono::Local::now();
 
-    let timeout_duration = Duration::from_secs(60);
+    let timeout_duration = state.config.request_timeout();
 
     // Run the request inside a timeout future
     match timeout(timeout_duration, next.run(req)).await {
@@ -55,14 +51,10 @@ pub async fn timeout_middleware(
                 duration_ms,
             );
 
-            Err((
-                StatusCode::REQUEST_TIMEOUT,
-                Json(TimeoutErrorResponse {
-                    error: "Request Timeout".to_string(),
-                    response_message: "Request exceeded the maximum allowed time of 60 seconds"
-                        .to_string(),
-                }),
-            ))
+            Err(AppError::RequestTimeout(format!(
+                "Request exceeded the maximum allowed time of {} seconds",
+                timeout_duration.as_secs()
+            )))
         }
     }
 }
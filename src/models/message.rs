@@ -0,0 +1,139 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Message {
+    pub id: i64,
+    pub room_id: i64,
+    pub sender_id: Option<i64>,
+    #[sqlx(rename = "type")]
+    pub message_type: String,
+    pub text_content: Option<String>,
+    pub attachment_1: Option<String>,
+    pub attachment_2: Option<String>,
+    pub attachment_3: Option<String>,
+    pub attachment_4: Option<String>,
+    pub status: String,
+    pub sent_at: String,
+    pub updates_counter: i32,
+    pub reply_to_message_id: Option<i64>,
+    pub is_deleted: bool,
+    pub deleted_at: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl Message {
+    pub fn has_attachment(&self) -> bool {
+        [
+            &self.attachment_1,
+            &self.attachment_2,
+            &self.attachment_3,
+            &self.attachment_4,
+        ]
+        .iter()
+        .any(|attachment| attachment.as_deref().is_some_and(|url| !url.is_empty()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NewMessage {
+    pub room_id: i64,
+    pub sender_id: i64,
+    pub message_type: String,
+    pub text_content: Option<String>,
+    pub reply_to_message_id: Option<i64>,
+    pub sent_at: String,
+}
+
+/// The `status` column of a message, as last set by its sender or its readers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageStatus {
+    Sent,
+    Delivered,
+    Seen,
+    Updated,
+    Reacted,
+    Deleted,
+}
+
+impl MessageStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageStatus::Sent => "sent",
+            MessageStatus::Delivered => "delivered",
+            MessageStatus::Seen => "seen",
+            MessageStatus::Updated => "updated",
+            MessageStatus::Reacted => "reacted",
+            MessageStatus::Deleted => "deleted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MessageReaction {
+    pub id: i64,
+    pub message_id: i64,
+    pub room_id: i64,
+    pub sender_id: i64,
+    pub reaction_type: String,
+    pub message_updates_counter: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewReaction {
+    pub message_id: i64,
+    pub room_id: i64,
+    pub sender_id: i64,
+    pub reaction_type: String,
+    pub message_updates_counter: i32,
+}
+
+/// An earlier version of a message, kept when its sender edits it.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MessageEdit {
+    pub id: i64,
+    pub message_id: i64,
+    pub previous_context: String,
+    pub new_content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Filters of a full-text message search. The search only ever covers rooms the searcher is a
+/// member of.
+#[derive(Debug, Clone, Default)]
+pub struct MessageSearch {
+    pub query: String,
+    pub room_id: Option<i64>,
+    pub sender_id: Option<i64>,
+    pub message_type: Option<String>,
+    pub sent_after: Option<i64>,  // milliseconds, inclusive
+    pub sent_before: Option<i64>, // milliseconds, inclusive
+    pub has_attachment: Option<bool>,
+    pub include_edits: bool,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MessageSearchHit {
+    pub id: i64,
+    pub room_id: i64,
+    pub sender_id: Option<i64>,
+    #[sqlx(rename = "type")]
+    pub message_type: String,
+    pub text_content: Option<String>,
+    pub attachment_1: Option<String>,
+    pub attachment_2: Option<String>,
+    pub attachment_3: Option<String>,
+    pub attachment_4: Option<String>,
+    pub status: String,
+    pub sent_at: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub rank: f32,
+    pub snippet: String,
+    pub matched_in: String, // "content" or "edit_history"
+}
@@ -0,0 +1,4 @@
+pub mod message;
+pub mod receipt;
+pub mod room;
+pub mod user;
@@ -0,0 +1,50 @@
+use crate::models::message::MessageStatus;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MessageStatusReceipt {
+    pub id: i64,
+    pub message_id: i64,
+    pub room_id: i64,
+    pub sender_id: i64,
+    pub receiver_id: Option<i64>,
+    pub status: String,
+    pub action: String,
+    pub updates_count_tracker: i32,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// What caused a receipt to be written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiptAction {
+    OriginalSend,
+    Edit,
+    Delete,
+    Reaction,
+    System,
+}
+
+impl ReceiptAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReceiptAction::OriginalSend => "original-send",
+            ReceiptAction::Edit => "edit",
+            ReceiptAction::Delete => "delete",
+            ReceiptAction::Reaction => "reaction",
+            ReceiptAction::System => "system",
+        }
+    }
+}
+
+/// A receipt to hand out to one or more receivers.
+#[derive(Debug, Clone)]
+pub struct NewReceipt {
+    pub message_id: i64,
+    pub room_id: i64,
+    pub sender_id: i64,
+    pub action: ReceiptAction,
+    pub status: MessageStatus,
+    pub updates_count_tracker: i32,
+}
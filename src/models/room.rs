@@ -0,0 +1,102 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Room {
+    pub id: i64,
+    pub room_name: Option<String>,
+    pub is_group: bool,
+    pub is_public: bool,
+    pub is_space: bool,
+    pub created_by: Option<i64>,
+    pub co_member: Option<i64>,       // for private rooms only
+    pub co_members: Option<Vec<i64>>, // for group rooms only
+    pub room_profile_image: Option<String>,
+    pub bookmarked_by: Vec<i64>,
+    pub archived_by: Vec<i64>,
+    pub pinned_by: Vec<i64>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RoomMember {
+    pub id: i64,
+    pub room_id: i64,
+    pub user_id: i64,
+    pub role: String,
+    pub joined_at: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomRole {
+    Admin,
+    Member,
+}
+
+impl RoomRole {
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "admin" => Some(RoomRole::Admin),
+            "member" => Some(RoomRole::Member),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoomRole::Admin => "admin",
+            RoomRole::Member => "member",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NewRoom {
+    pub room_name: String,
+    pub is_group: bool,
+    pub created_by: i64,
+    pub co_member: Option<i64>,
+    pub co_members: Vec<i64>,
+}
+
+/// Which rooms an admin listing covers. Spaces never show up in these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomListing {
+    All,
+    Open,
+    Closed,
+    Group,
+    Private,
+}
+
+impl RoomListing {
+    pub fn includes(&self, room: &Room) -> bool {
+        if room.is_space {
+            return false;
+        }
+
+        match self {
+            RoomListing::All => true,
+            RoomListing::Open => room.is_public,
+            RoomListing::Closed => !room.is_public,
+            RoomListing::Group => room.is_group,
+            RoomListing::Private => !room.is_group,
+        }
+    }
+}
+
+/// A partial update of a room's details. Fields left as `None` keep their value.
+#[derive(Debug, Clone, Default)]
+pub struct RoomChanges {
+    pub room_name: Option<String>,
+    pub is_public: Option<bool>,
+}
+
+impl RoomChanges {
+    pub fn is_empty(&self) -> bool {
+        self.room_name.is_none() && self.is_public.is_none()
+    }
+}
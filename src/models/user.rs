@@ -0,0 +1,73 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// What other users and admins get to see of an account. Password hashes, tokens and secrets
+/// never leave the users table through this.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct UserProfile {
+    pub id: i64,
+    pub full_name: String,
+    pub email: String,
+    pub profile_image: Option<String>,
+    pub status: String,
+    pub last_seen: Option<String>,
+    pub is_admin: bool,
+    pub is_active: bool,
+    pub email_verified: bool,
+    pub country: Option<String>,
+    pub phone_number: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// What signing in needs to know about an account. Never serialized, it carries the password hash.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UserCredentials {
+    pub id: i64,
+    pub email: String,
+    pub password: String,
+    pub email_verified: bool,
+    pub totp_enabled: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewUser {
+    pub email: String,
+    /// Already hashed.
+    pub password: String,
+    pub full_name: String,
+    pub country: String,
+    pub phone_number: String,
+}
+
+/// A partial update of a user's profile. Fields left as `None` keep their value.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileChanges {
+    pub full_name: Option<String>,
+    pub email: Option<String>,
+    pub country: Option<String>,
+    pub phone_number: Option<String>,
+    pub status: Option<String>,
+    pub last_seen: Option<String>,
+}
+
+impl ProfileChanges {
+    pub fn is_empty(&self) -> bool {
+        self.full_name.is_none()
+            && self.email.is_none()
+            && self.country.is_none()
+            && self.phone_number.is_none()
+            && self.status.is_none()
+            && self.last_seen.is_none()
+    }
+}
+
+/// A room member as shown in the room's presence list.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MemberPresence {
+    pub user_id: i64,
+    pub full_name: String,
+    pub profile_image: Option<String>,
+    pub status: String,
+    pub last_seen: Option<String>,
+}
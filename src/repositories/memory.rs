@@ -0,0 +1,1047 @@
+use crate::models::message::{
+    Message, MessageEdit, MessageReaction, MessageSearch, MessageSearchHit, MessageStatus,
+    NewMessage, NewReaction,
+};
+use crate::models::receipt::{MessageStatusReceipt, NewReceipt, ReceiptAction};
+use crate::models::room::{NewRoom, Room, RoomChanges, RoomListing, RoomMember, RoomRole};
+use crate::models::user::{MemberPresence, NewUser, ProfileChanges, UserCredentials, UserProfile};
+use crate::repositories::{MessageRepo, ReceiptRepo, RepoFuture, Repositories, RoomRepo, UserRepo};
+use crate::utils::app_error::AppError;
+use crate::utils::pagination_cursor::Cursor;
+use chrono::{NaiveDateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Every table the repositories touch, kept in plain vectors. Only meant for unit tests.
+#[derive(Debug, Default)]
+struct Tables {
+    next_id: i64,
+    users: Vec<UserProfile>,
+    // password hashes and the legacy (access, refresh) token columns, keyed by user id
+    passwords: HashMap<i64, String>,
+    legacy_tokens: HashMap<i64, (Option<String>, Option<String>)>,
+    rooms: Vec<Room>,
+    room_members: Vec<RoomMember>,
+    messages: Vec<Message>,
+    message_edits: Vec<MessageEdit>,
+    message_deletions: HashSet<(i64, i64)>,
+    message_archives: HashSet<(i64, i64)>,
+    message_bookmarks: HashSet<(i64, i64)>,
+    message_reactions: Vec<MessageReaction>,
+    message_status_receipts: Vec<MessageStatusReceipt>,
+}
+
+impl Tables {
+    fn next_id(&mut self) -> i64 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+/// Shared by all four in-memory repositories, so a room created through one is visible to the
+/// others just like in the database.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryStore {
+    tables: Arc<Mutex<Tables>>,
+}
+
+impl InMemoryStore {
+    fn lock(&self) -> MutexGuard<'_, Tables> {
+        self.tables.lock().expect("in-memory store poisoned")
+    }
+
+    pub fn insert_user(&self, full_name: &str) -> UserProfile {
+        let mut tables = self.lock();
+        let id = tables.next_id();
+        let user = UserProfile {
+            id,
+            full_name: full_name.to_string(),
+            email: format!("{}@example.com", full_name.to_lowercase().replace(' ', ".")),
+            profile_image: None,
+            status: "offline".to_string(),
+            last_seen: None,
+            is_admin: false,
+            is_active: true,
+            email_verified: true,
+            country: None,
+            phone_number: None,
+            created_at: now(),
+            updated_at: now(),
+        };
+
+        tables.users.push(user.clone());
+        user
+    }
+
+    pub fn receipts(&self) -> Vec<MessageStatusReceipt> {
+        self.lock().message_status_receipts.clone()
+    }
+
+    pub fn edits(&self) -> Vec<(i64, String, String)> {
+        self.lock()
+            .message_edits
+            .iter()
+            .map(|edit| {
+                (
+                    edit.message_id,
+                    edit.previous_context.clone(),
+                    edit.new_content.clone(),
+                )
+            })
+            .collect()
+    }
+
+    pub fn is_hidden_for(&self, message_id: i64, user_id: i64) -> bool {
+        self.lock()
+            .message_deletions
+            .contains(&(message_id, user_id))
+    }
+
+    /// Moves a message back in time, for rules that depend on its age.
+    pub fn backdate_message(&self, message_id: i64, sent_at: &str) {
+        if let Some(message) = self.lock().messages.iter_mut().find(|m| m.id == message_id) {
+            message.sent_at = sent_at.to_string();
+        }
+    }
+}
+
+impl Repositories {
+    pub fn in_memory() -> (Self, InMemoryStore) {
+        let store = InMemoryStore::default();
+        let repos = Repositories {
+            users: Arc::new(store.clone()),
+            rooms: Arc::new(store.clone()),
+            messages: Arc::new(store.clone()),
+            receipts: Arc::new(store.clone()),
+        };
+
+        (repos, store)
+    }
+}
+
+fn now() -> NaiveDateTime {
+    Utc::now().naive_utc()
+}
+
+fn ready<'a, T: Send + 'a>(value: T) -> RepoFuture<'a, T> {
+    Box::pin(std::future::ready(Ok(value)))
+}
+
+// what the `fetch_one` queries of the Postgres repositories fail with
+fn row_not_found<'a, T: Send + 'a>() -> RepoFuture<'a, T> {
+    Box::pin(std::future::ready(Err(sqlx::Error::RowNotFound.into())))
+}
+
+impl InMemoryStore {
+    fn update_user(&self, id: i64, update: impl FnOnce(&mut UserProfile)) -> Option<UserProfile> {
+        let mut tables = self.lock();
+        tables.users.iter_mut().find(|u| u.id == id).map(|user| {
+            update(user);
+            user.updated_at = now();
+            user.clone()
+        })
+    }
+
+    fn update_room(&self, id: i64, update: impl FnOnce(&mut Room)) -> RepoFuture<'_, Room> {
+        let mut tables = self.lock();
+        let Some(room) = tables.rooms.iter_mut().find(|r| r.id == id) else {
+            return row_not_found();
+        };
+
+        update(room);
+        room.updated_at = now();
+        ready(room.clone())
+    }
+
+    fn flag_room(
+        &self,
+        id: i64,
+        flags: impl FnOnce(&mut Room) -> &mut Vec<i64>,
+        user_id: i64,
+        flagged: bool,
+    ) -> RepoFuture<'_, ()> {
+        if let Some(room) = self.lock().rooms.iter_mut().find(|r| r.id == id) {
+            let flags = flags(room);
+            flags.retain(|flagged_by| *flagged_by != user_id);
+
+            if flagged {
+                flags.push(user_id);
+            }
+        }
+
+        ready(())
+    }
+}
+
+impl UserRepo for InMemoryStore {
+    fn find(&self, id: i64) -> RepoFuture<'_, Option<UserProfile>> {
+        ready(self.lock().users.iter().find(|u| u.id == id).cloned())
+    }
+
+    fn find_by_email<'a>(&'a self, email: &'a str) -> RepoFuture<'a, Option<UserProfile>> {
+        ready(self.lock().users.iter().find(|u| u.email == email).cloned())
+    }
+
+    fn find_by_phone_number<'a>(
+        &'a self,
+        phone_number: &'a str,
+    ) -> RepoFuture<'a, Option<UserProfile>> {
+        let tables = self.lock();
+        let user = tables
+            .users
+            .iter()
+            .find(|u| u.phone_number.as_deref() == Some(phone_number));
+
+        ready(user.cloned())
+    }
+
+    fn credentials<'a>(&'a self, email: &'a str) -> RepoFuture<'a, Option<UserCredentials>> {
+        let tables = self.lock();
+        let credentials = tables.users.iter().find(|u| u.email == email).map(|user| {
+            UserCredentials {
+                id: user.id,
+                email: user.email.clone(),
+                password: tables.passwords.get(&user.id).cloned().unwrap_or_default(),
+                email_verified: user.email_verified,
+                totp_enabled: false,
+            }
+        });
+
+        ready(credentials)
+    }
+
+    fn list(&self) -> RepoFuture<'_, Vec<UserProfile>> {
+        ready(self.lock().users.clone())
+    }
+
+    fn create<'a>(&'a self, user: &'a NewUser) -> RepoFuture<'a, UserProfile> {
+        let mut tables = self.lock();
+
+        if tables.users.iter().any(|u| u.email == user.email) {
+            return Box::pin(std::future::ready(Err(AppError::Conflict(
+                "Resource already exists".to_string(),
+            ))));
+        }
+
+        let id = tables.next_id();
+        let profile = UserProfile {
+            id,
+            full_name: user.full_name.clone(),
+            email: user.email.clone(),
+            profile_image: Some(String::new()),
+            status: "offline".to_string(),
+            last_seen: None,
+            is_admin: false,
+            is_active: true,
+            email_verified: false,
+            country: Some(user.country.clone()),
+            phone_number: Some(user.phone_number.clone()),
+            created_at: now(),
+            updated_at: now(),
+        };
+
+        tables.users.push(profile.clone());
+        tables.passwords.insert(id, user.password.clone());
+        ready(profile)
+    }
+
+    fn update_profile<'a>(
+        &'a self,
+        id: i64,
+        changes: &'a ProfileChanges,
+    ) -> RepoFuture<'a, Option<UserProfile>> {
+        ready(self.update_user(id, |user| {
+            let changes = changes.clone();
+            user.full_name = changes.full_name.unwrap_or(user.full_name.clone());
+            user.email = changes.email.unwrap_or(user.email.clone());
+            user.country = changes.country.or(user.country.take());
+            user.phone_number = changes.phone_number.or(user.phone_number.take());
+            user.status = changes.status.unwrap_or(user.status.clone());
+            user.last_seen = changes.last_seen.or(user.last_seen.take());
+        }))
+    }
+
+    fn set_password<'a>(
+        &'a self,
+        id: i64,
+        password: &'a str,
+    ) -> RepoFuture<'a, Option<UserProfile>> {
+        let user = self.update_user(id, |_| {});
+
+        if user.is_some() {
+            self.lock().passwords.insert(id, password.to_string());
+        }
+
+        ready(user)
+    }
+
+    fn set_profile_image<'a>(
+        &'a self,
+        id: i64,
+        profile_image: &'a str,
+    ) -> RepoFuture<'a, Option<UserProfile>> {
+        ready(self.update_user(id, |user| {
+            user.profile_image = Some(profile_image.to_string());
+        }))
+    }
+
+    fn set_active(&self, id: i64, is_active: bool) -> RepoFuture<'_, Option<UserProfile>> {
+        let mut tables = self.lock();
+        let user = tables.users.iter_mut().find(|u| u.id == id).map(|user| {
+            user.is_active = is_active;
+            user.updated_at = now();
+            user.clone()
+        });
+
+        ready(user)
+    }
+
+    fn set_admin(&self, id: i64, is_admin: bool) -> RepoFuture<'_, Option<UserProfile>> {
+        let mut tables = self.lock();
+        let user = tables.users.iter_mut().find(|u| u.id == id).map(|user| {
+            user.is_admin = is_admin;
+            user.updated_at = now();
+            user.clone()
+        });
+
+        ready(user)
+    }
+
+    fn legacy_refresh_token(&self, id: i64) -> RepoFuture<'_, Option<String>> {
+        let tables = self.lock();
+        let token = tables
+            .legacy_tokens
+            .get(&id)
+            .and_then(|(_, refresh_token)| refresh_token.clone());
+
+        ready(token)
+    }
+
+    fn store_legacy_tokens<'a>(
+        &'a self,
+        id: i64,
+        access_token: Option<&'a str>,
+        refresh_token: Option<&'a str>,
+    ) -> RepoFuture<'a, ()> {
+        self.lock().legacy_tokens.insert(
+            id,
+            (
+                access_token.map(str::to_string),
+                refresh_token.map(str::to_string),
+            ),
+        );
+
+        ready(())
+    }
+
+    fn log_out<'a>(&'a self, email: &'a str) -> RepoFuture<'a, bool> {
+        let mut tables = self.lock();
+        let Some(id) = tables.users.iter().find(|u| u.email == email).map(|u| u.id) else {
+            return ready(false);
+        };
+
+        tables
+            .legacy_tokens
+            .insert(id, (Some(String::new()), Some(String::new())));
+
+        ready(true)
+    }
+}
+
+impl RoomRepo for InMemoryStore {
+    fn find(&self, id: i64) -> RepoFuture<'_, Option<Room>> {
+        ready(self.lock().rooms.iter().find(|r| r.id == id).cloned())
+    }
+
+    fn list(&self, listing: RoomListing) -> RepoFuture<'_, Vec<Room>> {
+        let tables = self.lock();
+        let rooms = tables
+            .rooms
+            .iter()
+            .filter(|room| listing.includes(room))
+            .cloned()
+            .collect();
+
+        ready(rooms)
+    }
+
+    fn find_private(&self, created_by: i64, co_member: i64) -> RepoFuture<'_, Option<Room>> {
+        let tables = self.lock();
+        let room = tables.rooms.iter().find(|r| {
+            r.created_by == Some(created_by)
+                && r.co_member == Some(co_member)
+                && !r.is_group
+                && !r.is_space
+        });
+
+        ready(room.cloned())
+    }
+
+    fn create<'a>(&'a self, room: &'a NewRoom) -> RepoFuture<'a, Room> {
+        let mut tables = self.lock();
+        let id = tables.next_id();
+        let room = Room {
+            id,
+            room_name: Some(room.room_name.clone()),
+            is_group: room.is_group,
+            is_public: false,
+            is_space: false,
+            created_by: Some(room.created_by),
+            co_member: room.co_member,
+            co_members: Some(room.co_members.clone()),
+            room_profile_image: None,
+            bookmarked_by: Vec::new(),
+            archived_by: Vec::new(),
+            pinned_by: Vec::new(),
+            created_at: now(),
+            updated_at: now(),
+        };
+
+        tables.rooms.push(room.clone());
+        ready(room)
+    }
+
+    fn update<'a>(&'a self, id: i64, changes: &'a RoomChanges) -> RepoFuture<'a, Room> {
+        self.update_room(id, |room| {
+            if let Some(room_name) = &changes.room_name {
+                room.room_name = Some(room_name.clone());
+            }
+
+            if let Some(is_public) = changes.is_public {
+                room.is_public = is_public;
+            }
+        })
+    }
+
+    fn set_profile_image<'a>(
+        &'a self,
+        id: i64,
+        room_profile_image: &'a str,
+    ) -> RepoFuture<'a, Room> {
+        self.update_room(id, |room| {
+            room.room_profile_image = Some(room_profile_image.to_string());
+        })
+    }
+
+    fn set_archived(&self, id: i64, user_id: i64, archived: bool) -> RepoFuture<'_, ()> {
+        self.flag_room(id, |room| &mut room.archived_by, user_id, archived)
+    }
+
+    fn set_bookmarked(&self, id: i64, user_id: i64, bookmarked: bool) -> RepoFuture<'_, ()> {
+        self.flag_room(id, |room| &mut room.bookmarked_by, user_id, bookmarked)
+    }
+
+    fn set_pinned(&self, id: i64, user_id: i64, pinned: bool) -> RepoFuture<'_, ()> {
+        self.flag_room(id, |room| &mut room.pinned_by, user_id, pinned)
+    }
+
+    fn list_for_member(&self, user_id: i64) -> RepoFuture<'_, Vec<Room>> {
+        let tables = self.lock();
+        let rooms = tables
+            .rooms
+            .iter()
+            .filter(|room| {
+                tables
+                    .room_members
+                    .iter()
+                    .any(|m| m.room_id == room.id && m.user_id == user_id)
+            })
+            .cloned()
+            .collect();
+
+        ready(rooms)
+    }
+
+    fn member(&self, room_id: i64, user_id: i64) -> RepoFuture<'_, Option<RoomMember>> {
+        let tables = self.lock();
+        let member = tables
+            .room_members
+            .iter()
+            .find(|m| m.room_id == room_id && m.user_id == user_id);
+
+        ready(member.cloned())
+    }
+
+    fn members(&self, room_id: i64) -> RepoFuture<'_, Vec<RoomMember>> {
+        let tables = self.lock();
+        let members = tables
+            .room_members
+            .iter()
+            .filter(|m| m.room_id == room_id)
+            .cloned()
+            .collect();
+
+        ready(members)
+    }
+
+    fn presence(&self, room_id: i64) -> RepoFuture<'_, Vec<MemberPresence>> {
+        let tables = self.lock();
+        let mut members: Vec<MemberPresence> = tables
+            .users
+            .iter()
+            .filter(|user| {
+                tables
+                    .room_members
+                    .iter()
+                    .any(|m| m.room_id == room_id && m.user_id == user.id)
+            })
+            .map(|user| MemberPresence {
+                user_id: user.id,
+                full_name: user.full_name.clone(),
+                profile_image: user.profile_image.clone(),
+                status: user.status.clone(),
+                last_seen: user.last_seen.clone(),
+            })
+            .collect();
+
+        members.sort_by(|a, b| a.full_name.cmp(&b.full_name));
+        ready(members)
+    }
+
+    fn add_member<'a>(
+        &'a self,
+        room_id: i64,
+        user_id: i64,
+        role: RoomRole,
+        joined_at: &'a str,
+    ) -> RepoFuture<'a, bool> {
+        let mut tables = self.lock();
+
+        if tables
+            .room_members
+            .iter()
+            .any(|m| m.room_id == room_id && m.user_id == user_id)
+        {
+            return ready(false);
+        }
+
+        let id = tables.next_id();
+        tables.room_members.push(RoomMember {
+            id,
+            room_id,
+            user_id,
+            role: role.as_str().to_string(),
+            joined_at: joined_at.to_string(),
+            created_at: now(),
+            updated_at: now(),
+        });
+
+        ready(true)
+    }
+
+    fn remove_member(&self, room_id: i64, user_id: i64) -> RepoFuture<'_, bool> {
+        let mut tables = self.lock();
+        let before = tables.room_members.len();
+        tables
+            .room_members
+            .retain(|m| !(m.room_id == room_id && m.user_id == user_id));
+
+        ready(tables.room_members.len() < before)
+    }
+
+    fn set_member_role(&self, room_id: i64, user_id: i64, role: RoomRole) -> RepoFuture<'_, bool> {
+        let mut tables = self.lock();
+        let member = tables
+            .room_members
+            .iter_mut()
+            .find(|m| m.room_id == room_id && m.user_id == user_id);
+
+        ready(member.map(|m| m.role = role.as_str().to_string()).is_some())
+    }
+
+    fn add_co_member(&self, room_id: i64, user_id: i64) -> RepoFuture<'_, ()> {
+        if let Some(room) = self.lock().rooms.iter_mut().find(|r| r.id == room_id) {
+            room.co_members.get_or_insert_with(Vec::new).push(user_id);
+        }
+
+        ready(())
+    }
+
+    fn remove_co_member(&self, room_id: i64, user_id: i64) -> RepoFuture<'_, ()> {
+        if let Some(room) = self.lock().rooms.iter_mut().find(|r| r.id == room_id)
+            && let Some(co_members) = room.co_members.as_mut()
+        {
+            co_members.retain(|member| *member != user_id);
+        }
+
+        ready(())
+    }
+}
+
+impl MessageRepo for InMemoryStore {
+    fn find(&self, id: i64) -> RepoFuture<'_, Option<Message>> {
+        ready(self.lock().messages.iter().find(|m| m.id == id).cloned())
+    }
+
+    fn find_many<'a>(&'a self, ids: &'a [i64], viewer_id: i64) -> RepoFuture<'a, Vec<Message>> {
+        let tables = self.lock();
+        let messages = tables
+            .messages
+            .iter()
+            .filter(|m| {
+                ids.contains(&m.id) && !tables.message_deletions.contains(&(m.id, viewer_id))
+            })
+            .cloned()
+            .collect();
+
+        ready(messages)
+    }
+
+    fn create<'a>(&'a self, message: &'a NewMessage) -> RepoFuture<'a, Message> {
+        let mut tables = self.lock();
+        let id = tables.next_id();
+        let message = Message {
+            id,
+            room_id: message.room_id,
+            sender_id: Some(message.sender_id),
+            message_type: message.message_type.clone(),
+            text_content: message.text_content.clone(),
+            attachment_1: None,
+            attachment_2: None,
+            attachment_3: None,
+            attachment_4: None,
+            status: MessageStatus::Sent.as_str().to_string(),
+            sent_at: message.sent_at.clone(),
+            updates_counter: 0,
+            reply_to_message_id: message.reply_to_message_id,
+            is_deleted: false,
+            deleted_at: None,
+            created_at: now(),
+            updated_at: now(),
+        };
+
+        tables.messages.push(message.clone());
+        ready(message)
+    }
+
+    fn set_attachments<'a>(
+        &'a self,
+        id: i64,
+        attachments: &'a [Option<String>; 4],
+    ) -> RepoFuture<'a, Message> {
+        let mut tables = self.lock();
+        let Some(message) = tables.messages.iter_mut().find(|m| m.id == id) else {
+            return row_not_found();
+        };
+
+        let [attachment_1, attachment_2, attachment_3, attachment_4] = attachments.clone();
+        message.attachment_1 = attachment_1;
+        message.attachment_2 = attachment_2;
+        message.attachment_3 = attachment_3;
+        message.attachment_4 = attachment_4;
+
+        ready(message.clone())
+    }
+
+    fn update_text<'a>(
+        &'a self,
+        id: i64,
+        text_content: &'a str,
+        updates_counter: i32,
+    ) -> RepoFuture<'a, Message> {
+        let mut tables = self.lock();
+        let Some(message) = tables.messages.iter_mut().find(|m| m.id == id) else {
+            return row_not_found();
+        };
+
+        message.text_content = Some(text_content.to_string());
+        message.updates_counter = updates_counter;
+        message.status = MessageStatus::Updated.as_str().to_string();
+        message.updated_at = now();
+
+        ready(message.clone())
+    }
+
+    fn record_edit<'a>(
+        &'a self,
+        id: i64,
+        previous_content: &'a str,
+        new_content: &'a str,
+    ) -> RepoFuture<'a, ()> {
+        let mut tables = self.lock();
+        let edit_id = tables.next_id();
+        tables.message_edits.push(MessageEdit {
+            id: edit_id,
+            message_id: id,
+            previous_context: previous_content.to_string(),
+            new_content: new_content.to_string(),
+            created_at: Utc::now(),
+        });
+
+        ready(())
+    }
+
+    fn set_status(&self, id: i64, status: MessageStatus) -> RepoFuture<'_, Option<Message>> {
+        let mut tables = self.lock();
+        let message = tables
+            .messages
+            .iter_mut()
+            .find(|m| m.id == id && !m.is_deleted)
+            .map(|message| {
+                message.status = status.as_str().to_string();
+                message.clone()
+            });
+
+        ready(message)
+    }
+
+    fn tombstone<'a>(&'a self, id: i64, deleted_at: &'a str) -> RepoFuture<'a, Option<Message>> {
+        let mut tables = self.lock();
+        let message = tables
+            .messages
+            .iter_mut()
+            .find(|m| m.id == id && !m.is_deleted)
+            .map(|message| {
+                message.text_content = None;
+                message.attachment_1 = None;
+                message.attachment_2 = None;
+                message.attachment_3 = None;
+                message.attachment_4 = None;
+                message.is_deleted = true;
+                message.deleted_at = Some(deleted_at.to_string());
+                message.status = MessageStatus::Deleted.as_str().to_string();
+                message.updated_at = now();
+                message.clone()
+            });
+
+        if message.is_some() {
+            tables.message_edits.retain(|edit| edit.message_id != id);
+        }
+
+        ready(message)
+    }
+
+    fn edits(&self, id: i64) -> RepoFuture<'_, Vec<MessageEdit>> {
+        let tables = self.lock();
+        let edits = tables
+            .message_edits
+            .iter()
+            .rev()
+            .filter(|edit| edit.message_id == id)
+            .cloned()
+            .collect();
+
+        ready(edits)
+    }
+
+    fn list_for_room(&self, room_id: i64) -> RepoFuture<'_, Vec<Message>> {
+        let tables = self.lock();
+        let messages = tables
+            .messages
+            .iter()
+            .filter(|m| m.room_id == room_id)
+            .cloned()
+            .collect();
+
+        ready(messages)
+    }
+
+    fn page_for_room(
+        &self,
+        room_id: i64,
+        viewer_id: i64,
+        anchor: Option<Cursor>,
+        limit: i64,
+    ) -> RepoFuture<'_, Vec<Message>> {
+        let tables = self.lock();
+        let visible = tables.messages.iter().filter(|m| {
+            m.room_id == room_id && !tables.message_deletions.contains(&(m.id, viewer_id))
+        });
+
+        let messages: Vec<Message> = match anchor {
+            Some(Cursor::After(after)) => visible
+                .filter(|m| m.id > after)
+                .take(limit as usize)
+                .cloned()
+                .collect(),
+            Some(Cursor::Before(before)) => visible
+                .rev()
+                .filter(|m| m.id < before)
+                .take(limit as usize)
+                .cloned()
+                .collect(),
+            None => visible.rev().take(limit as usize).cloned().collect(),
+        };
+
+        ready(messages)
+    }
+
+    fn thread_room(&self, id: i64) -> RepoFuture<'_, Option<i64>> {
+        let tables = self.lock();
+        let room_id = tables
+            .messages
+            .iter()
+            .find(|m| m.id == id || m.reply_to_message_id == Some(id))
+            .map(|m| m.room_id);
+
+        ready(room_id)
+    }
+
+    fn replies(
+        &self,
+        id: i64,
+        viewer_id: i64,
+        after: Option<i64>,
+        limit: i64,
+    ) -> RepoFuture<'_, Vec<Message>> {
+        let tables = self.lock();
+        let replies = tables
+            .messages
+            .iter()
+            .filter(|m| {
+                m.reply_to_message_id == Some(id)
+                    && after.is_none_or(|after| m.id > after)
+                    && !tables.message_deletions.contains(&(m.id, viewer_id))
+            })
+            .take(limit as usize)
+            .cloned()
+            .collect();
+
+        ready(replies)
+    }
+
+    // plain case-insensitive substring matching stands in for Postgres full-text search
+    fn search<'a>(
+        &'a self,
+        user_id: i64,
+        search: &'a MessageSearch,
+    ) -> RepoFuture<'a, Vec<MessageSearchHit>> {
+        let tables = self.lock();
+        let query = search.query.to_lowercase();
+        let matches = |text: &str| text.to_lowercase().contains(&query);
+
+        let hits = tables
+            .messages
+            .iter()
+            .rev()
+            .filter(|m| {
+                !m.is_deleted
+                    && !tables.message_deletions.contains(&(m.id, user_id))
+                    && tables
+                        .room_members
+                        .iter()
+                        .any(|member| member.room_id == m.room_id && member.user_id == user_id)
+                    && search.room_id.is_none_or(|room_id| m.room_id == room_id)
+                    && search.sender_id.is_none_or(|sender_id| m.sender_id == Some(sender_id))
+                    && search
+                        .message_type
+                        .as_ref()
+                        .is_none_or(|message_type| &m.message_type == message_type)
+                    && search.sent_after.is_none_or(|after| {
+                        m.sent_at.parse::<i64>().is_ok_and(|sent_at| sent_at >= after)
+                    })
+                    && search.sent_before.is_none_or(|before| {
+                        m.sent_at.parse::<i64>().is_ok_and(|sent_at| sent_at <= before)
+                    })
+                    && search
+                        .has_attachment
+                        .is_none_or(|has_attachment| m.has_attachment() == has_attachment)
+            })
+            .filter_map(|m| {
+                let (snippet, matched_in) = match m.text_content.as_deref() {
+                    Some(text) if matches(text) => (text.to_string(), "content"),
+                    _ if search.include_edits => tables
+                        .message_edits
+                        .iter()
+                        .find(|edit| edit.message_id == m.id && matches(&edit.previous_context))
+                        .map(|edit| (edit.previous_context.clone(), "edit_history"))?,
+                    _ => return None,
+                };
+
+                Some(MessageSearchHit {
+                    id: m.id,
+                    room_id: m.room_id,
+                    sender_id: m.sender_id,
+                    message_type: m.message_type.clone(),
+                    text_content: m.text_content.clone(),
+                    attachment_1: m.attachment_1.clone(),
+                    attachment_2: m.attachment_2.clone(),
+                    attachment_3: m.attachment_3.clone(),
+                    attachment_4: m.attachment_4.clone(),
+                    status: m.status.clone(),
+                    sent_at: m.sent_at.clone(),
+                    created_at: m.created_at,
+                    updated_at: m.updated_at,
+                    rank: 1.0,
+                    snippet,
+                    matched_in: matched_in.to_string(),
+                })
+            })
+            .skip(search.offset as usize)
+            .take(search.limit as usize)
+            .collect();
+
+        ready(hits)
+    }
+
+    fn hide_for(&self, id: i64, user_id: i64) -> RepoFuture<'_, ()> {
+        self.lock().message_deletions.insert((id, user_id));
+
+        ready(())
+    }
+
+    fn set_archived(&self, id: i64, user_id: i64, archived: bool) -> RepoFuture<'_, ()> {
+        let mut tables = self.lock();
+
+        if archived {
+            tables.message_archives.insert((id, user_id));
+        } else {
+            tables.message_archives.remove(&(id, user_id));
+        }
+
+        ready(())
+    }
+
+    fn set_bookmarked(&self, id: i64, user_id: i64, bookmarked: bool) -> RepoFuture<'_, ()> {
+        let mut tables = self.lock();
+
+        if bookmarked {
+            tables.message_bookmarks.insert((id, user_id));
+        } else {
+            tables.message_bookmarks.remove(&(id, user_id));
+        }
+
+        ready(())
+    }
+
+    fn find_reaction(&self, id: i64, sender_id: i64) -> RepoFuture<'_, Option<MessageReaction>> {
+        let tables = self.lock();
+        let reaction = tables
+            .message_reactions
+            .iter()
+            .find(|r| r.message_id == id && r.sender_id == sender_id);
+
+        ready(reaction.cloned())
+    }
+
+    fn create_reaction<'a>(&'a self, reaction: &'a NewReaction) -> RepoFuture<'a, MessageReaction> {
+        let mut tables = self.lock();
+        let id = tables.next_id();
+        let reaction = MessageReaction {
+            id,
+            message_id: reaction.message_id,
+            room_id: reaction.room_id,
+            sender_id: reaction.sender_id,
+            reaction_type: reaction.reaction_type.clone(),
+            message_updates_counter: reaction.message_updates_counter,
+            created_at: now(),
+            updated_at: now(),
+        };
+
+        tables.message_reactions.push(reaction.clone());
+        ready(reaction)
+    }
+
+    fn update_reaction<'a>(
+        &'a self,
+        reaction_id: i64,
+        reaction: &'a NewReaction,
+    ) -> RepoFuture<'a, MessageReaction> {
+        let mut tables = self.lock();
+        let Some(existing) = tables
+            .message_reactions
+            .iter_mut()
+            .find(|r| r.id == reaction_id)
+        else {
+            return row_not_found();
+        };
+
+        existing.reaction_type = reaction.reaction_type.clone();
+        existing.message_updates_counter = reaction.message_updates_counter;
+        existing.updated_at = now();
+
+        ready(existing.clone())
+    }
+}
+
+impl ReceiptRepo for InMemoryStore {
+    fn create<'a>(&'a self, receipt: &'a NewReceipt, receiver_id: i64) -> RepoFuture<'a, ()> {
+        let mut tables = self.lock();
+        let id = tables.next_id();
+        tables.message_status_receipts.push(MessageStatusReceipt {
+            id,
+            message_id: receipt.message_id,
+            room_id: receipt.room_id,
+            sender_id: receipt.sender_id,
+            receiver_id: Some(receiver_id),
+            status: receipt.status.as_str().to_string(),
+            action: receipt.action.as_str().to_string(),
+            updates_count_tracker: receipt.updates_count_tracker,
+            created_at: now(),
+            updated_at: now(),
+        });
+
+        ready(())
+    }
+
+    fn receivers(
+        &self,
+        message_id: i64,
+        status: MessageStatus,
+        updates_count_tracker: i32,
+    ) -> RepoFuture<'_, Vec<i64>> {
+        let tables = self.lock();
+        let mut receivers: Vec<i64> = tables
+            .message_status_receipts
+            .iter()
+            .filter(|r| {
+                r.message_id == message_id
+                    && r.status == status.as_str()
+                    && r.updates_count_tracker == updates_count_tracker
+            })
+            .filter_map(|r| r.receiver_id)
+            .collect();
+
+        receivers.sort_unstable();
+        receivers.dedup();
+        ready(receivers)
+    }
+
+    fn list_for_message(&self, message_id: i64) -> RepoFuture<'_, Vec<MessageStatusReceipt>> {
+        let tables = self.lock();
+        let receipts = tables
+            .message_status_receipts
+            .iter()
+            .rev()
+            .filter(|r| r.message_id == message_id)
+            .cloned()
+            .collect();
+
+        ready(receipts)
+    }
+
+    fn mark_rooms_seen<'a>(&'a self, user_id: i64, room_ids: &'a [i64]) -> RepoFuture<'a, ()> {
+        let mut tables = self.lock();
+        let unseen: Vec<(i64, i64)> = tables
+            .messages
+            .iter()
+            .filter(|m| room_ids.contains(&m.room_id))
+            .filter(|m| {
+                !tables.message_status_receipts.iter().any(|r| {
+                    r.message_id == m.id
+                        && r.sender_id == user_id
+                        && r.status == MessageStatus::Seen.as_str()
+                })
+            })
+            .map(|m| (m.id, m.room_id))
+            .collect();
+
+        for (message_id, room_id) in unseen {
+            let id = tables.next_id();
+            tables.message_status_receipts.push(MessageStatusReceipt {
+                id,
+                message_id,
+                room_id,
+                sender_id: user_id,
+                receiver_id: None,
+                status: MessageStatus::Seen.as_str().to_string(),
+                action: ReceiptAction::OriginalSend.as_str().to_string(),
+                updates_count_tracker: 0,
+                created_at: now(),
+                updated_at: now(),
+            });
+        }
+
+        ready(())
+    }
+}
@@ -0,0 +1,196 @@
+#[cfg(test)]
+pub mod memory;
+pub mod postgres;
+
+use crate::models::message::{
+    Message, MessageEdit, MessageReaction, MessageSearch, MessageSearchHit, MessageStatus,
+    NewMessage, NewReaction,
+};
+use crate::models::receipt::{MessageStatusReceipt, NewReceipt};
+use crate::models::room::{NewRoom, Room, RoomChanges, RoomListing, RoomMember, RoomRole};
+use crate::models::user::{MemberPresence, NewUser, ProfileChanges, UserCredentials, UserProfile};
+use crate::utils::app_error::AppError;
+use crate::utils::pagination_cursor::Cursor;
+use sqlx::PgPool;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+pub type RepoFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, AppError>> + Send + 'a>>;
+
+/// Storage behind the business rules. Handlers and services only see `state.repos`, so the rules
+/// can be exercised against the in-memory store without a database.
+#[derive(Clone, Debug)]
+pub struct Repositories {
+    pub users: Arc<dyn UserRepo>,
+    pub rooms: Arc<dyn RoomRepo>,
+    pub messages: Arc<dyn MessageRepo>,
+    pub receipts: Arc<dyn ReceiptRepo>,
+}
+
+impl Repositories {
+    pub fn postgres(db: &PgPool) -> Self {
+        Repositories {
+            users: Arc::new(postgres::PgUserRepo::new(db.clone())),
+            rooms: Arc::new(postgres::PgRoomRepo::new(db.clone())),
+            messages: Arc::new(postgres::PgMessageRepo::new(db.clone())),
+            receipts: Arc::new(postgres::PgReceiptRepo::new(db.clone())),
+        }
+    }
+}
+
+pub trait UserRepo: Send + Sync + Debug {
+    fn find(&self, id: i64) -> RepoFuture<'_, Option<UserProfile>>;
+    fn find_by_email<'a>(&'a self, email: &'a str) -> RepoFuture<'a, Option<UserProfile>>;
+    fn find_by_phone_number<'a>(&'a self, phone_number: &'a str)
+    -> RepoFuture<'a, Option<UserProfile>>;
+    fn credentials<'a>(&'a self, email: &'a str) -> RepoFuture<'a, Option<UserCredentials>>;
+    fn list(&self) -> RepoFuture<'_, Vec<UserProfile>>;
+    /// Fails with a conflict when the email is already taken.
+    fn create<'a>(&'a self, user: &'a NewUser) -> RepoFuture<'a, UserProfile>;
+    fn update_profile<'a>(
+        &'a self,
+        id: i64,
+        changes: &'a ProfileChanges,
+    ) -> RepoFuture<'a, Option<UserProfile>>;
+    /// `password` is already hashed.
+    fn set_password<'a>(&'a self, id: i64, password: &'a str)
+    -> RepoFuture<'a, Option<UserProfile>>;
+    fn set_profile_image<'a>(
+        &'a self,
+        id: i64,
+        profile_image: &'a str,
+    ) -> RepoFuture<'a, Option<UserProfile>>;
+    fn set_active(&self, id: i64, is_active: bool) -> RepoFuture<'_, Option<UserProfile>>;
+    fn set_admin(&self, id: i64, is_admin: bool) -> RepoFuture<'_, Option<UserProfile>>;
+    /// The single refresh token kept on the user for clients on the legacy, session-less flow.
+    fn legacy_refresh_token(&self, id: i64) -> RepoFuture<'_, Option<String>>;
+    /// Keeps the legacy token columns in step with the latest sign-in.
+    fn store_legacy_tokens<'a>(
+        &'a self,
+        id: i64,
+        access_token: Option<&'a str>,
+        refresh_token: Option<&'a str>,
+    ) -> RepoFuture<'a, ()>;
+    /// Clears the legacy tokens. Returns false when no user has that email.
+    fn log_out<'a>(&'a self, email: &'a str) -> RepoFuture<'a, bool>;
+}
+
+pub trait RoomRepo: Send + Sync + Debug {
+    fn find(&self, id: i64) -> RepoFuture<'_, Option<Room>>;
+    fn list(&self, listing: RoomListing) -> RepoFuture<'_, Vec<Room>>;
+    /// The 1-on-1 room `created_by` already opened with `co_member`, if any.
+    fn find_private(&self, created_by: i64, co_member: i64) -> RepoFuture<'_, Option<Room>>;
+    fn create<'a>(&'a self, room: &'a NewRoom) -> RepoFuture<'a, Room>;
+    fn update<'a>(&'a self, id: i64, changes: &'a RoomChanges) -> RepoFuture<'a, Room>;
+    fn set_profile_image<'a>(&'a self, id: i64, room_profile_image: &'a str)
+    -> RepoFuture<'a, Room>;
+    fn set_archived(&self, id: i64, user_id: i64, archived: bool) -> RepoFuture<'_, ()>;
+    fn set_bookmarked(&self, id: i64, user_id: i64, bookmarked: bool) -> RepoFuture<'_, ()>;
+    fn set_pinned(&self, id: i64, user_id: i64, pinned: bool) -> RepoFuture<'_, ()>;
+    /// Every room `user_id` has a membership in.
+    fn list_for_member(&self, user_id: i64) -> RepoFuture<'_, Vec<Room>>;
+    fn member(&self, room_id: i64, user_id: i64) -> RepoFuture<'_, Option<RoomMember>>;
+    fn members(&self, room_id: i64) -> RepoFuture<'_, Vec<RoomMember>>;
+    /// Sorted by name.
+    fn presence(&self, room_id: i64) -> RepoFuture<'_, Vec<MemberPresence>>;
+    /// Returns false when the user already is a member.
+    fn add_member<'a>(
+        &'a self,
+        room_id: i64,
+        user_id: i64,
+        role: RoomRole,
+        joined_at: &'a str,
+    ) -> RepoFuture<'a, bool>;
+    /// Returns false when the user was not a member.
+    fn remove_member(&self, room_id: i64, user_id: i64) -> RepoFuture<'_, bool>;
+    /// Returns false when the user is not a member.
+    fn set_member_role(&self, room_id: i64, user_id: i64, role: RoomRole) -> RepoFuture<'_, bool>;
+    fn add_co_member(&self, room_id: i64, user_id: i64) -> RepoFuture<'_, ()>;
+    fn remove_co_member(&self, room_id: i64, user_id: i64) -> RepoFuture<'_, ()>;
+}
+
+pub trait MessageRepo: Send + Sync + Debug {
+    fn find(&self, id: i64) -> RepoFuture<'_, Option<Message>>;
+    /// Leaves out the ones `viewer_id` deleted for themselves.
+    fn find_many<'a>(&'a self, ids: &'a [i64], viewer_id: i64) -> RepoFuture<'a, Vec<Message>>;
+    fn create<'a>(&'a self, message: &'a NewMessage) -> RepoFuture<'a, Message>;
+    fn set_attachments<'a>(
+        &'a self,
+        id: i64,
+        attachments: &'a [Option<String>; 4],
+    ) -> RepoFuture<'a, Message>;
+    /// Replaces the text and marks the message as updated.
+    fn update_text<'a>(
+        &'a self,
+        id: i64,
+        text_content: &'a str,
+        updates_counter: i32,
+    ) -> RepoFuture<'a, Message>;
+    fn record_edit<'a>(
+        &'a self,
+        id: i64,
+        previous_content: &'a str,
+        new_content: &'a str,
+    ) -> RepoFuture<'a, ()>;
+    /// Deleted messages keep their status - returns None for them.
+    fn set_status(&self, id: i64, status: MessageStatus) -> RepoFuture<'_, Option<Message>>;
+    /// Clears content and attachments. Returns None when the message was already deleted.
+    fn tombstone<'a>(&'a self, id: i64, deleted_at: &'a str) -> RepoFuture<'a, Option<Message>>;
+    /// Newest first.
+    fn edits(&self, id: i64) -> RepoFuture<'_, Vec<MessageEdit>>;
+    /// Oldest first.
+    fn list_for_room(&self, room_id: i64) -> RepoFuture<'_, Vec<Message>>;
+    /// Up to `limit` messages of the room next to `anchor`, leaving out the ones `viewer_id`
+    /// deleted for themselves. Rows come in the order they are walked: newest first, or oldest
+    /// first after an `After` anchor.
+    fn page_for_room(
+        &self,
+        room_id: i64,
+        viewer_id: i64,
+        anchor: Option<Cursor>,
+        limit: i64,
+    ) -> RepoFuture<'_, Vec<Message>>;
+    /// The room a message was posted in, worked out from its replies once the message is gone.
+    fn thread_room(&self, id: i64) -> RepoFuture<'_, Option<i64>>;
+    /// Oldest first, leaving out the ones `viewer_id` deleted for themselves.
+    fn replies(
+        &self,
+        id: i64,
+        viewer_id: i64,
+        after: Option<i64>,
+        limit: i64,
+    ) -> RepoFuture<'_, Vec<Message>>;
+    /// Best match first, only across rooms `user_id` is a member of.
+    fn search<'a>(
+        &'a self,
+        user_id: i64,
+        search: &'a MessageSearch,
+    ) -> RepoFuture<'a, Vec<MessageSearchHit>>;
+    fn hide_for(&self, id: i64, user_id: i64) -> RepoFuture<'_, ()>;
+    fn set_archived(&self, id: i64, user_id: i64, archived: bool) -> RepoFuture<'_, ()>;
+    fn set_bookmarked(&self, id: i64, user_id: i64, bookmarked: bool) -> RepoFuture<'_, ()>;
+    fn find_reaction(&self, id: i64, sender_id: i64) -> RepoFuture<'_, Option<MessageReaction>>;
+    fn create_reaction<'a>(&'a self, reaction: &'a NewReaction) -> RepoFuture<'a, MessageReaction>;
+    fn update_reaction<'a>(
+        &'a self,
+        reaction_id: i64,
+        reaction: &'a NewReaction,
+    ) -> RepoFuture<'a, MessageReaction>;
+}
+
+pub trait ReceiptRepo: Send + Sync + Debug {
+    fn create<'a>(&'a self, receipt: &'a NewReceipt, receiver_id: i64) -> RepoFuture<'a, ()>;
+    /// Receivers holding a `status` receipt for the given revision of a message.
+    fn receivers(
+        &self,
+        message_id: i64,
+        status: MessageStatus,
+        updates_count_tracker: i32,
+    ) -> RepoFuture<'_, Vec<i64>>;
+    /// Newest first.
+    fn list_for_message(&self, message_id: i64) -> RepoFuture<'_, Vec<MessageStatusReceipt>>;
+    /// Records the user as having seen every message of the rooms they haven't seen yet.
+    fn mark_rooms_seen<'a>(&'a self, user_id: i64, room_ids: &'a [i64]) -> RepoFuture<'a, ()>;
+}
@@ -0,0 +1,1207 @@
+use crate::models::message::{
+    Message, MessageEdit, MessageReaction, MessageSearch, MessageSearchHit, MessageStatus,
+    NewMessage, NewReaction,
+};
+use crate::models::receipt::{MessageStatusReceipt, NewReceipt};
+use crate::models::room::{NewRoom, Room, RoomChanges, RoomListing, RoomMember, RoomRole};
+use crate::models::user::{MemberPresence, NewUser, ProfileChanges, UserCredentials, UserProfile};
+use crate::repositories::{MessageRepo, ReceiptRepo, RepoFuture, RoomRepo, UserRepo};
+use crate::utils::app_error::AppError;
+use crate::utils::pagination_cursor::Cursor;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+// everything but the password hash, tokens and two-factor secrets
+const USER_PROFILE_COLUMNS: &str = "id, full_name, email, profile_image, status, last_seen, is_admin, is_active, email_verified, country, phone_number, created_at, updated_at";
+
+#[derive(Debug)]
+pub struct PgUserRepo {
+    db: PgPool,
+}
+
+impl PgUserRepo {
+    pub fn new(db: PgPool) -> Self {
+        PgUserRepo { db }
+    }
+}
+
+impl UserRepo for PgUserRepo {
+    fn find(&self, id: i64) -> RepoFuture<'_, Option<UserProfile>> {
+        Box::pin(async move {
+            let user = sqlx::query_as::<_, UserProfile>(&format!(
+                "SELECT {} FROM users WHERE id = $1",
+                USER_PROFILE_COLUMNS
+            ))
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(user)
+        })
+    }
+
+    fn find_by_email<'a>(&'a self, email: &'a str) -> RepoFuture<'a, Option<UserProfile>> {
+        Box::pin(async move {
+            let user = sqlx::query_as::<_, UserProfile>(&format!(
+                "SELECT {} FROM users WHERE email = $1",
+                USER_PROFILE_COLUMNS
+            ))
+            .bind(email)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(user)
+        })
+    }
+
+    fn find_by_phone_number<'a>(
+        &'a self,
+        phone_number: &'a str,
+    ) -> RepoFuture<'a, Option<UserProfile>> {
+        Box::pin(async move {
+            let user = sqlx::query_as::<_, UserProfile>(&format!(
+                "SELECT {} FROM users WHERE phone_number = $1 LIMIT 1",
+                USER_PROFILE_COLUMNS
+            ))
+            .bind(phone_number)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(user)
+        })
+    }
+
+    fn credentials<'a>(&'a self, email: &'a str) -> RepoFuture<'a, Option<UserCredentials>> {
+        Box::pin(async move {
+            let credentials = sqlx::query_as::<_, UserCredentials>(
+                "SELECT id, email, password, email_verified, totp_enabled FROM users WHERE email = $1",
+            )
+            .bind(email)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(credentials)
+        })
+    }
+
+    fn list(&self) -> RepoFuture<'_, Vec<UserProfile>> {
+        Box::pin(async move {
+            let users = sqlx::query_as::<_, UserProfile>(&format!(
+                "SELECT {} FROM users ORDER BY id",
+                USER_PROFILE_COLUMNS
+            ))
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(users)
+        })
+    }
+
+    fn create<'a>(&'a self, user: &'a NewUser) -> RepoFuture<'a, UserProfile> {
+        Box::pin(async move {
+            let user = sqlx::query_as::<_, UserProfile>(&format!(
+                r#"
+                INSERT INTO users (email, password, full_name, profile_image, country, phone_number)
+                VALUES ($1, $2, $3, '', $4, $5)
+                RETURNING {}
+                "#,
+                USER_PROFILE_COLUMNS
+            ))
+            .bind(&user.email)
+            .bind(&user.password)
+            .bind(&user.full_name)
+            .bind(&user.country)
+            .bind(&user.phone_number)
+            .fetch_one(&self.db)
+            .await?;
+
+            Ok(user)
+        })
+    }
+
+    fn update_profile<'a>(
+        &'a self,
+        id: i64,
+        changes: &'a ProfileChanges,
+    ) -> RepoFuture<'a, Option<UserProfile>> {
+        Box::pin(async move {
+            let mut query = QueryBuilder::<Postgres>::new("UPDATE users SET updated_at = NOW()");
+
+            let fields = [
+                ("full_name", &changes.full_name),
+                ("email", &changes.email),
+                ("country", &changes.country),
+                ("phone_number", &changes.phone_number),
+                ("status", &changes.status),
+                ("last_seen", &changes.last_seen),
+            ];
+
+            for (column, value) in fields {
+                if let Some(value) = value {
+                    query.push(format!(", {} = ", column)).push_bind(value);
+                }
+            }
+
+            query
+                .push(" WHERE id = ")
+                .push_bind(id)
+                .push(format!(" RETURNING {}", USER_PROFILE_COLUMNS));
+
+            let user = query
+                .build_query_as::<UserProfile>()
+                .fetch_optional(&self.db)
+                .await?;
+
+            Ok(user)
+        })
+    }
+
+    fn set_password<'a>(
+        &'a self,
+        id: i64,
+        password: &'a str,
+    ) -> RepoFuture<'a, Option<UserProfile>> {
+        Box::pin(async move {
+            let user = sqlx::query_as::<_, UserProfile>(&format!(
+                "UPDATE users SET password = $1, updated_at = NOW() WHERE id = $2 RETURNING {}",
+                USER_PROFILE_COLUMNS
+            ))
+            .bind(password)
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(user)
+        })
+    }
+
+    fn set_profile_image<'a>(
+        &'a self,
+        id: i64,
+        profile_image: &'a str,
+    ) -> RepoFuture<'a, Option<UserProfile>> {
+        Box::pin(async move {
+            let user = sqlx::query_as::<_, UserProfile>(&format!(
+                "UPDATE users SET profile_image = $1, updated_at = NOW() WHERE id = $2 RETURNING {}",
+                USER_PROFILE_COLUMNS
+            ))
+            .bind(profile_image)
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(user)
+        })
+    }
+
+    fn set_active(&self, id: i64, is_active: bool) -> RepoFuture<'_, Option<UserProfile>> {
+        Box::pin(async move {
+            let user = sqlx::query_as::<_, UserProfile>(&format!(
+                "UPDATE users SET is_active = $1, updated_at = NOW() WHERE id = $2 RETURNING {}",
+                USER_PROFILE_COLUMNS
+            ))
+            .bind(is_active)
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(user)
+        })
+    }
+
+    fn set_admin(&self, id: i64, is_admin: bool) -> RepoFuture<'_, Option<UserProfile>> {
+        Box::pin(async move {
+            let user = sqlx::query_as::<_, UserProfile>(&format!(
+                "UPDATE users SET is_admin = $1, updated_at = NOW() WHERE id = $2 RETURNING {}",
+                USER_PROFILE_COLUMNS
+            ))
+            .bind(is_admin)
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(user)
+        })
+    }
+
+    fn legacy_refresh_token(&self, id: i64) -> RepoFuture<'_, Option<String>> {
+        Box::pin(async move {
+            let token = sqlx::query_scalar::<_, Option<String>>(
+                "SELECT refresh_token FROM users WHERE id = $1",
+            )
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(token.flatten())
+        })
+    }
+
+    fn store_legacy_tokens<'a>(
+        &'a self,
+        id: i64,
+        access_token: Option<&'a str>,
+        refresh_token: Option<&'a str>,
+    ) -> RepoFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                r#"
+                UPDATE users
+                SET
+                    access_token = $1,
+                    refresh_token = $2,
+                    is_logged_out = FALSE,
+                    updated_at = NOW()
+                WHERE id = $3
+                "#,
+            )
+            .bind(access_token)
+            .bind(refresh_token)
+            .bind(id)
+            .execute(&self.db)
+            .await?;
+
+            Ok(())
+        })
+    }
+
+    fn log_out<'a>(&'a self, email: &'a str) -> RepoFuture<'a, bool> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                r#"
+                UPDATE users
+                SET access_token = '', refresh_token = '', is_logged_out = TRUE, updated_at = NOW()
+                WHERE email = $1
+                "#,
+            )
+            .bind(email)
+            .execute(&self.db)
+            .await?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct PgRoomRepo {
+    db: PgPool,
+}
+
+impl PgRoomRepo {
+    pub fn new(db: PgPool) -> Self {
+        PgRoomRepo { db }
+    }
+}
+
+impl RoomRepo for PgRoomRepo {
+    fn find(&self, id: i64) -> RepoFuture<'_, Option<Room>> {
+        Box::pin(async move {
+            let room = sqlx::query_as::<_, Room>("SELECT * FROM rooms WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.db)
+                .await?;
+
+            Ok(room)
+        })
+    }
+
+    fn list(&self, listing: RoomListing) -> RepoFuture<'_, Vec<Room>> {
+        Box::pin(async move {
+            let filter = match listing {
+                RoomListing::All => "TRUE",
+                RoomListing::Open => "is_public = TRUE",
+                RoomListing::Closed => "is_public = FALSE",
+                RoomListing::Group => "is_group = TRUE",
+                RoomListing::Private => "is_group = FALSE",
+            };
+
+            let rooms = sqlx::query_as::<_, Room>(&format!(
+                "SELECT * FROM rooms WHERE is_space = FALSE AND {} ORDER BY id",
+                filter
+            ))
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(rooms)
+        })
+    }
+
+    fn find_private(&self, created_by: i64, co_member: i64) -> RepoFuture<'_, Option<Room>> {
+        Box::pin(async move {
+            let room = sqlx::query_as::<_, Room>(
+                r#"
+                SELECT *
+                FROM rooms
+                WHERE created_by = $1
+                    AND co_member = $2
+                    AND is_group = false
+                    AND is_space = false
+                LIMIT 1
+                "#,
+            )
+            .bind(created_by)
+            .bind(co_member)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(room)
+        })
+    }
+
+    fn create<'a>(&'a self, room: &'a NewRoom) -> RepoFuture<'a, Room> {
+        Box::pin(async move {
+            let room = sqlx::query_as::<_, Room>(
+                r#"
+                INSERT INTO rooms (room_name, is_group, created_by, co_member, co_members)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING *
+                "#,
+            )
+            .bind(&room.room_name)
+            .bind(room.is_group)
+            .bind(room.created_by)
+            .bind(room.co_member)
+            .bind(&room.co_members)
+            .fetch_one(&self.db)
+            .await?;
+
+            Ok(room)
+        })
+    }
+
+    fn update<'a>(&'a self, id: i64, changes: &'a RoomChanges) -> RepoFuture<'a, Room> {
+        Box::pin(async move {
+            let room = sqlx::query_as::<_, Room>(
+                r#"
+                UPDATE rooms
+                SET
+                    room_name = COALESCE($1, room_name),
+                    is_public = COALESCE($2, is_public),
+                    updated_at = NOW()
+                WHERE id = $3
+                RETURNING *
+                "#,
+            )
+            .bind(&changes.room_name)
+            .bind(changes.is_public)
+            .bind(id)
+            .fetch_one(&self.db)
+            .await?;
+
+            Ok(room)
+        })
+    }
+
+    fn set_profile_image<'a>(
+        &'a self,
+        id: i64,
+        room_profile_image: &'a str,
+    ) -> RepoFuture<'a, Room> {
+        Box::pin(async move {
+            let room = sqlx::query_as::<_, Room>(
+                "UPDATE rooms SET room_profile_image = $1, updated_at = NOW() WHERE id = $2 RETURNING *",
+            )
+            .bind(room_profile_image)
+            .bind(id)
+            .fetch_one(&self.db)
+            .await?;
+
+            Ok(room)
+        })
+    }
+
+    fn set_archived(&self, id: i64, user_id: i64, archived: bool) -> RepoFuture<'_, ()> {
+        Box::pin(set_room_flag(
+            &self.db,
+            "archived_by",
+            id,
+            user_id,
+            archived,
+        ))
+    }
+
+    fn set_bookmarked(&self, id: i64, user_id: i64, bookmarked: bool) -> RepoFuture<'_, ()> {
+        Box::pin(set_room_flag(
+            &self.db,
+            "bookmarked_by",
+            id,
+            user_id,
+            bookmarked,
+        ))
+    }
+
+    fn set_pinned(&self, id: i64, user_id: i64, pinned: bool) -> RepoFuture<'_, ()> {
+        Box::pin(set_room_flag(&self.db, "pinned_by", id, user_id, pinned))
+    }
+
+    fn list_for_member(&self, user_id: i64) -> RepoFuture<'_, Vec<Room>> {
+        Box::pin(async move {
+            let rooms = sqlx::query_as::<_, Room>(
+                r#"
+                SELECT r.*
+                FROM rooms r
+                JOIN room_members rm ON rm.room_id = r.id
+                WHERE rm.user_id = $1
+                ORDER BY r.id
+                "#,
+            )
+            .bind(user_id)
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(rooms)
+        })
+    }
+
+    fn member(&self, room_id: i64, user_id: i64) -> RepoFuture<'_, Option<RoomMember>> {
+        Box::pin(async move {
+            let member = sqlx::query_as::<_, RoomMember>(
+                "SELECT * FROM room_members WHERE room_id = $1 AND user_id = $2",
+            )
+            .bind(room_id)
+            .bind(user_id)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(member)
+        })
+    }
+
+    fn members(&self, room_id: i64) -> RepoFuture<'_, Vec<RoomMember>> {
+        Box::pin(async move {
+            let members = sqlx::query_as::<_, RoomMember>(
+                "SELECT * FROM room_members WHERE room_id = $1 ORDER BY id",
+            )
+            .bind(room_id)
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(members)
+        })
+    }
+
+    fn presence(&self, room_id: i64) -> RepoFuture<'_, Vec<MemberPresence>> {
+        Box::pin(async move {
+            let members = sqlx::query_as::<_, MemberPresence>(
+                r#"
+                SELECT u.id AS user_id, u.full_name, u.profile_image, u.status, u.last_seen
+                FROM users u
+                INNER JOIN room_members rm ON rm.user_id = u.id
+                WHERE rm.room_id = $1
+                ORDER BY u.full_name ASC
+                "#,
+            )
+            .bind(room_id)
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(members)
+        })
+    }
+
+    fn add_member<'a>(
+        &'a self,
+        room_id: i64,
+        user_id: i64,
+        role: RoomRole,
+        joined_at: &'a str,
+    ) -> RepoFuture<'a, bool> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                r#"
+                INSERT INTO room_members (room_id, user_id, role, joined_at)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (room_id, user_id) DO NOTHING
+                "#,
+            )
+            .bind(room_id)
+            .bind(user_id)
+            .bind(role.as_str())
+            .bind(joined_at)
+            .execute(&self.db)
+            .await?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn remove_member(&self, room_id: i64, user_id: i64) -> RepoFuture<'_, bool> {
+        Box::pin(async move {
+            let result =
+                sqlx::query("DELETE FROM room_members WHERE room_id = $1 AND user_id = $2")
+                    .bind(room_id)
+                    .bind(user_id)
+                    .execute(&self.db)
+                    .await?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn set_member_role(&self, room_id: i64, user_id: i64, role: RoomRole) -> RepoFuture<'_, bool> {
+        Box::pin(async move {
+            let result = sqlx::query(
+                "UPDATE room_members SET role = $1, updated_at = NOW() WHERE room_id = $2 AND user_id = $3",
+            )
+            .bind(role.as_str())
+            .bind(room_id)
+            .bind(user_id)
+            .execute(&self.db)
+            .await?;
+
+            Ok(result.rows_affected() > 0)
+        })
+    }
+
+    fn add_co_member(&self, room_id: i64, user_id: i64) -> RepoFuture<'_, ()> {
+        Box::pin(async move {
+            sqlx::query("UPDATE rooms SET co_members = array_append(co_members, $1) WHERE id = $2")
+                .bind(user_id)
+                .bind(room_id)
+                .execute(&self.db)
+                .await?;
+
+            Ok(())
+        })
+    }
+
+    fn remove_co_member(&self, room_id: i64, user_id: i64) -> RepoFuture<'_, ()> {
+        Box::pin(async move {
+            sqlx::query("UPDATE rooms SET co_members = array_remove(co_members, $1) WHERE id = $2")
+                .bind(user_id)
+                .bind(room_id)
+                .execute(&self.db)
+                .await?;
+
+            Ok(())
+        })
+    }
+}
+
+// archived_by, bookmarked_by and pinned_by hold the ids of the users who flagged the room
+async fn set_room_flag(
+    db: &PgPool,
+    column: &'static str,
+    id: i64,
+    user_id: i64,
+    flagged: bool,
+) -> Result<(), AppError> {
+    let query = if flagged {
+        format!(
+            "UPDATE rooms SET {0} = array_append({0}, $1) WHERE id = $2 AND NOT ($1 = ANY({0}))",
+            column
+        )
+    } else {
+        format!(
+            "UPDATE rooms SET {0} = array_remove({0}, $1) WHERE id = $2",
+            column
+        )
+    };
+
+    sqlx::query(&query)
+        .bind(user_id)
+        .bind(id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct PgMessageRepo {
+    db: PgPool,
+}
+
+impl PgMessageRepo {
+    pub fn new(db: PgPool) -> Self {
+        PgMessageRepo { db }
+    }
+}
+
+impl MessageRepo for PgMessageRepo {
+    fn find(&self, id: i64) -> RepoFuture<'_, Option<Message>> {
+        Box::pin(async move {
+            let message = sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.db)
+                .await?;
+
+            Ok(message)
+        })
+    }
+
+    fn find_many<'a>(&'a self, ids: &'a [i64], viewer_id: i64) -> RepoFuture<'a, Vec<Message>> {
+        Box::pin(async move {
+            let messages = sqlx::query_as::<_, Message>(
+                r#"
+                SELECT *
+                FROM messages
+                WHERE id = ANY($1)
+                    AND NOT EXISTS (
+                        SELECT 1 FROM message_deletions md
+                        WHERE md.message_id = messages.id AND md.user_id = $2
+                    )
+                "#,
+            )
+            .bind(ids)
+            .bind(viewer_id)
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(messages)
+        })
+    }
+
+    fn create<'a>(&'a self, message: &'a NewMessage) -> RepoFuture<'a, Message> {
+        Box::pin(async move {
+            let message = sqlx::query_as::<_, Message>(
+                r#"
+                INSERT INTO messages (room_id, sender_id, type, text_content, status, sent_at, reply_to_message_id)
+                VALUES ($1, $2, $3, $4, 'sent', $5, $6)
+                RETURNING *
+                "#,
+            )
+            .bind(message.room_id)
+            .bind(message.sender_id)
+            .bind(&message.message_type)
+            .bind(&message.text_content)
+            .bind(&message.sent_at)
+            .bind(message.reply_to_message_id)
+            .fetch_one(&self.db)
+            .await?;
+
+            Ok(message)
+        })
+    }
+
+    fn set_attachments<'a>(
+        &'a self,
+        id: i64,
+        attachments: &'a [Option<String>; 4],
+    ) -> RepoFuture<'a, Message> {
+        Box::pin(async move {
+            let message = sqlx::query_as::<_, Message>(
+                r#"
+                UPDATE messages
+                SET attachment_1 = $1, attachment_2 = $2, attachment_3 = $3, attachment_4 = $4
+                WHERE id = $5
+                RETURNING *
+                "#,
+            )
+            .bind(&attachments[0])
+            .bind(&attachments[1])
+            .bind(&attachments[2])
+            .bind(&attachments[3])
+            .bind(id)
+            .fetch_one(&self.db)
+            .await?;
+
+            Ok(message)
+        })
+    }
+
+    fn update_text<'a>(
+        &'a self,
+        id: i64,
+        text_content: &'a str,
+        updates_counter: i32,
+    ) -> RepoFuture<'a, Message> {
+        Box::pin(async move {
+            let message = sqlx::query_as::<_, Message>(
+                r#"
+                UPDATE messages
+                SET text_content = $1, updates_counter = $2, status = 'updated', updated_at = NOW()
+                WHERE id = $3
+                RETURNING *
+                "#,
+            )
+            .bind(text_content)
+            .bind(updates_counter)
+            .bind(id)
+            .fetch_one(&self.db)
+            .await?;
+
+            Ok(message)
+        })
+    }
+
+    fn record_edit<'a>(
+        &'a self,
+        id: i64,
+        previous_content: &'a str,
+        new_content: &'a str,
+    ) -> RepoFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                "INSERT INTO message_edits (message_id, previous_context, new_content) VALUES ($1, $2, $3)",
+            )
+            .bind(id)
+            .bind(previous_content)
+            .bind(new_content)
+            .execute(&self.db)
+            .await?;
+
+            Ok(())
+        })
+    }
+
+    fn set_status(&self, id: i64, status: MessageStatus) -> RepoFuture<'_, Option<Message>> {
+        Box::pin(async move {
+            let message = sqlx::query_as::<_, Message>(
+                "UPDATE messages SET status = $1 WHERE id = $2 AND is_deleted = FALSE RETURNING *",
+            )
+            .bind(status.as_str())
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(message)
+        })
+    }
+
+    fn tombstone<'a>(&'a self, id: i64, deleted_at: &'a str) -> RepoFuture<'a, Option<Message>> {
+        Box::pin(async move {
+            let mut tx = self.db.begin().await?;
+
+            let message = sqlx::query_as::<_, Message>(
+                r#"
+                UPDATE messages
+                SET
+                    text_content = NULL,
+                    attachment_1 = NULL,
+                    attachment_2 = NULL,
+                    attachment_3 = NULL,
+                    attachment_4 = NULL,
+                    is_deleted = TRUE,
+                    deleted_at = $1,
+                    status = 'deleted',
+                    updated_at = NOW()
+                WHERE id = $2 AND is_deleted = FALSE
+                RETURNING *
+                "#,
+            )
+            .bind(deleted_at)
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            // earlier versions would otherwise outlive the message in its edit history
+            if message.is_some() {
+                sqlx::query("DELETE FROM message_edits WHERE message_id = $1")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+
+            tx.commit().await?;
+
+            Ok(message)
+        })
+    }
+
+    fn edits(&self, id: i64) -> RepoFuture<'_, Vec<MessageEdit>> {
+        Box::pin(async move {
+            let edits = sqlx::query_as::<_, MessageEdit>(
+                "SELECT * FROM message_edits WHERE message_id = $1 ORDER BY created_at DESC",
+            )
+            .bind(id)
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(edits)
+        })
+    }
+
+    fn list_for_room(&self, room_id: i64) -> RepoFuture<'_, Vec<Message>> {
+        Box::pin(async move {
+            let messages = sqlx::query_as::<_, Message>(
+                "SELECT * FROM messages WHERE room_id = $1 ORDER BY created_at ASC, id ASC",
+            )
+            .bind(room_id)
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(messages)
+        })
+    }
+
+    fn page_for_room(
+        &self,
+        room_id: i64,
+        viewer_id: i64,
+        anchor: Option<Cursor>,
+        limit: i64,
+    ) -> RepoFuture<'_, Vec<Message>> {
+        Box::pin(async move {
+            let (bound, order, anchor_id) = match anchor {
+                Some(Cursor::After(after)) => ("id > $3", "ASC", Some(after)),
+                Some(Cursor::Before(before)) => ("id < $3", "DESC", Some(before)),
+                None => ("$3::BIGINT IS NULL", "DESC", None),
+            };
+
+            let messages = sqlx::query_as::<_, Message>(&format!(
+                r#"
+                SELECT *
+                FROM messages
+                WHERE room_id = $1 AND {}
+                    AND NOT EXISTS (
+                        SELECT 1 FROM message_deletions md
+                        WHERE md.message_id = messages.id AND md.user_id = $2
+                    )
+                ORDER BY id {}
+                LIMIT $4
+                "#,
+                bound, order
+            ))
+            .bind(room_id)
+            .bind(viewer_id)
+            .bind(anchor_id)
+            .bind(limit)
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(messages)
+        })
+    }
+
+    fn thread_room(&self, id: i64) -> RepoFuture<'_, Option<i64>> {
+        Box::pin(async move {
+            let room_id = sqlx::query_scalar::<_, i64>(
+                "SELECT room_id FROM messages WHERE id = $1 OR reply_to_message_id = $1 LIMIT 1",
+            )
+            .bind(id)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(room_id)
+        })
+    }
+
+    fn replies(
+        &self,
+        id: i64,
+        viewer_id: i64,
+        after: Option<i64>,
+        limit: i64,
+    ) -> RepoFuture<'_, Vec<Message>> {
+        Box::pin(async move {
+            let replies = sqlx::query_as::<_, Message>(
+                r#"
+                SELECT *
+                FROM messages
+                WHERE reply_to_message_id = $1 AND ($2::BIGINT IS NULL OR id > $2)
+                    AND NOT EXISTS (
+                        SELECT 1 FROM message_deletions md
+                        WHERE md.message_id = messages.id AND md.user_id = $3
+                    )
+                ORDER BY id ASC
+                LIMIT $4
+                "#,
+            )
+            .bind(id)
+            .bind(after)
+            .bind(viewer_id)
+            .bind(limit)
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(replies)
+        })
+    }
+
+    fn search<'a>(
+        &'a self,
+        user_id: i64,
+        search: &'a MessageSearch,
+    ) -> RepoFuture<'a, Vec<MessageSearchHit>> {
+        Box::pin(async move {
+            // Hits come from the current message text and, on request, from earlier versions kept
+            // in message_edits. A message matching in both places is listed once with its
+            // best-ranked match.
+            let hits = sqlx::query_as::<_, MessageSearchHit>(
+                r#"
+                WITH search AS (
+                    SELECT websearch_to_tsquery('english', $2) AS query
+                ),
+                matches AS (
+                    SELECT
+                        m.id AS message_id,
+                        ts_rank(m.text_search, s.query) AS rank,
+                        ts_headline('english', m.text_content, s.query,
+                            'StartSel=<mark>, StopSel=</mark>, MaxFragments=2') AS snippet,
+                        'content' AS matched_in
+                    FROM messages m, search s
+                    WHERE m.text_search @@ s.query
+
+                    UNION ALL
+
+                    SELECT
+                        e.message_id,
+                        ts_rank(to_tsvector('english', e.previous_context), s.query) AS rank,
+                        ts_headline('english', e.previous_context, s.query,
+                            'StartSel=<mark>, StopSel=</mark>, MaxFragments=2') AS snippet,
+                        'edit_history' AS matched_in
+                    FROM message_edits e, search s
+                    WHERE $3 AND to_tsvector('english', e.previous_context) @@ s.query
+                ),
+                best_matches AS (
+                    SELECT DISTINCT ON (message_id) *
+                    FROM matches
+                    ORDER BY message_id, rank DESC
+                )
+                SELECT
+                    m.id, m.room_id, m.sender_id, m.type, m.text_content,
+                    m.attachment_1, m.attachment_2, m.attachment_3, m.attachment_4,
+                    m.status, m.sent_at, m.created_at, m.updated_at,
+                    b.rank, b.snippet, b.matched_in
+                FROM best_matches b
+                INNER JOIN messages m ON m.id = b.message_id
+                INNER JOIN room_members rm ON rm.room_id = m.room_id AND rm.user_id = $1
+                WHERE m.is_deleted = FALSE
+                    AND NOT EXISTS (
+                        SELECT 1 FROM message_deletions md
+                        WHERE md.message_id = m.id AND md.user_id = $1
+                    )
+                    AND ($4::BIGINT IS NULL OR m.room_id = $4)
+                    AND ($5::BIGINT IS NULL OR m.sender_id = $5)
+                    AND ($6::TEXT IS NULL OR m.type = $6)
+                    AND ($7::BIGINT IS NULL OR m.sent_at::BIGINT >= $7)
+                    AND ($8::BIGINT IS NULL OR m.sent_at::BIGINT <= $8)
+                    AND (
+                        $9::BOOLEAN IS NULL
+                        OR (
+                            COALESCE(m.attachment_1, '') <> ''
+                            OR COALESCE(m.attachment_2, '') <> ''
+                            OR COALESCE(m.attachment_3, '') <> ''
+                            OR COALESCE(m.attachment_4, '') <> ''
+                        ) = $9
+                    )
+                ORDER BY b.rank DESC, m.id DESC
+                LIMIT $10 OFFSET $11
+                "#,
+            )
+            .bind(user_id)
+            .bind(&search.query)
+            .bind(search.include_edits)
+            .bind(search.room_id)
+            .bind(search.sender_id)
+            .bind(&search.message_type)
+            .bind(search.sent_after)
+            .bind(search.sent_before)
+            .bind(search.has_attachment)
+            .bind(search.limit)
+            .bind(search.offset)
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(hits)
+        })
+    }
+
+    fn hide_for(&self, id: i64, user_id: i64) -> RepoFuture<'_, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                "INSERT INTO message_deletions (user_id, message_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            )
+            .bind(user_id)
+            .bind(id)
+            .execute(&self.db)
+            .await?;
+
+            Ok(())
+        })
+    }
+
+    fn set_archived(&self, id: i64, user_id: i64, archived: bool) -> RepoFuture<'_, ()> {
+        Box::pin(async move {
+            let query = if archived {
+                "INSERT INTO message_archives (user_id, message_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"
+            } else {
+                "DELETE FROM message_archives WHERE user_id = $1 AND message_id = $2"
+            };
+
+            sqlx::query(query)
+                .bind(user_id)
+                .bind(id)
+                .execute(&self.db)
+                .await?;
+
+            Ok(())
+        })
+    }
+
+    fn set_bookmarked(&self, id: i64, user_id: i64, bookmarked: bool) -> RepoFuture<'_, ()> {
+        Box::pin(async move {
+            let query = if bookmarked {
+                "INSERT INTO message_bookmarks (user_id, message_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"
+            } else {
+                "DELETE FROM message_bookmarks WHERE user_id = $1 AND message_id = $2"
+            };
+
+            sqlx::query(query)
+                .bind(user_id)
+                .bind(id)
+                .execute(&self.db)
+                .await?;
+
+            Ok(())
+        })
+    }
+
+    fn find_reaction(&self, id: i64, sender_id: i64) -> RepoFuture<'_, Option<MessageReaction>> {
+        Box::pin(async move {
+            let reaction = sqlx::query_as::<_, MessageReaction>(
+                "SELECT * FROM message_reactions WHERE message_id = $1 AND sender_id = $2 LIMIT 1",
+            )
+            .bind(id)
+            .bind(sender_id)
+            .fetch_optional(&self.db)
+            .await?;
+
+            Ok(reaction)
+        })
+    }
+
+    fn create_reaction<'a>(&'a self, reaction: &'a NewReaction) -> RepoFuture<'a, MessageReaction> {
+        Box::pin(async move {
+            let reaction = sqlx::query_as::<_, MessageReaction>(
+                r#"
+                INSERT INTO message_reactions (message_id, room_id, sender_id, reaction_type, message_updates_counter)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING *
+                "#,
+            )
+            .bind(reaction.message_id)
+            .bind(reaction.room_id)
+            .bind(reaction.sender_id)
+            .bind(&reaction.reaction_type)
+            .bind(reaction.message_updates_counter)
+            .fetch_one(&self.db)
+            .await?;
+
+            Ok(reaction)
+        })
+    }
+
+    fn update_reaction<'a>(
+        &'a self,
+        reaction_id: i64,
+        reaction: &'a NewReaction,
+    ) -> RepoFuture<'a, MessageReaction> {
+        Box::pin(async move {
+            let reaction = sqlx::query_as::<_, MessageReaction>(
+                r#"
+                UPDATE message_reactions
+                SET reaction_type = $1, message_updates_counter = $2, updated_at = NOW()
+                WHERE id = $3
+                RETURNING *
+                "#,
+            )
+            .bind(&reaction.reaction_type)
+            .bind(reaction.message_updates_counter)
+            .bind(reaction_id)
+            .fetch_one(&self.db)
+            .await?;
+
+            Ok(reaction)
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct PgReceiptRepo {
+    db: PgPool,
+}
+
+impl PgReceiptRepo {
+    pub fn new(db: PgPool) -> Self {
+        PgReceiptRepo { db }
+    }
+}
+
+impl ReceiptRepo for PgReceiptRepo {
+    fn create<'a>(&'a self, receipt: &'a NewReceipt, receiver_id: i64) -> RepoFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                r#"
+                INSERT INTO message_status_receipts (message_id, sender_id, receiver_id, room_id, action, status, updates_count_tracker)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+            )
+            .bind(receipt.message_id)
+            .bind(receipt.sender_id)
+            .bind(receiver_id)
+            .bind(receipt.room_id)
+            .bind(receipt.action.as_str())
+            .bind(receipt.status.as_str())
+            .bind(receipt.updates_count_tracker)
+            .execute(&self.db)
+            .await?;
+
+            Ok(())
+        })
+    }
+
+    fn receivers(
+        &self,
+        message_id: i64,
+        status: MessageStatus,
+        updates_count_tracker: i32,
+    ) -> RepoFuture<'_, Vec<i64>> {
+        Box::pin(async move {
+            let receivers = sqlx::query_scalar::<_, i64>(
+                r#"
+                SELECT DISTINCT receiver_id
+                FROM message_status_receipts
+                WHERE message_id = $1
+                    AND status = $2
+                    AND updates_count_tracker = $3
+                    AND receiver_id IS NOT NULL
+                "#,
+            )
+            .bind(message_id)
+            .bind(status.as_str())
+            .bind(updates_count_tracker)
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(receivers)
+        })
+    }
+
+    fn list_for_message(&self, message_id: i64) -> RepoFuture<'_, Vec<MessageStatusReceipt>> {
+        Box::pin(async move {
+            let receipts = sqlx::query_as::<_, MessageStatusReceipt>(
+                "SELECT * FROM message_status_receipts WHERE message_id = $1 ORDER BY created_at DESC",
+            )
+            .bind(message_id)
+            .fetch_all(&self.db)
+            .await?;
+
+            Ok(receipts)
+        })
+    }
+
+    fn mark_rooms_seen<'a>(&'a self, user_id: i64, room_ids: &'a [i64]) -> RepoFuture<'a, ()> {
+        Box::pin(async move {
+            sqlx::query(
+                r#"
+                INSERT INTO message_status_receipts (message_id, sender_id, room_id, status, action)
+                SELECT m.id, $1, m.room_id, 'seen', 'original-send'
+                FROM messages m
+                WHERE m.room_id = ANY($2)
+                AND NOT EXISTS (
+                    SELECT 1 FROM message_status_receipts msr
+                    WHERE msr.message_id = m.id AND msr.sender_id = $1 AND msr.status = 'seen'
+                )
+                "#,
+            )
+            .bind(user_id)
+            .bind(room_ids)
+            .execute(&self.db)
+            .await?;
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,39 @@
+use crate::tests::harness::TestApp;
+use axum::http::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn deactivated_users_are_locked_out_until_reactivated() {
+    let app = TestApp::spawn().await;
+
+    let root = app.register("Root").await;
+    let ada = app.register("Ada").await;
+    let bob = app.register("Bob").await;
+    app.make_admin(&root).await;
+
+    let deactivate = format!("/api/v1/admin/deactivate-user/{}", bob.id);
+    let activate = format!("/api/v1/admin/activate-user/{}", bob.id);
+
+    app.patch_json(&deactivate, &ada, json!({}))
+        .await
+        .expect(StatusCode::FORBIDDEN);
+
+    app.patch_json(&deactivate, &root, json!({}))
+        .await
+        .expect(StatusCode::OK);
+
+    let locked_out = app
+        .get(&format!("/api/v1/user/get-user/{}", bob.id), &bob)
+        .await
+        .expect(StatusCode::FORBIDDEN);
+    assert_eq!(locked_out.message(), "Your account is deactivated");
+
+    app.patch_json(&activate, &root, json!({}))
+        .await
+        .expect(StatusCode::OK);
+
+    app.get(&format!("/api/v1/user/get-user/{}", bob.id), &bob)
+        .await
+        .expect(StatusCode::OK);
+}
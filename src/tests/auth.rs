@@ -0,0 +1,232 @@
+use crate::domains::auth::two_factor::build_totp;
+use crate::tests::harness::{TEST_PASSWORD, TestApp};
+use axum::http::StatusCode;
+use serde_json::json;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The code an authenticator app would show `ahead_secs` from now.
+fn totp_code(secret: &str, email: &str, ahead_secs: u64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+
+    build_totp(secret, email).unwrap().generate(now + ahead_secs)
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn registered_users_can_sign_in_and_reach_protected_routes() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+
+    let profile = app
+        .get(&format!("/api/v1/user/get-user/{}", ada.id), &ada)
+        .await
+        .expect(StatusCode::OK);
+
+    assert_eq!(profile.response()["email"], ada.email.as_str());
+    // the shared profile never carries credentials
+    assert!(profile.response().get("password").is_none());
+    assert!(profile.response().get("access_token").is_none());
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn wrong_passwords_and_missing_credentials_are_turned_away() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+
+    let login = app
+        .login(&ada.email, &format!("{}-typo", TEST_PASSWORD))
+        .await
+        .expect(StatusCode::UNAUTHORIZED);
+    assert_eq!(login.code(), "unauthorized");
+
+    let anonymous = app
+        .post_json(
+            "/api/v1/rooms/create-private-chat-room",
+            None,
+            json!({ "co_member": ada.id }),
+        )
+        .await
+        .expect(StatusCode::UNAUTHORIZED);
+    assert_eq!(anonymous.code(), "unauthorized");
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn refresh_tokens_cannot_stand_in_for_access_tokens() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+    let profile_path = format!("/api/v1/user/get-user/{}", ada.id);
+    let with_refresh_token = ada.bearing(&ada.refresh_token);
+
+    app.get(&profile_path, &with_refresh_token)
+        .await
+        .expect(StatusCode::UNAUTHORIZED);
+
+    // nor once it has been rotated away
+    app.post_json(
+        "/api/v1/auth/refresh",
+        None,
+        json!({ "refresh_token": ada.refresh_token }),
+    )
+    .await
+    .expect(StatusCode::OK);
+
+    app.get(&profile_path, &with_refresh_token)
+        .await
+        .expect(StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn two_factor_challenges_are_single_use_and_two_factor_can_be_turned_off() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+
+    let enrollment = app
+        .post_json("/api/v1/auth/enroll-two-factor", Some(&ada), json!({}))
+        .await
+        .expect(StatusCode::OK);
+    let secret = enrollment.response()["secret"].as_str().unwrap().to_string();
+
+    let confirmed = app
+        .post_json(
+            "/api/v1/auth/confirm-two-factor",
+            Some(&ada),
+            json!({ "code": totp_code(&secret, &ada.email, 0) }),
+        )
+        .await
+        .expect(StatusCode::OK);
+    let recovery_codes: Vec<String> = confirmed.response()["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|code| code.as_str().unwrap().to_string())
+        .collect();
+
+    let login = app
+        .login(&ada.email, TEST_PASSWORD)
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(login.response()["two_factor_required"], true);
+    let challenge_token = login.response()["challenge_token"].as_str().unwrap();
+
+    app.post_json(
+        "/api/v1/auth/verify-two-factor",
+        None,
+        json!({ "challenge_token": challenge_token, "code": "abcdef" }),
+    )
+    .await
+    .expect(StatusCode::UNAUTHORIZED);
+
+    // the confirmation used up the current step, the app's next code is still in the window
+    app.post_json(
+        "/api/v1/auth/verify-two-factor",
+        None,
+        json!({
+            "challenge_token": challenge_token,
+            "code": totp_code(&secret, &ada.email, 30),
+        }),
+    )
+    .await
+    .expect(StatusCode::OK);
+
+    // answered challenges can't be traded for another session
+    app.post_json(
+        "/api/v1/auth/verify-two-factor",
+        None,
+        json!({ "challenge_token": challenge_token, "recovery_code": recovery_codes[0] }),
+    )
+    .await
+    .expect(StatusCode::UNAUTHORIZED);
+
+    let regenerated = app
+        .post_json(
+            "/api/v1/auth/regenerate-recovery-codes",
+            Some(&ada),
+            json!({ "recovery_code": recovery_codes[0] }),
+        )
+        .await
+        .expect(StatusCode::OK);
+    let new_recovery_code = regenerated.response()["recovery_codes"][0]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    app.post_json(
+        "/api/v1/auth/disable-two-factor",
+        Some(&ada),
+        json!({ "recovery_code": recovery_codes[1] }),
+    )
+    .await
+    .expect(StatusCode::UNAUTHORIZED);
+
+    app.post_json(
+        "/api/v1/auth/disable-two-factor",
+        Some(&ada),
+        json!({ "recovery_code": new_recovery_code }),
+    )
+    .await
+    .expect(StatusCode::OK);
+
+    let login = app
+        .login(&ada.email, TEST_PASSWORD)
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(login.response()["two_factor_required"], false);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn a_right_password_does_not_reset_wrong_two_factor_codes() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+
+    let enrollment = app
+        .post_json("/api/v1/auth/enroll-two-factor", Some(&ada), json!({}))
+        .await
+        .expect(StatusCode::OK);
+    let secret = enrollment.response()["secret"].as_str().unwrap().to_string();
+
+    app.post_json(
+        "/api/v1/auth/confirm-two-factor",
+        Some(&ada),
+        json!({ "code": totp_code(&secret, &ada.email, 0) }),
+    )
+    .await
+    .expect(StatusCode::OK);
+
+    let guess_wrong = async |expected: StatusCode| {
+        let login = app
+            .login(&ada.email, TEST_PASSWORD)
+            .await
+            .expect(StatusCode::OK);
+        let challenge_token = login.response()["challenge_token"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        app.post_json(
+            "/api/v1/auth/verify-two-factor",
+            None,
+            json!({ "challenge_token": challenge_token, "code": "000000" }),
+        )
+        .await
+        .expect(expected);
+    };
+
+    // a fresh challenge for every guess, each preceded by the right password
+    for _ in 0..4 {
+        guess_wrong(StatusCode::UNAUTHORIZED).await;
+    }
+    guess_wrong(StatusCode::TOO_MANY_REQUESTS).await;
+
+    app.login(&ada.email, TEST_PASSWORD)
+        .await
+        .expect(StatusCode::TOO_MANY_REQUESTS);
+}
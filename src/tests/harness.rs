@@ -0,0 +1,624 @@
+use crate::repositories::Repositories;
+use crate::utils::load_config::AppConfig;
+use crate::utils::mail_sender::build_mail_sender;
+use crate::utils::metrics::Metrics;
+use crate::utils::object_store::{InMemoryObjectStore, UPLOADS_ROUTE};
+use crate::utils::presence_tracker::PresenceTracker;
+use crate::utils::rate_limiter::RateLimiter;
+use crate::utils::realtime_hub::RealtimeHub;
+use crate::utils::shutdown::Shutdown;
+use crate::{AppState, build_app};
+use axum::body::Bytes;
+use axum::http::{Method, Request, StatusCode, header};
+use config::{Config, File, FileFormat};
+use http_body_util::{BodyExt, Full};
+use hyper_util::client::legacy::{Client, connect::HttpConnector};
+use hyper_util::rt::TokioExecutor;
+use serde_json::{Value, json};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Connection, PgConnection, PgPool};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::Once;
+use std::sync::atomic::{AtomicU32, Ordering};
+use tokio::task::JoinHandle;
+
+pub const TEST_PASSWORD: &str = "Sup3r-secret-pass!";
+
+const AUTH_COOKIE: &str = "rusty_chat_auth_cookie";
+
+// Every middleware on, limits high enough that a scenario never trips them by accident.
+const TEST_CONFIG: &str = r#"
+[app]
+name = "rusty-chat-test"
+environment = "test"
+
+[client_integrations]
+allow_access_middleware = true
+allow_sessions_middleware = true
+allow_logging_middleware = true
+allow_request_timeout_middleware = true
+allow_admin_routes_protector_middleware = true
+allow_rate_limit_middleware = true
+
+[messages]
+delete_for_everyone_window_secs = 3600
+
+[mail]
+sender = "log"
+
+[auth]
+email_verification = "off"
+
+[security]
+rate_limit_per_minute = 10000
+
+[security.rate_limit_overrides]
+login = 10000
+create_message = 10000
+
+[storage]
+backend = "memory"
+
+[observability]
+enable_tracing = false
+enable_metrics = true
+"#;
+
+static INIT_ENV: Once = Once::new();
+
+fn init_env() {
+    INIT_ENV.call_once(|| {
+        if std::env::var("JWT_SECRET").is_err() {
+            // SAFETY: runs once, before any test gets to spawn a server that reads it
+            unsafe { std::env::set_var("JWT_SECRET", "integration-test-secret") };
+        }
+    });
+}
+
+fn test_config() -> AppConfig {
+    Config::builder()
+        .add_source(File::from_str(TEST_CONFIG, FileFormat::Toml))
+        .build()
+        .and_then(|config| config.try_deserialize())
+        .expect("Invalid test config")
+}
+
+/// Applies every file in `migrations/` in order, the same way `sqlx migrate run` would.
+async fn run_migrations(db: &PgPool) {
+    let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/migrations");
+
+    let mut files: Vec<_> = std::fs::read_dir(directory)
+        .expect("Failed to read migrations directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
+        .collect();
+    files.sort();
+
+    for file in files {
+        let sql = std::fs::read_to_string(&file).unwrap();
+
+        sqlx::raw_sql(&sql)
+            .execute(db)
+            .await
+            .unwrap_or_else(|e| panic!("Migration {} failed: {}", file.display(), e));
+    }
+}
+
+/// A signed in user: the cookie and headers the auth middlewares want on every request.
+#[derive(Clone, Debug)]
+pub struct TestUser {
+    pub id: i64,
+    pub email: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    cookie: String,
+}
+
+impl TestUser {
+    /// The same user, presenting `token` as their bearer token instead of the access token.
+    pub fn bearing(&self, token: &str) -> Self {
+        Self {
+            access_token: token.to_string(),
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub body: Value,
+    cookie: Option<String>,
+}
+
+impl TestResponse {
+    /// Panics with the body when the status isn't the expected one, so failures say why.
+    #[track_caller]
+    pub fn expect(self, status: StatusCode) -> Self {
+        assert_eq!(self.status, status, "unexpected response: {}", self.body);
+        self
+    }
+
+    pub fn response(&self) -> &Value {
+        &self.body["response"]
+    }
+
+    pub fn message(&self) -> &str {
+        self.body["response_message"].as_str().unwrap_or_default()
+    }
+
+    pub fn code(&self) -> &str {
+        self.body["code"].as_str().unwrap_or_default()
+    }
+
+    #[track_caller]
+    pub fn id(&self) -> i64 {
+        self.response()["id"]
+            .as_i64()
+            .unwrap_or_else(|| panic!("response has no id: {}", self.body))
+    }
+}
+
+/// A file part for `post_multipart`.
+pub struct Upload<'a> {
+    pub field: &'a str,
+    pub file_name: &'a str,
+    pub content_type: &'a str,
+    pub bytes: &'a [u8],
+}
+
+/// The full router served on a random local port, against its own Postgres schema and the
+/// in-memory object store. The schema is dropped with the app.
+///
+/// Set `TEST_DATABASE_URL` to a Postgres the tests can create schemas in. The integration tests
+/// are `#[ignore]`d, so a plain `cargo test` leaves them out - run them with `--ignored`.
+pub struct TestApp {
+    pub address: SocketAddr,
+    pub db: PgPool,
+    client: Client<HttpConnector, Full<Bytes>>,
+    database_url: String,
+    schema: String,
+    server: JoinHandle<()>,
+    users_created: AtomicU32,
+}
+
+impl TestApp {
+    pub async fn spawn() -> Self {
+        Self::spawn_with(|_| {}).await
+    }
+
+    /// Like `spawn`, with `configure` getting a go at the test config before the server starts.
+    pub async fn spawn_with(configure: impl FnOnce(&mut AppConfig)) -> Self {
+        let database_url = std::env::var("TEST_DATABASE_URL")
+            .expect("TEST_DATABASE_URL must be set to run the integration tests");
+
+        init_env();
+
+        let schema = format!("test_{}", uuid::Uuid::new_v4().simple());
+
+        let mut admin = PgConnection::connect(&database_url)
+            .await
+            .expect("Failed to connect to TEST_DATABASE_URL");
+        sqlx::query(&format!(r#"CREATE SCHEMA "{}""#, schema))
+            .execute(&mut admin)
+            .await
+            .expect("Failed to create test schema");
+        admin.close().await.ok();
+
+        let options = PgConnectOptions::from_str(&database_url)
+            .expect("Invalid TEST_DATABASE_URL")
+            .options([("search_path", schema.as_str())]);
+        let db = PgPoolOptions::new()
+            .max_connections(5)
+            .connect_with(options)
+            .await
+            .expect("Failed to connect to test schema");
+
+        run_migrations(&db).await;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let mut config = test_config();
+        configure(&mut config);
+
+        let state = AppState {
+            mailer: build_mail_sender(config.mail.as_ref()),
+            config: Arc::new(config),
+            repos: Repositories::postgres(&db),
+            db: db.clone(),
+            storage: Arc::new(InMemoryObjectStore::new(format!(
+                "http://{}{}",
+                address, UPLOADS_ROUTE
+            ))),
+            hub: RealtimeHub::new(),
+            presence: PresenceTracker::new(),
+            rate_limiter: RateLimiter::new(),
+            shutdown: Shutdown::new(),
+            metrics: Metrics::new(),
+        };
+
+        let app = build_app(state);
+
+        let server = tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap();
+        });
+
+        Self {
+            address,
+            db,
+            client: Client::builder(TokioExecutor::new()).build_http(),
+            database_url,
+            schema,
+            server,
+            users_created: AtomicU32::new(0),
+        }
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        user: Option<&TestUser>,
+        content_type: Option<String>,
+        body: Vec<u8>,
+    ) -> TestResponse {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(format!("http://{}{}", self.address, path));
+
+        if let Some(content_type) = content_type {
+            request = request.header(header::CONTENT_TYPE, content_type);
+        }
+
+        if let Some(user) = user {
+            request = request
+                .header("email", &user.email)
+                .header(
+                    header::AUTHORIZATION,
+                    format!("Bearer {}", user.access_token),
+                )
+                .header(header::COOKIE, format!("{}={}", AUTH_COOKIE, user.cookie));
+        }
+
+        let response = self
+            .client
+            .request(request.body(Full::new(Bytes::from(body))).unwrap())
+            .await
+            .unwrap_or_else(|e| panic!("Request to {} failed: {}", path, e));
+
+        let status = response.status();
+        let cookie = response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(|value| value.strip_prefix(&format!("{}=", AUTH_COOKIE)))
+            .map(|value| value.split(';').next().unwrap_or_default().to_string());
+
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body = serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()));
+
+        TestResponse {
+            status,
+            body,
+            cookie,
+        }
+    }
+
+    pub async fn get(&self, path: &str, user: &TestUser) -> TestResponse {
+        self.send(Method::GET, path, Some(user), None, Vec::new())
+            .await
+    }
+
+    pub async fn delete(&self, path: &str, user: &TestUser) -> TestResponse {
+        self.send(Method::DELETE, path, Some(user), None, Vec::new())
+            .await
+    }
+
+    pub async fn post_json(
+        &self,
+        path: &str,
+        user: Option<&TestUser>,
+        body: Value,
+    ) -> TestResponse {
+        self.json(Method::POST, path, user, body).await
+    }
+
+    pub async fn patch_json(&self, path: &str, user: &TestUser, body: Value) -> TestResponse {
+        self.json(Method::PATCH, path, Some(user), body).await
+    }
+
+    async fn json(
+        &self,
+        method: Method,
+        path: &str,
+        user: Option<&TestUser>,
+        body: Value,
+    ) -> TestResponse {
+        self.send(
+            method,
+            path,
+            user,
+            Some("application/json".to_string()),
+            body.to_string().into_bytes(),
+        )
+        .await
+    }
+
+    pub async fn post_multipart(
+        &self,
+        path: &str,
+        user: &TestUser,
+        fields: &[(&str, String)],
+        uploads: &[Upload<'_>],
+    ) -> TestResponse {
+        let boundary = format!("test-boundary-{}", uuid::Uuid::new_v4().simple());
+        let mut body = Vec::new();
+
+        for (name, value) in fields {
+            body.extend_from_slice(
+                format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                    boundary, name, value
+                )
+                .as_bytes(),
+            );
+        }
+
+        for upload in uploads {
+            body.extend_from_slice(
+                format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                    boundary, upload.field, upload.file_name, upload.content_type
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(upload.bytes);
+            body.extend_from_slice(b"\r\n");
+        }
+
+        body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+
+        self.send(
+            Method::POST,
+            path,
+            Some(user),
+            Some(format!("multipart/form-data; boundary={}", boundary)),
+            body,
+        )
+        .await
+    }
+
+    // ------------------------------------------------------------------------
+    // Auth
+    // ------------------------------------------------------------------------
+
+    /// Registers a fresh user with `TEST_PASSWORD` and signs them in.
+    pub async fn register(&self, first_name: &str) -> TestUser {
+        let n = self.users_created.fetch_add(1, Ordering::Relaxed);
+        let email = format!(
+            "{}.{}.{}@example.com",
+            first_name.to_lowercase(),
+            n,
+            &self.schema[5..13]
+        );
+
+        self.post_json(
+            "/api/v1/auth/register",
+            None,
+            json!({
+                "first_name": first_name,
+                "last_name": "Tester",
+                "email": email,
+                "password": TEST_PASSWORD,
+                "country": "NG",
+                "phone_number": format!("+1555{:07}", n),
+            }),
+        )
+        .await
+        .expect(StatusCode::CREATED);
+
+        self.sign_in(&email).await
+    }
+
+    pub async fn login(&self, email: &str, password: &str) -> TestResponse {
+        self.post_json(
+            "/api/v1/auth/login",
+            None,
+            json!({ "email": email, "password": password }),
+        )
+        .await
+    }
+
+    pub async fn sign_in(&self, email: &str) -> TestUser {
+        let response = self
+            .login(email, TEST_PASSWORD)
+            .await
+            .expect(StatusCode::OK);
+        let core = response.response();
+
+        TestUser {
+            id: core["user_profile"]["id"].as_i64().unwrap(),
+            email: email.to_string(),
+            access_token: core["access_token"].as_str().unwrap().to_string(),
+            refresh_token: core["refresh_token"].as_str().unwrap().to_string(),
+            cookie: response.cookie.clone().expect("login set no auth cookie"),
+        }
+    }
+
+    /// There is no endpoint for bootstrapping the first admin, so this goes to the database.
+    pub async fn make_admin(&self, user: &TestUser) {
+        sqlx::query("UPDATE users SET is_admin = TRUE WHERE id = $1")
+            .bind(user.id)
+            .execute(&self.db)
+            .await
+            .unwrap();
+    }
+
+    // ------------------------------------------------------------------------
+    // Rooms
+    // ------------------------------------------------------------------------
+
+    pub async fn create_private_room(&self, owner: &TestUser, co_member: &TestUser) -> i64 {
+        self.post_json(
+            "/api/v1/rooms/create-private-chat-room",
+            Some(owner),
+            json!({ "co_member": co_member.id }),
+        )
+        .await
+        .expect(StatusCode::OK)
+        .id()
+    }
+
+    pub async fn create_group_room(
+        &self,
+        owner: &TestUser,
+        room_name: &str,
+        co_members: &[&TestUser],
+    ) -> i64 {
+        let co_members: Vec<i64> = co_members.iter().map(|member| member.id).collect();
+
+        self.post_json(
+            "/api/v1/rooms/create-group-chat-room",
+            Some(owner),
+            json!({ "room_name": room_name, "co_members": co_members }),
+        )
+        .await
+        .expect(StatusCode::OK)
+        .id()
+    }
+
+    /// Marks the user's email as verified without going through the emailed link.
+    pub async fn verify_email(&self, user: &TestUser) {
+        sqlx::query("UPDATE users SET email_verified = TRUE WHERE id = $1")
+            .bind(user.id)
+            .execute(&self.db)
+            .await
+            .unwrap();
+    }
+
+    // ------------------------------------------------------------------------
+    // Messages
+    // ------------------------------------------------------------------------
+
+    pub async fn send_message(&self, sender: &TestUser, room_id: i64, text: &str) -> TestResponse {
+        self.post_multipart(
+            "/api/v1/messages/create-message",
+            sender,
+            &[
+                ("room_id", room_id.to_string()),
+                ("text_content", text.to_string()),
+            ],
+            &[],
+        )
+        .await
+    }
+
+    pub async fn react(&self, user: &TestUser, message_id: i64, reaction: &str) -> TestResponse {
+        self.post_json(
+            &format!("/api/v1/messages/react-to-message/{}", message_id),
+            Some(user),
+            json!({ "reaction_type": reaction }),
+        )
+        .await
+    }
+
+    pub async fn sync_delivered(&self, user: &TestUser, room_id: i64) -> TestResponse {
+        self.post_json(
+            &format!(
+                "/api/v1/messages/sync-room-messages-status-to-delivered/{}",
+                room_id
+            ),
+            Some(user),
+            json!({}),
+        )
+        .await
+    }
+
+    pub async fn sync_seen(&self, user: &TestUser) -> TestResponse {
+        self.post_json(
+            "/api/v1/messages/sync-messages-status-to-seen",
+            Some(user),
+            json!({}),
+        )
+        .await
+    }
+
+    /// The message's status column, read straight from the database.
+    pub async fn message_status(&self, message_id: i64) -> String {
+        sqlx::query_scalar("SELECT status FROM messages WHERE id = $1")
+            .bind(message_id)
+            .fetch_one(&self.db)
+            .await
+            .unwrap()
+    }
+
+    /// `(receiver_id, status, action)` of every receipt for a message, oldest first.
+    pub async fn receipts(&self, user: &TestUser, message_id: i64) -> Vec<(i64, String, String)> {
+        let response = self
+            .get(
+                &format!(
+                    "/api/v1/messages/get-message-status-receipts/{}",
+                    message_id
+                ),
+                user,
+            )
+            .await
+            .expect(StatusCode::OK);
+
+        let mut receipts: Vec<(i64, String, String)> = response
+            .response()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|receipt| {
+                (
+                    receipt["receiver_id"].as_i64().unwrap_or_default(),
+                    receipt["status"].as_str().unwrap().to_string(),
+                    receipt["action"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+
+        // the endpoint lists newest first
+        receipts.reverse();
+        receipts
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        self.server.abort();
+
+        let database_url = self.database_url.clone();
+        let schema = self.schema.clone();
+
+        // Drop can't await, and the test's own runtime may be the one blocked here
+        let cleanup = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+
+            runtime.block_on(async {
+                if let Ok(mut connection) = PgConnection::connect(&database_url).await {
+                    let drop_schema = format!(r#"DROP SCHEMA IF EXISTS "{}" CASCADE"#, schema);
+                    sqlx::query(&drop_schema)
+                        .execute(&mut connection)
+                        .await
+                        .ok();
+                }
+            });
+        });
+
+        cleanup.join().ok();
+    }
+}
@@ -0,0 +1,333 @@
+use crate::tests::harness::{TestApp, Upload};
+use crate::utils::load_config::EmailVerificationMode;
+use axum::http::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn private_messages_move_from_sent_to_delivered_to_seen() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+    let bob = app.register("Bob").await;
+    let room_id = app.create_private_room(&ada, &bob).await;
+
+    let message_id = app
+        .send_message(&ada, room_id, "hello bob")
+        .await
+        .expect(StatusCode::CREATED)
+        .id();
+
+    assert_eq!(app.message_status(message_id).await, "sent");
+    assert_eq!(
+        app.receipts(&ada, message_id).await,
+        vec![(bob.id, "sent".to_string(), "original-send".to_string())]
+    );
+
+    app.sync_delivered(&bob, room_id)
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(app.message_status(message_id).await, "delivered");
+
+    app.sync_seen(&bob).await.expect(StatusCode::OK);
+    assert_eq!(app.message_status(message_id).await, "seen");
+
+    // the sender syncing their own messages changes nothing
+    app.sync_delivered(&ada, room_id)
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(app.message_status(message_id).await, "seen");
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn group_messages_are_delivered_once_every_member_has_synced() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+    let bob = app.register("Bob").await;
+    let cy = app.register("Cy").await;
+    let room_id = app.create_group_room(&ada, "Standup", &[&bob, &cy]).await;
+
+    let message_id = app
+        .send_message(&bob, room_id, "running late")
+        .await
+        .expect(StatusCode::CREATED)
+        .id();
+
+    // the creator is a member like everyone else and gets a receipt too
+    let receivers: Vec<i64> = app
+        .receipts(&bob, message_id)
+        .await
+        .into_iter()
+        .map(|(receiver, _, _)| receiver)
+        .collect();
+    assert_eq!(receivers.len(), 2);
+    assert!(receivers.contains(&ada.id) && receivers.contains(&cy.id));
+
+    app.sync_delivered(&ada, room_id)
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(app.message_status(message_id).await, "sent");
+
+    app.sync_delivered(&cy, room_id)
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(app.message_status(message_id).await, "delivered");
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn reacting_again_replaces_the_previous_reaction() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+    let bob = app.register("Bob").await;
+    let eve = app.register("Eve").await;
+    let room_id = app.create_private_room(&ada, &bob).await;
+
+    let message_id = app
+        .send_message(&ada, room_id, "pizza tonight?")
+        .await
+        .expect(StatusCode::CREATED)
+        .id();
+
+    app.react(&eve, message_id, "👎")
+        .await
+        .expect(StatusCode::FORBIDDEN);
+
+    app.react(&bob, message_id, "👍")
+        .await
+        .expect(StatusCode::OK);
+    app.react(&bob, message_id, "🍕")
+        .await
+        .expect(StatusCode::OK);
+
+    let reactions: Vec<String> =
+        sqlx::query_scalar("SELECT reaction_type FROM message_reactions WHERE message_id = $1")
+            .bind(message_id)
+            .fetch_all(&app.db)
+            .await
+            .unwrap();
+    assert_eq!(reactions, vec!["🍕".to_string()]);
+
+    assert_eq!(app.message_status(message_id).await, "reacted");
+
+    let reaction_receipts = app
+        .receipts(&ada, message_id)
+        .await
+        .into_iter()
+        .filter(|(receiver, _, action)| *receiver == ada.id && action == "reaction")
+        .count();
+    assert_eq!(reaction_receipts, 2);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn only_the_sender_can_delete_a_message_for_everyone() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+    let bob = app.register("Bob").await;
+    let room_id = app.create_private_room(&ada, &bob).await;
+
+    let message_id = app
+        .send_message(&ada, room_id, "wrong chat, sorry")
+        .await
+        .expect(StatusCode::CREATED)
+        .id();
+
+    let delete_for_everyone = format!("/api/v1/messages/delete-message/{}", message_id);
+
+    app.delete(&delete_for_everyone, &bob)
+        .await
+        .expect(StatusCode::FORBIDDEN);
+
+    // the sender used to come from the path, so naming Ada there must not get Bob anywhere
+    app.delete(&format!("{}/{}", delete_for_everyone, ada.id), &bob)
+        .await
+        .expect(StatusCode::NOT_FOUND);
+    assert_eq!(app.message_status(message_id).await, "sent");
+
+    let deleted = app
+        .delete(&delete_for_everyone, &ada)
+        .await
+        .expect(StatusCode::OK);
+    assert!(deleted.response()["text_content"].is_null());
+    assert_eq!(app.message_status(message_id).await, "deleted");
+
+    app.delete(&delete_for_everyone, &ada)
+        .await
+        .expect(StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn attachments_are_uploaded_to_the_object_store() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+    let bob = app.register("Bob").await;
+    let room_id = app.create_private_room(&ada, &bob).await;
+
+    let message = app
+        .post_multipart(
+            "/api/v1/messages/create-message",
+            &ada,
+            &[
+                ("room_id", room_id.to_string()),
+                ("text_content", "notes attached".to_string()),
+            ],
+            &[Upload {
+                field: "attachment_1",
+                file_name: "notes.txt",
+                content_type: "text/plain",
+                bytes: b"remember the milk",
+            }],
+        )
+        .await
+        .expect(StatusCode::CREATED);
+
+    let url = message.response()["attachment_1"]
+        .as_str()
+        .expect("attachment_1 should hold the upload's url")
+        .to_string();
+    let key = url
+        .strip_prefix(&format!("http://{}/uploads/", app.address))
+        .expect("attachments should be served by this server");
+
+    // the key carries a random part, so it can't be worked out from the message id
+    assert!(key.starts_with(&format!("message_attachment_1_{}_", message.id())));
+    assert!(key.ends_with(".txt"));
+
+    let download = app
+        .get(&format!("/uploads/{}", key), &bob)
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(download.body, "remember the milk");
+
+    app.get("/uploads/message_attachment_1_0.txt", &bob)
+        .await
+        .expect(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn only_room_members_can_read_messages_and_edit_history() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+    let bob = app.register("Bob").await;
+    let eve = app.register("Eve").await;
+    let room_id = app.create_private_room(&ada, &bob).await;
+
+    let message_id = app
+        .send_message(&ada, room_id, "first draft")
+        .await
+        .expect(StatusCode::CREATED)
+        .id();
+
+    app.patch_json(
+        &format!("/api/v1/messages/update-message/{}", message_id),
+        &ada,
+        json!({ "text_content": "second draft" }),
+    )
+    .await
+    .expect(StatusCode::OK);
+
+    let room_messages = format!("/api/v1/messages/get-room-messages/{}", room_id);
+    let edit_history = format!("/api/v1/messages/get-message-edit-history/{}", message_id);
+
+    app.get(&room_messages, &eve)
+        .await
+        .expect(StatusCode::FORBIDDEN);
+    app.get(&edit_history, &eve)
+        .await
+        .expect(StatusCode::FORBIDDEN);
+
+    let history = app.get(&edit_history, &bob).await.expect(StatusCode::OK);
+    assert_eq!(history.response().as_array().unwrap().len(), 1);
+
+    app.delete(
+        &format!("/api/v1/messages/delete-message/{}", message_id),
+        &ada,
+    )
+    .await
+    .expect(StatusCode::OK);
+
+    // the earlier versions go with the message
+    let history = app.get(&edit_history, &bob).await.expect(StatusCode::OK);
+    assert!(history.response().as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn the_acting_user_comes_from_the_session_not_the_request() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+    let bob = app.register("Bob").await;
+    let room_id = app.create_private_room(&ada, &bob).await;
+
+    let message_id = app
+        .send_message(&ada, room_id, "first draft")
+        .await
+        .expect(StatusCode::CREATED)
+        .id();
+
+    // naming Ada in the body doesn't make Bob the sender
+    app.patch_json(
+        &format!("/api/v1/messages/update-message/{}", message_id),
+        &bob,
+        json!({ "text_content": "not yours", "sender_id": ada.id }),
+    )
+    .await
+    .expect(StatusCode::FORBIDDEN);
+
+    app.get(&format!("/api/v1/rooms/get-user-rooms/{}", ada.id), &bob)
+        .await
+        .expect(StatusCode::FORBIDDEN);
+    app.get(&format!("/api/v1/rooms/get-user-rooms/{}", ada.id), &ada)
+        .await
+        .expect(StatusCode::OK);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn unverified_users_cannot_message_when_verification_gates_messaging() {
+    let app = TestApp::spawn_with(|config| {
+        config.auth.as_mut().unwrap().email_verification = EmailVerificationMode::Messaging;
+    })
+    .await;
+
+    let ada = app.register("Ada").await;
+    let bob = app.register("Bob").await;
+    app.verify_email(&bob).await;
+    let room_id = app.create_private_room(&ada, &bob).await;
+
+    let message_id = app
+        .send_message(&bob, room_id, "hello")
+        .await
+        .expect(StatusCode::CREATED)
+        .id();
+
+    app.send_message(&ada, room_id, "hi")
+        .await
+        .expect(StatusCode::FORBIDDEN);
+    app.react(&ada, message_id, "👍")
+        .await
+        .expect(StatusCode::FORBIDDEN);
+    app.post_json(
+        "/api/v1/calls/start-call",
+        Some(&ada),
+        json!({ "room_id": room_id, "call_type": "voice_call" }),
+    )
+    .await
+    .expect(StatusCode::FORBIDDEN);
+
+    app.verify_email(&ada).await;
+
+    app.react(&ada, message_id, "👍")
+        .await
+        .expect(StatusCode::OK);
+}
@@ -0,0 +1,8 @@
+//! End-to-end tests against the full router. See `harness::TestApp` for what they need to run.
+
+mod harness;
+
+mod admin;
+mod auth;
+mod messages;
+mod rooms;
@@ -0,0 +1,79 @@
+use crate::tests::harness::TestApp;
+use axum::http::StatusCode;
+use serde_json::json;
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn each_pair_of_users_gets_one_private_room() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+    let bob = app.register("Bob").await;
+
+    let room_id = app.create_private_room(&ada, &bob).await;
+
+    let room = app
+        .get(&format!("/api/v1/rooms/get-room/{}", room_id), &bob)
+        .await
+        .expect(StatusCode::OK);
+    assert_eq!(room.response()["room_name"], "Bob Tester");
+    assert_eq!(room.response()["co_member"], bob.id);
+
+    let duplicate = app
+        .post_json(
+            "/api/v1/rooms/create-private-chat-room",
+            Some(&ada),
+            json!({ "co_member": bob.id }),
+        )
+        .await
+        .expect(StatusCode::CONFLICT);
+    assert_eq!(duplicate.code(), "conflict");
+
+    app.post_json(
+        "/api/v1/rooms/create-private-chat-room",
+        Some(&ada),
+        json!({ "co_member": ada.id }),
+    )
+    .await
+    .expect(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn only_current_group_members_can_post() {
+    let app = TestApp::spawn().await;
+
+    let ada = app.register("Ada").await;
+    let bob = app.register("Bob").await;
+    let cy = app.register("Cy").await;
+
+    let room_id = app.create_group_room(&ada, "Book club", &[&bob]).await;
+
+    app.send_message(&cy, room_id, "let me in")
+        .await
+        .expect(StatusCode::FORBIDDEN);
+
+    let add_member = format!("/api/v1/rooms/add-room-member/{}", room_id);
+    app.post_json(&add_member, Some(&ada), json!({ "user_id": cy.id }))
+        .await
+        .expect(StatusCode::CREATED);
+    app.post_json(&add_member, Some(&ada), json!({ "user_id": cy.id }))
+        .await
+        .expect(StatusCode::CONFLICT);
+
+    app.send_message(&cy, room_id, "thanks for the invite")
+        .await
+        .expect(StatusCode::CREATED);
+
+    app.post_json(
+        &format!("/api/v1/rooms/remove-room-member/{}", room_id),
+        Some(&ada),
+        json!({ "user_id": bob.id }),
+    )
+    .await
+    .expect(StatusCode::OK);
+
+    app.send_message(&bob, room_id, "still here?")
+        .await
+        .expect(StatusCode::FORBIDDEN);
+}
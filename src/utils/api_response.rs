@@ -0,0 +1,86 @@
+use crate::utils::app_error::AppError;
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+/// The envelope every handler answers with. Failures go out in the same shape through `AppError`,
+/// with a machine-readable `code` added.
+#[derive(Debug, Serialize)]
+pub struct ApiResponse<T> {
+    #[serde(skip)]
+    pub status: StatusCode,
+    pub response_message: String,
+    // only set on list responses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+    pub response: Option<T>,
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+}
+
+pub type ApiResult<T> = Result<ApiResponse<T>, AppError>;
+
+impl<T: Serialize> ApiResponse<T> {
+    pub fn ok(message: impl Into<String>, response: T) -> Self {
+        Self {
+            status: StatusCode::OK,
+            response_message: message.into(),
+            count: None,
+            response: Some(response),
+            error: None,
+            code: None,
+        }
+    }
+
+    pub fn created(message: impl Into<String>, response: T) -> Self {
+        Self::ok(message, response).with_status(StatusCode::CREATED)
+    }
+
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// For requests that went through but not all the way, e.g. a message that was saved while
+    /// its attachments weren't.
+    pub fn with_error(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+}
+
+impl<T: Serialize> ApiResponse<Vec<T>> {
+    /// A list along with how many items it holds.
+    pub fn list(message: impl Into<String>, items: Vec<T>) -> Self {
+        let count = items.len();
+
+        Self {
+            count: Some(count),
+            ..Self::ok(message, items)
+        }
+    }
+}
+
+impl ApiResponse<()> {
+    /// A success with nothing to send back but the message.
+    pub fn message(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::OK,
+            response_message: message.into(),
+            count: None,
+            response: None,
+            error: None,
+            code: None,
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for ApiResponse<T> {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
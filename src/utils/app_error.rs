@@ -0,0 +1,238 @@
+use crate::utils::api_response::ApiResponse;
+use aws_sdk_s3::error::{DisplayErrorContext, SdkError};
+use axum::{
+    extract::multipart::MultipartError,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use jsonwebtoken::errors::ErrorKind;
+use std::fmt;
+use tracing::error;
+
+/// Every way a request can fail. Client errors carry the message shown to the caller. Server
+/// errors carry a message for the caller plus the underlying error, which only goes to the log.
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest(String),
+    Unauthorized(String),
+    TokenExpired,
+    InvalidToken,
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    PayloadTooLarge(String),
+    RequestTimeout(String),
+    TooManyRequests {
+        message: String,
+        retry_after_secs: u64,
+    },
+    Database {
+        message: String,
+        source: sqlx::Error,
+    },
+    Storage {
+        message: String,
+        source: String,
+    },
+    Internal {
+        message: String,
+        source: String,
+    },
+}
+
+impl AppError {
+    /// Stable, machine-readable code sent with every error response. Clients should match on this
+    /// rather than on messages, which may change.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::TokenExpired => "token_expired",
+            AppError::InvalidToken => "invalid_token",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::NotFound(_) => "not_found",
+            AppError::Conflict(_) => "conflict",
+            AppError::PayloadTooLarge(_) => "payload_too_large",
+            AppError::RequestTimeout(_) => "request_timeout",
+            AppError::TooManyRequests { .. } => "rate_limited",
+            AppError::Database { .. } => "database_error",
+            AppError::Storage { .. } => "storage_error",
+            AppError::Internal { .. } => "internal_error",
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) | AppError::TokenExpired | AppError::InvalidToken => {
+                StatusCode::UNAUTHORIZED
+            }
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::RequestTimeout(_) => StatusCode::REQUEST_TIMEOUT,
+            AppError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Database { .. } | AppError::Storage { .. } | AppError::Internal { .. } => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::BadRequest(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::NotFound(message)
+            | AppError::Conflict(message)
+            | AppError::PayloadTooLarge(message)
+            | AppError::RequestTimeout(message)
+            | AppError::TooManyRequests { message, .. }
+            | AppError::Database { message, .. }
+            | AppError::Storage { message, .. }
+            | AppError::Internal { message, .. } => message,
+            AppError::TokenExpired => "Token has expired",
+            AppError::InvalidToken => "Invalid token",
+        }
+    }
+
+    pub fn internal(message: impl Into<String>, source: impl fmt::Display) -> Self {
+        AppError::Internal {
+            message: message.into(),
+            source: source.to_string(),
+        }
+    }
+
+    /// Replaces the generic message of a server error with one that says what was being done.
+    /// Client errors already say what is wrong and keep theirs.
+    pub fn with_message(mut self, new_message: impl Into<String>) -> Self {
+        if let AppError::Database { message, .. }
+        | AppError::Storage { message, .. }
+        | AppError::Internal { message, .. } = &mut self
+        {
+            *message = new_message.into();
+        }
+
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Database { message, source } => write!(f, "{}: {}", message, source),
+            AppError::Storage { message, source } | AppError::Internal { message, source } => {
+                write!(f, "{}: {}", message, source)
+            }
+            _ => f.write_str(self.message()),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let code = self.code();
+
+        if status.is_server_error() {
+            error!(code, "REQUEST FAILED: {}!", self);
+        }
+
+        let retry_after = match &self {
+            AppError::TooManyRequests {
+                retry_after_secs, ..
+            } => Some(*retry_after_secs),
+            _ => None,
+        };
+
+        let body = ApiResponse::<()> {
+            status,
+            response_message: self.message().to_string(),
+            count: None,
+            response: None,
+            error: Some(status.canonical_reason().unwrap_or("Error").to_string()),
+            code: Some(code),
+        };
+
+        match retry_after {
+            Some(secs) => ([(header::RETRY_AFTER, secs.to_string())], body).into_response(),
+            None => body.into_response(),
+        }
+    }
+}
+
+/// Gives a failed call's error a message saying what was being done, e.g.
+/// `.context("Failed to create message")?`.
+pub trait ErrorContext<T> {
+    fn context(self, message: &str) -> Result<T, AppError>;
+}
+
+impl<T, E: Into<AppError>> ErrorContext<T> for Result<T, E> {
+    fn context(self, message: &str) -> Result<T, AppError> {
+        self.map_err(|e| e.into().with_message(message))
+    }
+}
+
+// ============================================================================
+// Conversions
+// ============================================================================
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        match &e {
+            sqlx::Error::RowNotFound => AppError::NotFound("Resource not found".to_string()),
+            sqlx::Error::Database(db_error) if db_error.is_unique_violation() => {
+                AppError::Conflict("Resource already exists".to_string())
+            }
+            sqlx::Error::Database(db_error) if db_error.is_foreign_key_violation() => {
+                AppError::BadRequest("Referenced resource does not exist".to_string())
+            }
+            _ => AppError::Database {
+                message: "Database error".to_string(),
+                source: e,
+            },
+        }
+    }
+}
+
+impl From<MultipartError> for AppError {
+    fn from(e: MultipartError) -> Self {
+        // axum's multipart errors describe what was wrong with the request body
+        if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            AppError::PayloadTooLarge(e.body_text())
+        } else {
+            AppError::BadRequest(e.body_text())
+        }
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for AppError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        match e.kind() {
+            ErrorKind::ExpiredSignature => AppError::TokenExpired,
+            _ => AppError::InvalidToken,
+        }
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(e: anyhow::Error) -> Self {
+        AppError::internal("Internal server error", e)
+    }
+}
+
+impl<E, R> From<SdkError<E, R>> for AppError
+where
+    E: std::error::Error + Send + Sync + 'static,
+    R: fmt::Debug + Send + Sync + 'static,
+{
+    fn from(e: SdkError<E, R>) -> Self {
+        AppError::Storage {
+            message: "File storage error".to_string(),
+            source: DisplayErrorContext(&e).to_string(),
+        }
+    }
+}
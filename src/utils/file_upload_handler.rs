@@ -1,13 +1,20 @@
-use aws_sdk_s3::Client;
+use crate::utils::app_error::AppError;
+use crate::utils::object_store::content_type_for;
 use axum::extract::State;
-use axum::extract::multipart::{Field, MultipartError};
+use axum::extract::multipart::Field;
 use serde::Serialize;
-use std::env;
-
-#[derive(Clone, Debug)]
-pub struct S3AppState {
-    pub s3_client: Client,
-    pub bucket_name: String,
+use tracing::{Instrument, Span, field::Empty, info_span};
+
+/// Child span for one upload, so slow uploads show up in the request's trace.
+fn put_object_span(backend: &str, key: &str, size: usize) -> Span {
+    info_span!(
+        "object_store.put",
+        otel.kind = "client",
+        otel.status_code = Empty,
+        storage.backend = %backend,
+        storage.key = %key,
+        upload.size_bytes = size,
+    )
 }
 
 #[derive(Serialize)]
@@ -15,28 +22,6 @@ struct ErrorResponse {
     error: String,
 }
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
-pub struct UserProfile {
-    id: i64,
-    full_name: String,
-    email: String,
-    profile_image: Option<String>,
-    access_token: String,
-    refresh_token: String,
-    status: String,
-    last_seen: Option<String>,
-    #[serde(skip_serializing)]
-    password: String,
-    is_admin: bool,
-    is_active: bool,
-}
-
-pub struct UpdateResponse {
-    response_message: String,
-    response: Option<UserProfile>,
-    error: Option<String>,
-}
-
 pub enum UploadType {
     UserProfileImage,
     RoomProfileImage,
@@ -46,96 +31,90 @@ pub enum UploadType {
     MessageAttachment_4,
 }
 
+/// The extension comes from the client - anything odd is dropped rather than put in a key.
+fn sanitized_extension(extension: &str) -> String {
+    if !extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric()) {
+        extension.to_ascii_lowercase()
+    } else {
+        "bin".to_string()
+    }
+}
+
+/// Object keys end up in public urls, so each one carries a random part - knowing one upload's
+/// url says nothing about the urls of the others.
+fn object_key(upload_type: UploadType, upload_id: &i64, extension: &str) -> String {
+    let prefix = match upload_type {
+        UploadType::RoomProfileImage => "room_image",
+        UploadType::UserProfileImage => "profile_image",
+        UploadType::MessageAttachment_1 => "message_attachment_1",
+        UploadType::MessageAttachment_2 => "message_attachment_2",
+        UploadType::MessageAttachment_3 => "message_attachment_3",
+        UploadType::MessageAttachment_4 => "message_attachment_4",
+    };
+
+    format!(
+        "{}_{}_{}.{}",
+        prefix,
+        upload_id,
+        uuid::Uuid::new_v4().simple(),
+        extension
+    )
+}
+
+/// Hands the file to the configured object store and returns its url.
+async fn store_object(
+    state: &crate::AppState,
+    file_key: &str,
+    content_type: &str,
+    data: Vec<u8>,
+) -> Result<String, AppError> {
+    let backend = state.storage.backend();
+    let size = data.len();
+
+    let upload_span = put_object_span(backend, file_key, size);
+
+    let upload_result = state
+        .storage
+        .put(file_key, content_type, data)
+        .instrument(upload_span.clone())
+        .await;
+
+    if upload_result.is_err() {
+        upload_span.record("otel.status_code", "error");
+    }
+
+    state
+        .metrics
+        .record_upload(backend, size, upload_result.is_ok());
+
+    upload_result.map_err(|e| AppError::Storage {
+        message: "File storage error".to_string(),
+        source: format!("{:#}", e),
+    })
+}
+
 pub async fn upload_file(
     State(state): State<&crate::AppState>,
     field: Field<'_>,
     upload_id: &i64,
     upload_type: UploadType,
-) -> Result<String, MultipartError> {
-    // Your implementation
-
-    // Generate unique S3 key
+) -> Result<String, AppError> {
     let extension = field
         .file_name()
-        .expect("Failed to extract object file name!")
-        .split('.')
-        .next_back()
-        .expect("Failed to get object extension");
+        .and_then(|file_name| file_name.split('.').next_back())
+        .ok_or_else(|| AppError::BadRequest("Uploaded file has no file name".into()))?
+        .to_string();
 
-    let file_key = match upload_type {
-        UploadType::RoomProfileImage => {
-            format!("room_image_{}.{}", upload_id, extension)
-        }
-        UploadType::UserProfileImage => {
-            format!("profile_image_{}.{}", upload_id, extension)
-        }
-        UploadType::MessageAttachment_1 => {
-            format!(
-                "message_attachment_1_{}.{}",
-                upload_id,
-                // uuid::Uuid::new_v4(),
-                extension
-            )
-        }
-        UploadType::MessageAttachment_2 => {
-            format!(
-                "message_attachment_2_{}.{}",
-                upload_id,
-                // uuid::Uuid::new_v4(),
-                extension
-            )
-        }
-        UploadType::MessageAttachment_3 => {
-            format!(
-                "message_attachment_3_{}.{}",
-                upload_id,
-                // uuid::Uuid::new_v4(),
-                extension
-            )
-        }
-        UploadType::MessageAttachment_4 => {
-            format!(
-                "message_attachment_4_{}.{}",
-                upload_id,
-                // uuid::Uuid::new_v4(),
-                extension
-            )
-        }
-    };
-
-    let aws_region = env::var("AWS_S3_BUCKET_REGION").expect("AWS_S3_BUCKET_REGION must be set");
+    let extension = sanitized_extension(&extension);
+    let file_key = object_key(upload_type, upload_id, &extension);
 
-    // Construct the S3 URL
-    // Format: https://{bucket}.s3.{region}.amazonaws.com/{key}
-    let file_url = format!(
-        "https://{}.s3.{}.amazonaws.com/{}",
-        state.s3.bucket_name, aws_region, file_key
-    );
+    // served back with a type of our choosing, never the one the client claimed
+    let content_type = content_type_for(&extension);
 
-    let content_type = field
-        .content_type()
-        .map(|ct| ct.to_string())
-        .expect("Failed to extract object content type!");
-
-    // Use streaming body for S3 upload
     // let body = aws_sdk_s3::primitives::ByteStream::from_stream(field);
     let data = field.bytes().await?.to_vec();
 
-    // Upload to S3
-    state
-        .s3
-        .s3_client
-        .put_object()
-        .bucket(&state.s3.bucket_name)
-        .key(&file_key)
-        .content_type(content_type)
-        .body(data.into())
-        // .body(body)
-        .send()
-        .await
-        .expect("S3 File upload failed!");
-
-    Ok(file_url)
+    store_object(state, &file_key, content_type, data).await
 }
 
 pub async fn upload_file_from_bytes(
@@ -144,70 +123,16 @@ pub async fn upload_file_from_bytes(
     filename: &str,
     upload_id: &i64,
     upload_type: UploadType,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     // Extract extension from filename
-    let extension = filename
-        .split('.')
-        .next_back()
-        .unwrap_or("bin");
-
-    let file_key = match upload_type {
-        UploadType::RoomProfileImage => {
-            format!("room_image_{}.{}", upload_id, extension)
-        }
-        UploadType::UserProfileImage => {
-            format!("profile_image_{}.{}", upload_id, extension)
-        }
-        UploadType::MessageAttachment_1 => {
-            format!("message_attachment_1_{}.{}", upload_id, extension)
-        }
-        UploadType::MessageAttachment_2 => {
-            format!("message_attachment_2_{}.{}", upload_id, extension)
-        }
-        UploadType::MessageAttachment_3 => {
-            format!("message_attachment_3_{}.{}", upload_id, extension)
-        }
-        UploadType::MessageAttachment_4 => {
-            format!("message_attachment_4_{}.{}", upload_id, extension)
-        }
-    };
-
-    let aws_region = env::var("AWS_S3_BUCKET_REGION").expect("AWS_S3_BUCKET_REGION must be set");
+    let extension = sanitized_extension(filename.split('.').next_back().unwrap_or("bin"));
 
-    let file_url = format!(
-        "https://{}.s3.{}.amazonaws.com/{}",
-        state.s3.bucket_name, aws_region, file_key
-    );
+    let file_key = object_key(upload_type, upload_id, &extension);
 
     // Infer content type from extension
-    let content_type = match extension {
-        "jpg" | "jpeg" => "image/jpeg",
-        "png" => "image/png",
-        "gif" => "image/gif",
-        "webp" => "image/webp",
-        "pdf" => "application/pdf",
-        "mp4" => "video/mp4",
-        "mp3" => "audio/mpeg",
-        _ => "application/octet-stream",
-    };
-
-    let byte_stream = aws_sdk_s3::primitives::ByteStream::from(bytes);
-
-    // Upload to S3
-    state
-        .s3
-        .s3_client
-        .put_object()
-        .bucket(&state.s3.bucket_name)
-        .key(&file_key)
-        .content_type(content_type)
-        // .body(bytes.into())
-        .body(byte_stream)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    let content_type = content_type_for(&extension);
 
-    Ok(file_url)
+    store_object(state, &file_key, content_type, bytes).await
 }
 
 // pub async fn streaming_upload(
@@ -286,4 +211,4 @@ pub async fn upload_file_from_bytes(
 //         .expect("S3 File upload failed!");
 
 //     Ok(file_url)
-// }
\ No newline at end of file
+// }
@@ -1,4 +1,5 @@
 use crate::utils::hashing_handler::hashing_handler;
+use crate::utils::load_config::AppConfig;
 use crate::utils::load_env::load_env;
 use chrono::{Duration, Utc};
 use jsonwebtoken::errors::Error as JwtError;
@@ -12,6 +13,23 @@ pub struct Claims {
     pub email: String,
     pub exp: usize,
     pub iat: usize,
+    // only present on tokens issued for a row of the sessions table
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    // set on everything but access tokens (refresh, password reset, 2FA challenge) so they can't
+    // stand in for one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+}
+
+impl Claims {
+    /// Only access tokens may be presented as a bearer token. Refresh tokens issued before they
+    /// were given a purpose still carry a jti, so they are turned away too.
+    pub fn is_access_token(&self) -> bool {
+        self.purpose.is_none() && self.jti.is_none()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -27,31 +45,62 @@ pub struct Tokens {
     pub access_token: Option<String>,
     pub refresh_token: Option<String>,
     pub one_time_password_token: Option<String>,
+    pub two_factor_challenge_token: Option<String>,
     pub auth_cookie: Option<String>,
 }
 
-pub async fn generate_tokens(token_type: &str, user: User) -> Result<Tokens, JwtError> {
+pub async fn generate_tokens(
+    config: &AppConfig,
+    token_type: &str,
+    user: User,
+) -> Result<Tokens, JwtError> {
+    build_tokens(config, token_type, user, None, None).await
+}
+
+/// Same as `generate_tokens("auth", ..)`, but binds both tokens to a session. The refresh token
+/// carries `refresh_jti`, which must match the session row for the token to be accepted.
+pub async fn generate_session_tokens(
+    config: &AppConfig,
+    user: User,
+    session_id: i64,
+    refresh_jti: &str,
+) -> Result<Tokens, JwtError> {
+    build_tokens(config, "auth", user, Some(session_id), Some(refresh_jti)).await
+}
+
+/// Same as `generate_tokens("two_factor_challenge", ..)`, but carries the id of the challenge row
+/// so its attempts can be counted and the challenge burned once it's answered.
+pub async fn generate_two_factor_challenge(
+    config: &AppConfig,
+    user: User,
+    challenge_id: &str,
+) -> Result<Tokens, JwtError> {
+    build_tokens(config, "two_factor_challenge", user, None, Some(challenge_id)).await
+}
+
+async fn build_tokens(
+    config: &AppConfig,
+    token_type: &str,
+    user: User,
+    session_id: Option<i64>,
+    jti: Option<&str>,
+) -> Result<Tokens, JwtError> {
     load_env();
 
     let jwt_secret = env::var("JWT_SECRET").unwrap();
-    let access_expiry = env::var("JWT_ACCESS_EXPIRATION_TIME").unwrap_or("1".to_string());
-    let session_expiry = env::var("JWT_SESSION_EXPIRATION_TIME").unwrap_or("24".to_string());
-    let otp_expiry = env::var("JWT_ONE_TIME_PASSWORD_LIFETIME").unwrap_or("5".to_string());
+    let ttls = config.token_ttls();
 
-    let access_token_expiration = Utc::now()
-        .checked_add_signed(Duration::hours(access_expiry.parse().unwrap()))
-        .unwrap()
-        .timestamp() as usize;
+    let expires_in = |secs: u64| {
+        Utc::now()
+            .checked_add_signed(Duration::seconds(secs as i64))
+            .unwrap()
+            .timestamp() as usize
+    };
 
-    let refresh_token_expiration = Utc::now()
-        .checked_add_signed(Duration::hours(session_expiry.parse().unwrap()))
-        .unwrap()
-        .timestamp() as usize;
-
-    let otp_token_expiration = Utc::now()
-        .checked_add_signed(Duration::minutes(otp_expiry.parse().unwrap()))
-        .unwrap()
-        .timestamp() as usize;
+    let access_token_expiration = expires_in(ttls.access_token_secs);
+    let refresh_token_expiration = expires_in(ttls.refresh_token_secs);
+    let otp_token_expiration = expires_in(ttls.one_time_password_secs);
+    let challenge_token_expiration = expires_in(ttls.two_factor_challenge_secs);
 
     match token_type {
         "auth" => {
@@ -60,6 +109,9 @@ pub async fn generate_tokens(token_type: &str, user: User) -> Result<Tokens, Jwt
                 email: user.email.clone(),
                 exp: access_token_expiration,
                 iat: Utc::now().timestamp_millis() as usize,
+                sid: session_id,
+                jti: None,
+                purpose: None,
             };
 
             let access_token = encode(
@@ -73,6 +125,9 @@ pub async fn generate_tokens(token_type: &str, user: User) -> Result<Tokens, Jwt
                 email: user.email.clone(),
                 exp: refresh_token_expiration,
                 iat: Utc::now().timestamp_millis() as usize,
+                sid: session_id,
+                jti: jti.map(str::to_string),
+                purpose: Some("refresh".to_string()),
             };
 
             let refresh_token = encode(
@@ -100,6 +155,7 @@ pub async fn generate_tokens(token_type: &str, user: User) -> Result<Tokens, Jwt
                 access_token: Some(access_token),
                 refresh_token: Some(refresh_token),
                 one_time_password_token: None,
+                two_factor_challenge_token: None,
                 auth_cookie: Some(auth_cookie),
             })
         }
@@ -110,6 +166,9 @@ pub async fn generate_tokens(token_type: &str, user: User) -> Result<Tokens, Jwt
                 email: user.email.clone(),
                 exp: otp_token_expiration,
                 iat: Utc::now().timestamp_millis() as usize,
+                sid: None,
+                jti: None,
+                purpose: Some(token_type.to_string()),
             };
 
             let otp_token = encode(
@@ -122,6 +181,33 @@ pub async fn generate_tokens(token_type: &str, user: User) -> Result<Tokens, Jwt
                 access_token: None,
                 refresh_token: None,
                 one_time_password_token: Some(otp_token),
+                two_factor_challenge_token: None,
+                auth_cookie: None,
+            })
+        }
+
+        "two_factor_challenge" => {
+            let challenge_claims = Claims {
+                id: user.id,
+                email: user.email.clone(),
+                exp: challenge_token_expiration,
+                iat: Utc::now().timestamp_millis() as usize,
+                sid: None,
+                jti: jti.map(str::to_string),
+                purpose: Some(token_type.to_string()),
+            };
+
+            let challenge_token = encode(
+                &Header::default(),
+                &challenge_claims,
+                &EncodingKey::from_secret(jwt_secret.as_bytes()),
+            )?;
+
+            Ok(Tokens {
+                access_token: None,
+                refresh_token: None,
+                one_time_password_token: None,
+                two_factor_challenge_token: Some(challenge_token),
                 auth_cookie: None,
             })
         }
@@ -130,6 +216,7 @@ pub async fn generate_tokens(token_type: &str, user: User) -> Result<Tokens, Jwt
             access_token: None,
             refresh_token: None,
             one_time_password_token: None,
+            two_factor_challenge_token: None,
             auth_cookie: None,
         }),
     }
@@ -2,6 +2,9 @@ use crate::utils::load_env::load_env;
 use anyhow::{Context, Result};
 use config::{Config, Environment, File};
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
 
 #[derive(Debug, Deserialize)]
 pub struct AppConfig {
@@ -11,9 +14,12 @@ pub struct AppConfig {
 
     // Optional / currently commented-out sections
     pub server: Option<ServerSection>,
-    // pub database: Option<DatabaseSection>,
-    // pub auth: Option<AuthSection>,
-    // pub security: Option<SecuritySection>,
+    pub messages: Option<MessagesSection>,
+    pub mail: Option<MailSection>,
+    pub auth: Option<AuthSection>,
+    pub security: Option<SecuritySection>,
+    pub database: Option<DatabaseSection>,
+    pub storage: Option<StorageSection>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -41,42 +47,184 @@ pub struct ClientIntegrationsSection {
 
     #[serde(default)]
     pub allow_admin_routes_protector_middleware: bool,
+
+    #[serde(default)]
+    pub allow_rate_limit_middleware: bool,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceExporterKind {
+    // one JSON line per batch on stdout, for local runs
+    #[default]
+    Stdout,
+    File,
+    // OTLP/HTTP with JSON payloads, e.g. to a local OpenTelemetry collector
+    Otlp,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ObservabilitySection {
     pub enable_tracing: bool,
     pub enable_metrics: bool,
+    #[serde(default)]
+    pub trace_exporter: TraceExporterKind,
+    // only used by the file exporter
+    pub trace_file: Option<String>,
+    // base url of the collector, spans are posted to `<otlp_endpoint>/v1/traces`
+    pub otlp_endpoint: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ServerSection {
     pub host: String,
     pub port: u16,
-    // pub graceful_shutdown_secs: u64,
-}
-
-// #[derive(Debug, Deserialize)]
-// pub struct DatabaseSection {
-//     pub engine: String,
-//     pub host: String,
-//     pub port: u16,
-//     pub name: String,
-//     pub max_connections: u32,
-//     pub connect_timeout_secs: u64,
-// }
-
-// #[derive(Debug, Deserialize)]
-// pub struct AuthSection {
-//     pub access_token_ttl_secs: u64,
-//     pub refresh_token_ttl_secs: u64,
-// }
-
-// #[derive(Debug, Deserialize)]
-// pub struct SecuritySection {
-//     pub bcrypt_cost: u32,
-//     pub rate_limit_per_minute: u32,
-// }
+    // requests running longer than this are answered with a 408
+    pub request_timeout_secs: Option<u64>,
+    // how long in-flight requests and websocket connections get to finish on shutdown
+    pub graceful_shutdown_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MessagesSection {
+    // how long after sending a message its sender can still delete it for everyone
+    pub delete_for_everyone_window_secs: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MailSenderKind {
+    Log,
+    File,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MailSection {
+    pub sender: MailSenderKind,
+    pub from_address: Option<String>,
+    // only used by the file sender
+    pub outbox_dir: Option<String>,
+}
+
+// Credentials stay in the POSTGRES_* env variables. host, port and name fall back to them too when
+// they aren't set here.
+#[derive(Debug, Deserialize)]
+pub struct DatabaseSection {
+    // pub engine: String,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub name: Option<String>,
+    pub max_connections: Option<u32>,
+    // how long to wait for a free connection from the pool
+    pub connect_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackendKind {
+    #[default]
+    S3,
+    // files under `local_dir`, served by the server itself
+    Local,
+    // lost on restart, for tests and throwaway setups
+    Memory,
+}
+
+// Credentials stay in the AWS_ACCESS_KEY and AWS_SECRET_ACCESS_KEY env variables. bucket and region
+// fall back to AWS_S3_BUCKET_NAME and AWS_S3_BUCKET_REGION when they aren't set here.
+#[derive(Debug, Deserialize)]
+pub struct StorageSection {
+    #[serde(default)]
+    pub backend: StorageBackendKind,
+    pub bucket: Option<String>,
+    pub region: Option<String>,
+    // S3-compatible servers like MinIO, e.g. "http://localhost:9000" - switches to path-style urls
+    pub endpoint_url: Option<String>,
+    // only used by the local backend
+    pub local_dir: Option<String>,
+    // prefix of the urls handed out for stored files, e.g. a CDN in front of the bucket
+    pub public_base_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailVerificationMode {
+    // unverified users are treated like everyone else
+    #[default]
+    Off,
+    // unverified users can't log in
+    Login,
+    // unverified users can log in but can't send messages
+    Messaging,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuthSection {
+    #[serde(default)]
+    pub email_verification: EmailVerificationMode,
+    pub verification_resend_cooldown_secs: Option<u64>,
+    pub access_token_ttl_secs: Option<u64>,
+    // also how long a session lives without being refreshed
+    pub refresh_token_ttl_secs: Option<u64>,
+    pub one_time_password_ttl_secs: Option<u64>,
+    pub two_factor_challenge_ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TokenTtls {
+    pub access_token_secs: u64,
+    pub refresh_token_secs: u64,
+    pub one_time_password_secs: u64,
+    pub two_factor_challenge_secs: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct SecuritySection {
+    // failed logins inside the window before the account or IP is locked out
+    pub max_failed_logins_per_account: u32,
+    pub max_failed_logins_per_ip: u32,
+    pub failed_login_window_secs: u64,
+    pub lockout_duration_secs: u64,
+    // each further failure doubles the delay, up to the max
+    pub failed_login_base_delay_ms: u64,
+    pub failed_login_max_delay_ms: u64,
+    // requests per minute for each user (or IP when signed out), per rate limit policy
+    pub rate_limit_per_minute: u32,
+    // stricter or looser limits for named policies, e.g. `login = 10`
+    pub rate_limit_overrides: HashMap<String, u32>,
+    // load balancers / reverse proxies whose X-Forwarded-For is believed, e.g. ["10.0.0.2"]
+    pub trusted_proxies: Vec<IpAddr>,
+    // pub bcrypt_cost: u32,
+}
+
+impl SecuritySection {
+    pub fn rate_limit_for(&self, policy: &str) -> u32 {
+        self.rate_limit_overrides
+            .get(policy)
+            .copied()
+            .unwrap_or(self.rate_limit_per_minute)
+    }
+}
+
+impl Default for SecuritySection {
+    fn default() -> Self {
+        Self {
+            max_failed_logins_per_account: 5,
+            max_failed_logins_per_ip: 20,
+            failed_login_window_secs: 900,
+            lockout_duration_secs: 900,
+            failed_login_base_delay_ms: 250,
+            failed_login_max_delay_ms: 4000,
+            rate_limit_per_minute: 60,
+            rate_limit_overrides: HashMap::from([
+                ("login".to_string(), 10),
+                ("create_message".to_string(), 30),
+            ]),
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
 
 pub fn load_config() -> Result<AppConfig> {
     load_env();
@@ -141,8 +289,199 @@ impl AppConfig {
             if server.port == 0 {
                 anyhow::bail!("server.port cannot be 0");
             }
+
+            if server.request_timeout_secs == Some(0) {
+                anyhow::bail!("server.request_timeout_secs cannot be 0");
+            }
+        }
+
+        if let Some(database) = &self.database
+            && database.max_connections == Some(0)
+        {
+            anyhow::bail!("database.max_connections cannot be 0");
+        }
+
+        let ttls = self.token_ttls();
+        if ttls.access_token_secs == 0
+            || ttls.refresh_token_secs == 0
+            || ttls.one_time_password_secs == 0
+            || ttls.two_factor_challenge_secs == 0
+        {
+            anyhow::bail!("auth token ttls cannot be 0");
+        }
+
+        let observability = &self.observability;
+        if observability.enable_tracing
+            && observability.trace_exporter == TraceExporterKind::Otlp
+            && observability
+                .otlp_endpoint
+                .as_deref()
+                .is_some_and(|endpoint| !endpoint.starts_with("http://"))
+        {
+            anyhow::bail!("observability.otlp_endpoint must be a plain http:// url");
+        }
+
+        if let Some(storage) = &self.storage {
+            for (field, url) in [
+                ("endpoint_url", &storage.endpoint_url),
+                ("public_base_url", &storage.public_base_url),
+            ] {
+                if url
+                    .as_deref()
+                    .is_some_and(|url| !url.starts_with("http://") && !url.starts_with("https://"))
+                {
+                    anyhow::bail!("storage.{} must be an http:// or https:// url", field);
+                }
+            }
+        }
+
+        let integrations = &self.client_integrations;
+
+        // every authenticated handler, and the access middleware, reads the user the sessions
+        // middleware puts on the request - without it they all fail with a 500
+        if !integrations.allow_sessions_middleware {
+            anyhow::bail!("client_integrations.allow_sessions_middleware cannot be disabled");
+        }
+
+        if self.is_production() {
+            let disabled: Vec<&str> = [
+                (
+                    "allow_access_middleware",
+                    integrations.allow_access_middleware,
+                ),
+                (
+                    "allow_admin_routes_protector_middleware",
+                    integrations.allow_admin_routes_protector_middleware,
+                ),
+                (
+                    "allow_rate_limit_middleware",
+                    integrations.allow_rate_limit_middleware,
+                ),
+            ]
+            .into_iter()
+            .filter(|(_, enabled)| !enabled)
+            .map(|(name, _)| name)
+            .collect();
+
+            if !disabled.is_empty() {
+                anyhow::bail!(
+                    "client_integrations {} cannot be disabled in production",
+                    disabled.join(", ")
+                );
+            }
         }
 
         Ok(())
     }
+
+    pub fn is_production(&self) -> bool {
+        self.app.environment.as_deref() == Some("production")
+    }
+
+    pub fn email_verification_mode(&self) -> EmailVerificationMode {
+        self.auth
+            .as_ref()
+            .map(|auth| auth.email_verification)
+            .unwrap_or_default()
+    }
+
+    pub fn storage_backend(&self) -> StorageBackendKind {
+        self.storage
+            .as_ref()
+            .map(|storage| storage.backend)
+            .unwrap_or_default()
+    }
+
+    pub fn bind_address(&self) -> (String, u16) {
+        self.server
+            .as_ref()
+            .map(|server| (server.host.clone(), server.port))
+            .unwrap_or_else(|| ("127.0.0.1".to_string(), 8000))
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.server
+                .as_ref()
+                .and_then(|server| server.request_timeout_secs)
+                .unwrap_or(60),
+        )
+    }
+
+    pub fn graceful_shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.server
+                .as_ref()
+                .and_then(|server| server.graceful_shutdown_secs)
+                .unwrap_or(30),
+        )
+    }
+
+    pub fn token_ttls(&self) -> TokenTtls {
+        let auth = self.auth.as_ref();
+
+        TokenTtls {
+            access_token_secs: auth.and_then(|a| a.access_token_ttl_secs).unwrap_or(3600),
+            refresh_token_secs: auth.and_then(|a| a.refresh_token_ttl_secs).unwrap_or(86400),
+            one_time_password_secs: auth
+                .and_then(|a| a.one_time_password_ttl_secs)
+                .unwrap_or(300),
+            two_factor_challenge_secs: auth
+                .and_then(|a| a.two_factor_challenge_ttl_secs)
+                .unwrap_or(300),
+        }
+    }
+
+    pub fn trusted_proxies(&self) -> &[IpAddr] {
+        self.security
+            .as_ref()
+            .map(|security| security.trusted_proxies.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn rate_limit_for(&self, policy: &str) -> u32 {
+        match &self.security {
+            Some(security) => security.rate_limit_for(policy),
+            None => SecuritySection::default().rate_limit_for(policy),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::FileFormat;
+
+    fn config_with_integrations(integrations: &str) -> AppConfig {
+        let toml = format!(
+            r#"
+            [app]
+            name = "rusty-chat"
+
+            [observability]
+            enable_tracing = false
+            enable_metrics = false
+
+            [client_integrations]
+            {}
+            "#,
+            integrations
+        );
+
+        Config::builder()
+            .add_source(File::from_str(&toml, FileFormat::Toml))
+            .build()
+            .and_then(|config| config.try_deserialize())
+            .unwrap()
+    }
+
+    #[test]
+    fn the_sessions_middleware_cannot_be_turned_off() {
+        let without_sessions = config_with_integrations("allow_sessions_middleware = false");
+        let err = without_sessions.validate().unwrap_err();
+        assert!(err.to_string().contains("allow_sessions_middleware"));
+
+        let with_sessions = config_with_integrations("allow_sessions_middleware = true");
+        assert!(with_sessions.validate().is_ok());
+    }
 }
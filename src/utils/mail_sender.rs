@@ -0,0 +1,99 @@
+use crate::utils::current_time_in_milliseconds::current_time_millis;
+use crate::utils::load_config::{MailSection, MailSenderKind};
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use tracing::info;
+
+const DEFAULT_FROM_ADDRESS: &str = "no-reply@rustychat.local";
+const DEFAULT_OUTBOX_DIR: &str = "outbox";
+
+#[derive(Debug, Clone)]
+pub struct OutgoingMail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+pub type MailFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Anything that can deliver mail. Handlers only see `state.mailer`, so an SMTP or API backed
+/// sender can be dropped in without touching them.
+pub trait MailSender: Send + Sync + std::fmt::Debug {
+    fn send<'a>(&'a self, mail: &'a OutgoingMail) -> MailFuture<'a>;
+}
+
+/// Writes mail to the server log - for local development only, the body ends up in plain text.
+#[derive(Debug)]
+pub struct LogMailSender {
+    from_address: String,
+}
+
+impl MailSender for LogMailSender {
+    fn send<'a>(&'a self, mail: &'a OutgoingMail) -> MailFuture<'a> {
+        Box::pin(async move {
+            info!(
+                from = %self.from_address,
+                to = %mail.to,
+                subject = %mail.subject,
+                body = %mail.body,
+                "MAIL SENT TO LOG"
+            );
+
+            Ok(())
+        })
+    }
+}
+
+/// Drops every mail as a file in the outbox directory, one file per message.
+#[derive(Debug)]
+pub struct FileMailSender {
+    from_address: String,
+    outbox_dir: PathBuf,
+}
+
+impl MailSender for FileMailSender {
+    fn send<'a>(&'a self, mail: &'a OutgoingMail) -> MailFuture<'a> {
+        Box::pin(async move {
+            tokio::fs::create_dir_all(&self.outbox_dir)
+                .await
+                .context("Failed to create mail outbox directory")?;
+
+            let file_name = format!(
+                "{}_{}.eml",
+                current_time_millis(),
+                mail.to
+                    .replace(|c: char| !c.is_ascii_alphanumeric() && c != '.', "_")
+            );
+
+            let contents = format!(
+                "From: {}\r\nTo: {}\r\nSubject: {}\r\n\r\n{}\r\n",
+                self.from_address, mail.to, mail.subject, mail.body
+            );
+
+            tokio::fs::write(self.outbox_dir.join(file_name), contents)
+                .await
+                .context("Failed to write mail to outbox")
+        })
+    }
+}
+
+/// Picks the sender configured in the [mail] section, falling back to the log sender.
+pub fn build_mail_sender(config: Option<&MailSection>) -> Arc<dyn MailSender> {
+    let from_address = config
+        .and_then(|mail| mail.from_address.clone())
+        .unwrap_or_else(|| DEFAULT_FROM_ADDRESS.to_string());
+
+    match config.map(|mail| &mail.sender) {
+        Some(MailSenderKind::File) => Arc::new(FileMailSender {
+            from_address,
+            outbox_dir: config
+                .and_then(|mail| mail.outbox_dir.clone())
+                .unwrap_or_else(|| DEFAULT_OUTBOX_DIR.to_string())
+                .into(),
+        }),
+        Some(MailSenderKind::Log) | None => Arc::new(LogMailSender { from_address }),
+    }
+}
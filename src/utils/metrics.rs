@@ -0,0 +1,252 @@
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+// upper bounds of the request latency buckets, in seconds
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct Metric {
+    pub name: &'static str,
+    pub help: &'static str,
+}
+
+pub const HTTP_REQUESTS: Metric = Metric {
+    name: "http_requests_total",
+    help: "HTTP requests handled, by matched route, method and status",
+};
+
+pub const HTTP_REQUEST_DURATION: Metric = Metric {
+    name: "http_request_duration_seconds",
+    help: "HTTP request latency, by matched route, method and status",
+};
+
+pub const UPLOADS: Metric = Metric {
+    name: "uploads_total",
+    help: "Files uploaded to object storage, by backend",
+};
+
+pub const UPLOAD_BYTES: Metric = Metric {
+    name: "upload_bytes_total",
+    help: "Bytes uploaded to object storage, by backend",
+};
+
+pub const UPLOAD_ERRORS: Metric = Metric {
+    name: "upload_errors_total",
+    help: "Uploads to object storage that failed, by backend",
+};
+
+pub const MESSAGES_CREATED: Metric = Metric {
+    name: "messages_created_total",
+    help: "Chat messages created",
+};
+
+pub const MESSAGE_REACTIONS: Metric = Metric {
+    name: "message_reactions_total",
+    help: "Reactions added to or changed on messages, by action",
+};
+
+pub const LOGINS: Metric = Metric {
+    name: "logins_total",
+    help: "Login attempts, by outcome",
+};
+
+type Labels = Vec<(&'static str, String)>;
+
+#[derive(Debug)]
+struct Family<T> {
+    help: &'static str,
+    series: BTreeMap<Labels, T>,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    // cumulative, one count per entry of LATENCY_BUCKETS
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    counters: BTreeMap<&'static str, Family<f64>>,
+    histograms: BTreeMap<&'static str, Family<Histogram>>,
+}
+
+/// Process-wide metrics, rendered in the Prometheus text format by `/metrics`.
+#[derive(Clone, Debug, Default)]
+pub struct Metrics {
+    registry: Arc<Mutex<Registry>>,
+}
+
+fn to_labels(labels: &[(&'static str, &str)]) -> Labels {
+    labels
+        .iter()
+        .map(|(name, value)| (*name, value.to_string()))
+        .collect()
+}
+
+fn format_labels(labels: &Labels, extra: Option<(&str, &str)>) -> String {
+    let escape = |value: &str| {
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    };
+
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .chain(extra)
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+        .collect();
+
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn increment(&self, metric: Metric, labels: &[(&'static str, &str)]) {
+        self.add(metric, labels, 1.0);
+    }
+
+    pub fn add(&self, metric: Metric, labels: &[(&'static str, &str)], value: f64) {
+        let mut registry = self.registry.lock().expect("Metrics lock poisoned!");
+
+        *registry
+            .counters
+            .entry(metric.name)
+            .or_insert_with(|| Family {
+                help: metric.help,
+                series: BTreeMap::new(),
+            })
+            .series
+            .entry(to_labels(labels))
+            .or_default() += value;
+    }
+
+    pub fn observe(&self, metric: Metric, labels: &[(&'static str, &str)], value: f64) {
+        let mut registry = self.registry.lock().expect("Metrics lock poisoned!");
+
+        let histogram = registry
+            .histograms
+            .entry(metric.name)
+            .or_insert_with(|| Family {
+                help: metric.help,
+                series: BTreeMap::new(),
+            })
+            .series
+            .entry(to_labels(labels))
+            .or_default();
+
+        for (count, bound) in histogram.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if value <= bound {
+                *count += 1;
+            }
+        }
+
+        histogram.sum += value;
+        histogram.count += 1;
+    }
+
+    pub fn record_upload(&self, backend: &str, bytes: usize, succeeded: bool) {
+        let labels = [("backend", backend)];
+
+        if succeeded {
+            self.increment(UPLOADS, &labels);
+            self.add(UPLOAD_BYTES, &labels, bytes as f64);
+        } else {
+            self.increment(UPLOAD_ERRORS, &labels);
+        }
+    }
+
+    /// Everything recorded so far, plus the database pool gauges read at call time.
+    pub fn render(&self, pool: &PgPool) -> String {
+        let mut out = String::new();
+
+        {
+            let registry = self.registry.lock().expect("Metrics lock poisoned!");
+
+            for (name, family) in &registry.counters {
+                let _ = writeln!(out, "# HELP {} {}", name, family.help);
+                let _ = writeln!(out, "# TYPE {} counter", name);
+
+                for (labels, value) in &family.series {
+                    let _ = writeln!(out, "{}{} {}", name, format_labels(labels, None), value);
+                }
+            }
+
+            for (name, family) in &registry.histograms {
+                let _ = writeln!(out, "# HELP {} {}", name, family.help);
+                let _ = writeln!(out, "# TYPE {} histogram", name);
+
+                for (labels, histogram) in &family.series {
+                    for (count, bound) in histogram.buckets.iter().zip(LATENCY_BUCKETS) {
+                        let _ = writeln!(
+                            out,
+                            "{}_bucket{} {}",
+                            name,
+                            format_labels(labels, Some(("le", &bound.to_string()))),
+                            count
+                        );
+                    }
+
+                    let _ = writeln!(
+                        out,
+                        "{}_bucket{} {}",
+                        name,
+                        format_labels(labels, Some(("le", "+Inf"))),
+                        histogram.count
+                    );
+                    let _ = writeln!(
+                        out,
+                        "{}_sum{} {}",
+                        name,
+                        format_labels(labels, None),
+                        histogram.sum
+                    );
+                    let _ = writeln!(
+                        out,
+                        "{}_count{} {}",
+                        name,
+                        format_labels(labels, None),
+                        histogram.count
+                    );
+                }
+            }
+        }
+
+        let idle = pool.num_idle() as u32;
+        let in_use = pool.size().saturating_sub(idle);
+
+        let _ = writeln!(
+            out,
+            "# HELP db_pool_connections Database pool connections, by state"
+        );
+        let _ = writeln!(out, "# TYPE db_pool_connections gauge");
+        let _ = writeln!(out, "db_pool_connections{{state=\"idle\"}} {}", idle);
+        let _ = writeln!(out, "db_pool_connections{{state=\"in_use\"}} {}", in_use);
+        let _ = writeln!(
+            out,
+            "# HELP db_pool_max_connections Database pool size limit"
+        );
+        let _ = writeln!(out, "# TYPE db_pool_max_connections gauge");
+        let _ = writeln!(
+            out,
+            "db_pool_max_connections {}",
+            pool.options().get_max_connections()
+        );
+
+        out
+    }
+}
@@ -1,3 +1,5 @@
+pub mod api_response;
+pub mod app_error;
 pub mod cookie_deploy_handler;
 pub mod current_time_in_milliseconds;
 pub mod file_upload_handler;
@@ -5,4 +7,14 @@ pub mod generate_tokens;
 pub mod hashing_handler;
 pub mod load_config;
 pub mod load_env;
+pub mod mail_sender;
+pub mod metrics;
+pub mod object_store;
+pub mod pagination_cursor;
+pub mod presence_tracker;
+pub mod rate_limiter;
+pub mod realtime_hub;
+pub mod session_manager;
+pub mod shutdown;
+pub mod trace_exporter;
 pub mod verification_handler;
@@ -0,0 +1,376 @@
+use crate::utils::load_config::{AppConfig, StorageBackendKind};
+use anyhow::{Context, Result, anyhow};
+use aws_config::Region;
+use aws_credential_types::Credentials;
+use aws_sdk_s3::Client;
+use aws_sdk_s3::error::DisplayErrorContext;
+use std::collections::HashMap;
+use std::env;
+use std::future::Future;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+const DEFAULT_LOCAL_DIR: &str = "uploads";
+
+/// Route the local and in-memory backends serve their files from.
+pub const UPLOADS_ROUTE: &str = "/uploads";
+
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Anywhere uploaded files can live. Handlers only see `state.storage`, so the backend is a
+/// config change.
+pub trait ObjectStore: Send + Sync + std::fmt::Debug {
+    /// Short name for logs, metrics and the readiness check.
+    fn backend(&self) -> &'static str;
+
+    /// Stores `bytes` under `key`, replacing what was there, and returns the url clients fetch
+    /// the file from.
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        content_type: &'a str,
+        bytes: Vec<u8>,
+    ) -> StoreFuture<'a, String>;
+
+    fn get<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<StoredObject>>;
+
+    /// Cheap round trip to the backend, for the readiness check.
+    fn check(&self) -> StoreFuture<'_, ()>;
+}
+
+/// Keys are flat file names - anything that could climb out of the local directory is refused.
+pub fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with('.')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+pub fn content_type_for(extension: &str) -> &'static str {
+    match extension {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "mp4" => "video/mp4",
+        "mp3" => "audio/mpeg",
+        "txt" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Content type for a stored key, going by its extension.
+pub fn content_type_for_key(key: &str) -> &'static str {
+    let extension = key.rsplit_once('.').map(|(_, ext)| ext).unwrap_or_default();
+
+    content_type_for(extension)
+}
+
+fn object_url(public_base_url: &str, key: &str) -> String {
+    format!("{}/{}", public_base_url, key)
+}
+
+/// AWS S3, or any S3-compatible server when an endpoint url is configured.
+#[derive(Debug)]
+pub struct S3ObjectStore {
+    client: Client,
+    bucket: String,
+    public_base_url: String,
+}
+
+impl ObjectStore for S3ObjectStore {
+    fn backend(&self) -> &'static str {
+        "s3"
+    }
+
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        content_type: &'a str,
+        bytes: Vec<u8>,
+    ) -> StoreFuture<'a, String> {
+        Box::pin(async move {
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .content_type(content_type)
+                .body(bytes.into())
+                .send()
+                .await
+                .map_err(|e| anyhow!("{}", DisplayErrorContext(e)))
+                .with_context(|| format!("Failed to upload {} to bucket {}", key, self.bucket))?;
+
+            Ok(object_url(&self.public_base_url, key))
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<StoredObject>> {
+        Box::pin(async move {
+            let output = match self
+                .client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .send()
+                .await
+            {
+                Ok(output) => output,
+                Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => {
+                    return Ok(None);
+                }
+                Err(e) => {
+                    return Err(anyhow!("{}", DisplayErrorContext(e)))
+                        .with_context(|| format!("Failed to download {}", key));
+                }
+            };
+
+            let content_type = output
+                .content_type()
+                .unwrap_or("application/octet-stream")
+                .to_string();
+            let bytes = output
+                .body
+                .collect()
+                .await
+                .with_context(|| format!("Failed to read {}", key))?
+                .to_vec();
+
+            Ok(Some(StoredObject {
+                content_type,
+                bytes,
+            }))
+        })
+    }
+
+    fn check(&self) -> StoreFuture<'_, ()> {
+        Box::pin(async move {
+            self.client
+                .head_bucket()
+                .bucket(&self.bucket)
+                .send()
+                .await
+                .map_err(|e| anyhow!("{}", DisplayErrorContext(e)))
+                .with_context(|| format!("Bucket {} unreachable", self.bucket))?;
+
+            Ok(())
+        })
+    }
+}
+
+/// One file per object under a directory on this machine. The content type is worked out from
+/// the extension again when the file is read back.
+#[derive(Debug)]
+pub struct LocalObjectStore {
+    root: PathBuf,
+    public_base_url: String,
+}
+
+impl ObjectStore for LocalObjectStore {
+    fn backend(&self) -> &'static str {
+        "local"
+    }
+
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        _content_type: &'a str,
+        bytes: Vec<u8>,
+    ) -> StoreFuture<'a, String> {
+        Box::pin(async move {
+            anyhow::ensure!(is_valid_key(key), "Invalid object key: {}", key);
+
+            tokio::fs::create_dir_all(&self.root)
+                .await
+                .context("Failed to create uploads directory")?;
+            tokio::fs::write(self.root.join(key), bytes)
+                .await
+                .with_context(|| format!("Failed to write {}", key))?;
+
+            Ok(object_url(&self.public_base_url, key))
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<StoredObject>> {
+        Box::pin(async move {
+            if !is_valid_key(key) {
+                return Ok(None);
+            }
+
+            let bytes = match tokio::fs::read(self.root.join(key)).await {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(e).with_context(|| format!("Failed to read {}", key)),
+            };
+
+            Ok(Some(StoredObject {
+                content_type: content_type_for_key(key).to_string(),
+                bytes,
+            }))
+        })
+    }
+
+    fn check(&self) -> StoreFuture<'_, ()> {
+        Box::pin(async move {
+            tokio::fs::create_dir_all(&self.root)
+                .await
+                .with_context(|| format!("Uploads directory {} unusable", self.root.display()))
+        })
+    }
+}
+
+/// Keeps every object in memory - gone when the process exits. Like the local backend, it works
+/// the content type out from the key's extension instead of trusting the uploader's.
+#[derive(Debug)]
+pub struct InMemoryObjectStore {
+    objects: Mutex<HashMap<String, StoredObject>>,
+    public_base_url: String,
+}
+
+impl InMemoryObjectStore {
+    pub fn new(public_base_url: impl Into<String>) -> Self {
+        Self {
+            objects: Mutex::default(),
+            public_base_url: public_base_url.into(),
+        }
+    }
+}
+
+impl ObjectStore for InMemoryObjectStore {
+    fn backend(&self) -> &'static str {
+        "memory"
+    }
+
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        _content_type: &'a str,
+        bytes: Vec<u8>,
+    ) -> StoreFuture<'a, String> {
+        Box::pin(async move {
+            self.objects
+                .lock()
+                .expect("Object store lock poisoned!")
+                .insert(
+                    key.to_string(),
+                    StoredObject {
+                        content_type: content_type_for_key(key).to_string(),
+                        bytes,
+                    },
+                );
+
+            Ok(object_url(&self.public_base_url, key))
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<StoredObject>> {
+        Box::pin(async move {
+            Ok(self
+                .objects
+                .lock()
+                .expect("Object store lock poisoned!")
+                .get(key)
+                .cloned())
+        })
+    }
+
+    fn check(&self) -> StoreFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+async fn build_s3_store(
+    config: &AppConfig,
+    public_base_url: Option<String>,
+) -> Result<S3ObjectStore> {
+    let storage = config.storage.as_ref();
+
+    let access_key_id = env::var("AWS_ACCESS_KEY").context("AWS_ACCESS_KEY must be set")?;
+    let secret_access_key =
+        env::var("AWS_SECRET_ACCESS_KEY").context("AWS_SECRET_ACCESS_KEY must be set")?;
+    let region = match storage.and_then(|storage| storage.region.clone()) {
+        Some(region) => region,
+        None => env::var("AWS_S3_BUCKET_REGION").context("AWS_S3_BUCKET_REGION must be set")?,
+    };
+    let bucket = match storage.and_then(|storage| storage.bucket.clone()) {
+        Some(bucket) => bucket,
+        None => env::var("AWS_S3_BUCKET_NAME").context("AWS_S3_BUCKET_NAME must be set")?,
+    };
+    let endpoint_url = storage
+        .and_then(|storage| storage.endpoint_url.clone())
+        .map(|url| url.trim_end_matches('/').to_string());
+
+    // note here that the "None" is in place of a session token
+    let credentials = Credentials::from_keys(access_key_id, secret_access_key, None);
+
+    let mut loader = aws_config::from_env()
+        .region(Region::new(region.clone()))
+        .credentials_provider(credentials);
+
+    if let Some(endpoint_url) = &endpoint_url {
+        loader = loader.endpoint_url(endpoint_url);
+    }
+
+    let sdk_config = loader.load().await;
+
+    // MinIO and friends don't do bucket subdomains
+    let s3_config = aws_sdk_s3::config::Builder::from(&sdk_config)
+        .force_path_style(endpoint_url.is_some())
+        .build();
+
+    let public_base_url = public_base_url.unwrap_or_else(|| match &endpoint_url {
+        Some(endpoint_url) => format!("{}/{}", endpoint_url, bucket),
+        None => format!("https://{}.s3.{}.amazonaws.com", bucket, region),
+    });
+
+    Ok(S3ObjectStore {
+        client: Client::from_conf(s3_config),
+        bucket,
+        public_base_url,
+    })
+}
+
+/// Picks the backend configured in the [storage] section, falling back to S3.
+pub async fn build_object_store(config: &AppConfig) -> Result<Arc<dyn ObjectStore>> {
+    let storage = config.storage.as_ref();
+
+    let public_base_url = storage
+        .and_then(|storage| storage.public_base_url.as_deref())
+        .map(|url| url.trim_end_matches('/').to_string());
+
+    // the local backends serve their own files
+    let served_here = || {
+        public_base_url.clone().unwrap_or_else(|| {
+            let (host, port) = config.bind_address();
+            format!("http://{}:{}{}", host, port, UPLOADS_ROUTE)
+        })
+    };
+
+    let store: Arc<dyn ObjectStore> = match config.storage_backend() {
+        StorageBackendKind::S3 => Arc::new(
+            build_s3_store(config, public_base_url.clone())
+                .await
+                .context("Failed to set up S3 storage")?,
+        ),
+        StorageBackendKind::Local => Arc::new(LocalObjectStore {
+            root: storage
+                .and_then(|storage| storage.local_dir.clone())
+                .unwrap_or_else(|| DEFAULT_LOCAL_DIR.to_string())
+                .into(),
+            public_base_url: served_here(),
+        }),
+        StorageBackendKind::Memory => Arc::new(InMemoryObjectStore::new(served_here())),
+    };
+
+    Ok(store)
+}
@@ -0,0 +1,105 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+
+use crate::utils::current_time_in_milliseconds::current_time_millis;
+
+/// How long a message insert may stay uncommitted before a forward cursor moves past it.
+pub const COMMIT_LAG_MILLIS: i64 = 5_000;
+
+/// Keyset position used by paginated message listings. A cursor keeps pointing at the same place
+/// no matter how many messages are inserted after it was issued.
+///
+/// Ids are handed out when a row is inserted, not when it commits, so a message can become
+/// visible after a message with a higher id has already been paged past. Forward cursors are
+/// therefore built with [`Cursor::settled_after`], which holds them back behind recent messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cursor {
+    Before(i64),
+    After(i64),
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        let raw = match self {
+            Cursor::Before(id) => format!("before:{}", id),
+            Cursor::After(id) => format!("after:{}", id),
+        };
+
+        URL_SAFE_NO_PAD.encode(raw)
+    }
+
+    pub fn decode(cursor: &str) -> Option<Cursor> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        let raw = String::from_utf8(bytes).ok()?;
+        let (direction, id) = raw.split_once(':')?;
+        let id = id.parse::<i64>().ok()?;
+
+        match direction {
+            "before" => Some(Cursor::Before(id)),
+            "after" => Some(Cursor::After(id)),
+            _ => None,
+        }
+    }
+
+    /// Forward cursor for an oldest-first `(id, sent_at)` page, advanced only over messages sent
+    /// more than [`COMMIT_LAG_MILLIS`] ago. Recent messages are served again on the next poll
+    /// (clients dedupe by id) so one that commits late is still picked up; an insert that stays
+    /// uncommitted for longer than the lag can still be missed. `resume_from` is where the page
+    /// started.
+    pub fn settled_after<'a>(
+        page: impl IntoIterator<Item = (i64, &'a str)>,
+        resume_from: Option<i64>,
+    ) -> Option<Cursor> {
+        let settled_before = current_time_millis() as i64 - COMMIT_LAG_MILLIS;
+
+        page.into_iter()
+            .take_while(|(_, sent_at)| {
+                sent_at
+                    .parse::<i64>()
+                    .is_ok_and(|sent_at| sent_at < settled_before)
+            })
+            .map(|(id, _)| id)
+            .last()
+            .or(resume_from)
+            .map(Cursor::After)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursors_round_trip() {
+        for cursor in [Cursor::Before(42), Cursor::After(7)] {
+            assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+        }
+    }
+
+    #[test]
+    fn forward_cursor_stops_at_the_first_recent_message() {
+        let now = current_time_millis() as i64;
+        let old = (now - 60_000).to_string();
+        let recent = now.to_string();
+        let page = [
+            (10, old.as_str()),
+            (11, recent.as_str()),
+            (12, old.as_str()),
+        ];
+
+        assert_eq!(
+            Cursor::settled_after(page, Some(9)),
+            Some(Cursor::After(10))
+        );
+    }
+
+    #[test]
+    fn forward_cursor_falls_back_to_where_the_page_started() {
+        let recent = current_time_millis().to_string();
+
+        assert_eq!(
+            Cursor::settled_after([(10, recent.as_str())], Some(9)),
+            Some(Cursor::After(9))
+        );
+        assert_eq!(Cursor::settled_after([], None), None);
+    }
+}
@@ -0,0 +1,233 @@
+use crate::utils::current_time_in_milliseconds::current_time_millis;
+use crate::utils::realtime_hub::RealtimeEventType;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::error;
+
+// a user that stops sending heartbeats for this long is considered offline
+pub const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(60);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceStatus {
+    Online,
+    Away,
+    Offline,
+}
+
+impl PresenceStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PresenceStatus::Online => "online",
+            PresenceStatus::Away => "away",
+            PresenceStatus::Offline => "offline",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PresenceEntry {
+    status: PresenceStatus,
+    connections: usize,
+    last_heartbeat: Instant,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PresenceUpdate {
+    pub user_id: i64,
+    pub status: PresenceStatus,
+    pub last_seen: String,
+}
+
+/// Heartbeat-driven presence of connected users. Every method returns the new status only when it
+/// actually changed, so callers persist and broadcast transitions rather than every heartbeat.
+#[derive(Clone, Debug, Default)]
+pub struct PresenceTracker {
+    users: Arc<Mutex<HashMap<i64, PresenceEntry>>>,
+}
+
+impl PresenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connect(&self, user_id: i64) -> Option<PresenceStatus> {
+        let mut users = self.users.lock().expect("Presence tracker lock poisoned!");
+
+        let entry = users.entry(user_id).or_insert(PresenceEntry {
+            status: PresenceStatus::Offline,
+            connections: 0,
+            last_heartbeat: Instant::now(),
+        });
+
+        entry.connections += 1;
+        entry.last_heartbeat = Instant::now();
+
+        if entry.status == PresenceStatus::Offline {
+            entry.status = PresenceStatus::Online;
+            return Some(PresenceStatus::Online);
+        }
+
+        None
+    }
+
+    pub fn heartbeat(&self, user_id: i64, status: PresenceStatus) -> Option<PresenceStatus> {
+        let mut users = self.users.lock().expect("Presence tracker lock poisoned!");
+
+        let entry = users.entry(user_id).or_insert(PresenceEntry {
+            status: PresenceStatus::Offline,
+            connections: 1,
+            last_heartbeat: Instant::now(),
+        });
+
+        entry.last_heartbeat = Instant::now();
+
+        if entry.status != status {
+            entry.status = status;
+            return Some(status);
+        }
+
+        None
+    }
+
+    pub fn disconnect(&self, user_id: i64) -> Option<PresenceStatus> {
+        let mut users = self.users.lock().expect("Presence tracker lock poisoned!");
+
+        let entry = users.get_mut(&user_id)?;
+        entry.connections = entry.connections.saturating_sub(1);
+
+        // other devices of the same user are still connected
+        if entry.connections > 0 {
+            return None;
+        }
+
+        let was_offline = entry.status == PresenceStatus::Offline;
+        users.remove(&user_id);
+
+        // the sweeper already announced a user whose heartbeats stopped
+        (!was_offline).then_some(PresenceStatus::Offline)
+    }
+
+    /// Marks users whose last heartbeat is older than `timeout` offline and returns their ids.
+    /// Their connection count is kept, so a later heartbeat brings them back online and their
+    /// sockets still add up when they disconnect.
+    pub fn expire_stale(&self, timeout: Duration) -> Vec<i64> {
+        let mut users = self.users.lock().expect("Presence tracker lock poisoned!");
+
+        let mut stale = Vec::new();
+
+        for (user_id, entry) in users.iter_mut() {
+            if entry.status != PresenceStatus::Offline && entry.last_heartbeat.elapsed() > timeout {
+                entry.status = PresenceStatus::Offline;
+                stale.push(*user_id);
+            }
+        }
+
+        users.retain(|_, entry| entry.connections > 0);
+
+        stale
+    }
+}
+
+/// Persists a presence transition on the user row and pushes it to every room the user belongs to.
+pub async fn publish_presence(state: &crate::AppState, user_id: i64, status: PresenceStatus) {
+    let last_seen = current_time_millis().to_string();
+
+    let update_res = sqlx::query(
+        r#"
+        UPDATE users
+        SET status = $1, last_seen = $2
+        WHERE id = $3
+        "#,
+    )
+    .bind(status.as_str())
+    .bind(&last_seen)
+    .bind(user_id)
+    .execute(&state.db)
+    .await;
+
+    if let Err(e) = update_res {
+        error!("FAILED TO PERSIST USER PRESENCE: {}", e);
+    }
+
+    let room_ids = match sqlx::query_scalar::<_, i64>(
+        "SELECT room_id FROM room_members WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            error!("FAILED TO FETCH ROOMS FOR PRESENCE UPDATE: {}", e);
+            return;
+        }
+    };
+
+    let update = PresenceUpdate {
+        user_id,
+        status,
+        last_seen,
+    };
+
+    for room_id in room_ids {
+        state
+            .hub
+            .publish(room_id, RealtimeEventType::PresenceUpdated, &update);
+    }
+}
+
+/// Periodically marks users offline once their heartbeats stop arriving.
+pub fn spawn_presence_sweeper(state: crate::AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => (),
+                _ = state.shutdown.triggered() => break,
+            }
+
+            for user_id in state.presence.expire_stale(HEARTBEAT_TIMEOUT) {
+                publish_presence(&state, user_id, PresenceStatus::Offline).await;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expired_users_keep_their_connection_count() {
+        let presence = PresenceTracker::new();
+        presence.connect(1);
+        presence.connect(1);
+
+        assert_eq!(presence.expire_stale(Duration::ZERO), vec![1]);
+        // already offline, so the next sweep has nothing to announce
+        assert!(presence.expire_stale(Duration::ZERO).is_empty());
+
+        assert_eq!(
+            presence.heartbeat(1, PresenceStatus::Online),
+            Some(PresenceStatus::Online)
+        );
+
+        // the second device is still connected after the first one leaves
+        assert_eq!(presence.disconnect(1), None);
+        assert_eq!(presence.disconnect(1), Some(PresenceStatus::Offline));
+    }
+
+    #[test]
+    fn disconnecting_an_expired_user_announces_nothing_new() {
+        let presence = PresenceTracker::new();
+        presence.connect(1);
+
+        assert_eq!(presence.expire_stale(Duration::ZERO), vec![1]);
+        assert_eq!(presence.disconnect(1), None);
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// a bucket untouched for a minute has refilled completely, so it can be dropped and recreated
+const BUCKET_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    entries: HashMap<(&'static str, String), Bucket>,
+    last_sweep: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitDecision {
+    Allowed,
+    Limited { retry_after_secs: u64 },
+}
+
+/// In-memory token buckets, one per policy and client. A bucket holds a minute's worth of requests
+/// and refills continuously, so clients can burst up to the limit and are then paced evenly.
+#[derive(Clone, Debug, Default)]
+pub struct RateLimiter {
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn check(&self, policy: &'static str, key: &str, per_minute: u32) -> RateLimitDecision {
+        let capacity = f64::from(per_minute.max(1));
+        let refill_per_sec = capacity / 60.0;
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().expect("Rate limiter lock poisoned!");
+
+        if buckets
+            .last_sweep
+            .is_none_or(|last_sweep| now.duration_since(last_sweep) >= SWEEP_INTERVAL)
+        {
+            buckets
+                .entries
+                .retain(|_, bucket| now.duration_since(bucket.last_refill) < BUCKET_IDLE_TIMEOUT);
+            buckets.last_sweep = Some(now);
+        }
+
+        let bucket = buckets
+            .entries
+            .entry((policy, key.to_string()))
+            .or_insert(Bucket {
+                tokens: capacity,
+                last_refill: now,
+            });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill_per_sec).min(capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return RateLimitDecision::Allowed;
+        }
+
+        RateLimitDecision::Limited {
+            retry_after_secs: ((1.0 - bucket.tokens) / refill_per_sec).ceil().max(1.0) as u64,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast;
+use tracing::error;
+
+// buffered events per room before slow subscribers start lagging
+const ROOM_CHANNEL_CAPACITY: usize = 256;
+// buffered access revocations before a connection lags behind and has to reconnect
+const REVOCATION_CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RealtimeEventType {
+    MessageCreated,
+    MessageUpdated,
+    MessageDeleted,
+    MessageReacted,
+    ReceiptsSynced,
+    UserTyping,
+    PresenceUpdated,
+    CallUpdated,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RealtimeEvent {
+    pub event: RealtimeEventType,
+    pub room_id: i64,
+    pub payload: serde_json::Value,
+}
+
+/// Something that takes access away from connections that are already open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessRevocation {
+    /// The user left or was removed from the room.
+    RoomMembership { room_id: i64, user_id: i64 },
+    /// The account was deactivated.
+    User { user_id: i64 },
+    /// A single session was revoked.
+    Session { session_id: i64 },
+    /// Every session of the user was revoked, apart from `except`.
+    Sessions { user_id: i64, except: Option<i64> },
+}
+
+/// In-process fan-out of room events to every websocket connection subscribed to that room.
+#[derive(Clone, Debug)]
+pub struct RealtimeHub {
+    rooms: Arc<RwLock<HashMap<i64, broadcast::Sender<RealtimeEvent>>>>,
+    revocations: broadcast::Sender<AccessRevocation>,
+}
+
+impl Default for RealtimeHub {
+    fn default() -> Self {
+        Self {
+            rooms: Arc::default(),
+            revocations: broadcast::channel(REVOCATION_CHANNEL_CAPACITY).0,
+        }
+    }
+}
+
+impl RealtimeHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every connection listens here, so membership and session changes reach it while it's open.
+    pub fn revocations(&self) -> broadcast::Receiver<AccessRevocation> {
+        self.revocations.subscribe()
+    }
+
+    pub fn revoke(&self, revocation: AccessRevocation) {
+        // an error here only means no connection is open
+        let _ = self.revocations.send(revocation);
+    }
+
+    pub fn subscribe(&self, room_id: i64) -> broadcast::Receiver<RealtimeEvent> {
+        let mut rooms = self.rooms.write().expect("Realtime hub lock poisoned!");
+
+        rooms
+            .entry(room_id)
+            .or_insert_with(|| broadcast::channel(ROOM_CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Drops the room channel once its last subscriber is gone.
+    pub fn release(&self, room_id: i64) {
+        let mut rooms = self.rooms.write().expect("Realtime hub lock poisoned!");
+
+        if let Some(sender) = rooms.get(&room_id)
+            && sender.receiver_count() == 0
+        {
+            rooms.remove(&room_id);
+        }
+    }
+
+    /// Pushes an event to every live subscriber of the room. Rooms nobody is listening on are skipped.
+    pub fn publish<T: Serialize>(&self, room_id: i64, event: RealtimeEventType, payload: &T) {
+        let payload = match serde_json::to_value(payload) {
+            Ok(value) => value,
+            Err(e) => {
+                error!("FAILED TO SERIALIZE REALTIME EVENT PAYLOAD: {}", e);
+                return;
+            }
+        };
+
+        let rooms = self.rooms.read().expect("Realtime hub lock poisoned!");
+
+        if let Some(sender) = rooms.get(&room_id) {
+            // an error here only means every receiver has gone away in the meantime
+            let _ = sender.send(RealtimeEvent {
+                event,
+                room_id,
+                payload,
+            });
+        }
+    }
+}
+
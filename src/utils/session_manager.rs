@@ -0,0 +1,253 @@
+use crate::AppState;
+use crate::utils::generate_tokens::Claims;
+use crate::utils::load_config::AppConfig;
+use crate::utils::realtime_hub::AccessRevocation;
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::{Extensions, HeaderMap, request::Parts};
+use chrono::NaiveDateTime;
+use jsonwebtoken::errors::Error as JwtError;
+use jsonwebtoken::{DecodingKey, Validation, decode};
+use serde::Serialize;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Session {
+    pub id: i64,
+    pub user_id: i64,
+    #[serde(skip_serializing)]
+    pub refresh_jti: String,
+    pub device_name: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub expires_at: NaiveDateTime,
+    pub last_used_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub revoked_reason: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    // worked out by the database so the check doesn't depend on the server's clock or timezone
+    #[sqlx(default)]
+    #[serde(skip_serializing)]
+    pub is_expired: bool,
+}
+
+/// What we know about the device a session was opened from.
+#[derive(Debug, Default)]
+pub struct ClientMeta {
+    pub device_name: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl ClientMeta {
+    pub fn from_request(headers: &HeaderMap, extensions: &Extensions, config: &AppConfig) -> Self {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        Self {
+            device_name: header("x-device-name"),
+            ip_address: client_ip(headers, extensions, config).map(|ip| ip.to_string()),
+            user_agent: header("user-agent"),
+        }
+    }
+}
+
+impl FromRequestParts<AppState> for ClientMeta {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Infallible> {
+        Ok(Self::from_request(
+            &parts.headers,
+            &parts.extensions,
+            &state.config,
+        ))
+    }
+}
+
+/// The address a request really came from. Forwarded headers are only believed when the peer is
+/// one of `security.trusted_proxies`, anyone else could put whatever they like in them. `None` when
+/// the server wasn't started with connect info.
+pub fn client_ip(
+    headers: &HeaderMap,
+    extensions: &Extensions,
+    config: &AppConfig,
+) -> Option<IpAddr> {
+    let peer = extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_canonical())?;
+
+    let trusted_proxies = config.trusted_proxies();
+
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    // each proxy appends the address it got the request from, so walking back from our end the
+    // first address none of our proxies vouch for is the client
+    let forwarded: Vec<IpAddr> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+        .collect();
+
+    forwarded
+        .into_iter()
+        .rev()
+        .find(|ip| !trusted_proxies.contains(ip))
+        .or_else(|| {
+            headers
+                .get("x-real-ip")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|ip| ip.trim().parse().ok())
+        })
+        .or(Some(peer))
+}
+
+// a session lives as long as its refresh token
+fn session_lifetime_secs(state: &AppState) -> f64 {
+    state.config.token_ttls().refresh_token_secs as f64
+}
+
+pub fn new_refresh_jti() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+pub async fn fetch_session(
+    state: &AppState,
+    session_id: i64,
+    user_id: i64,
+) -> Result<Option<Session>, sqlx::Error> {
+    sqlx::query_as::<_, Session>(
+        "SELECT *, expires_at <= NOW() AS is_expired FROM sessions WHERE id = $1 AND user_id = $2",
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await
+}
+
+/// Bumps `last_used_at`, at most once a minute so busy clients don't write on every request.
+pub async fn touch_session(state: &AppState, session_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE sessions
+        SET last_used_at = NOW()
+        WHERE id = $1 AND last_used_at < NOW() - INTERVAL '1 minute'
+        "#,
+    )
+    .bind(session_id)
+    .execute(&state.db)
+    .await
+    .map(|_| ())
+}
+
+pub async fn create_session(
+    state: &AppState,
+    user_id: i64,
+    meta: &ClientMeta,
+) -> Result<Session, sqlx::Error> {
+    sqlx::query_as::<_, Session>(
+        r#"
+        INSERT INTO sessions (user_id, refresh_jti, device_name, ip_address, user_agent, expires_at)
+        VALUES ($1, $2, $3, $4, $5, NOW() + make_interval(secs => $6))
+        RETURNING *
+        "#,
+    )
+    .bind(user_id)
+    .bind(new_refresh_jti())
+    .bind(&meta.device_name)
+    .bind(&meta.ip_address)
+    .bind(&meta.user_agent)
+    .bind(session_lifetime_secs(state))
+    .fetch_one(&state.db)
+    .await
+}
+
+/// Swaps the session's refresh token id for a new one, but only while `current_jti` is still the
+/// valid one. `None` means the session is gone, revoked, expired or the token was already used.
+pub async fn rotate_session(
+    state: &AppState,
+    session_id: i64,
+    user_id: i64,
+    current_jti: &str,
+    meta: &ClientMeta,
+) -> Result<Option<Session>, sqlx::Error> {
+    sqlx::query_as::<_, Session>(
+        r#"
+        UPDATE sessions
+        SET
+            refresh_jti = $1,
+            ip_address = COALESCE($2, ip_address),
+            user_agent = COALESCE($3, user_agent),
+            expires_at = NOW() + make_interval(secs => $4),
+            last_used_at = NOW(),
+            updated_at = NOW()
+        WHERE id = $5
+            AND user_id = $6
+            AND refresh_jti = $7
+            AND revoked_at IS NULL
+            AND expires_at > NOW()
+        RETURNING *
+        "#,
+    )
+    .bind(new_refresh_jti())
+    .bind(&meta.ip_address)
+    .bind(&meta.user_agent)
+    .bind(session_lifetime_secs(state))
+    .bind(session_id)
+    .bind(user_id)
+    .bind(current_jti)
+    .fetch_optional(&state.db)
+    .await
+}
+
+pub async fn revoke_session(
+    state: &AppState,
+    session_id: i64,
+    reason: &str,
+) -> Result<bool, sqlx::Error> {
+    let revoked = sqlx::query(
+        r#"
+        UPDATE sessions
+        SET revoked_at = NOW(), revoked_reason = $1, updated_at = NOW()
+        WHERE id = $2 AND revoked_at IS NULL
+        "#,
+    )
+    .bind(reason)
+    .bind(session_id)
+    .execute(&state.db)
+    .await?
+    .rows_affected()
+        > 0;
+
+    // websockets opened with the session are closed along with it
+    if revoked {
+        state.hub.revoke(AccessRevocation::Session { session_id });
+    }
+
+    Ok(revoked)
+}
+
+/// Decodes and verifies a token signed by this server. With `validate_exp` off, an expired token
+/// is still accepted - used to find out which session an expired access token belonged to.
+pub fn read_token_claims(token: &str, validate_exp: bool) -> Result<Claims, JwtError> {
+    let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
+
+    let mut validation = Validation::default();
+    validation.validate_exp = validate_exp;
+
+    decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(jwt_secret.as_bytes()),
+        &validation,
+    )
+    .map(|token_data| token_data.claims)
+}
@@ -0,0 +1,88 @@
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tokio_util::task::task_tracker::TrackedFuture;
+use tracing::{error, info};
+
+/// Shared shutdown state. Long-lived tasks select on `triggered()` to wind down, and websocket
+/// connections - which axum stops tracking once they are upgraded - are tracked here so they can
+/// be drained before the database pool is closed.
+#[derive(Clone, Debug, Default)]
+pub struct Shutdown {
+    token: CancellationToken,
+    connections: TaskTracker,
+    triggered_at: Arc<OnceLock<Instant>>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn trigger(&self) {
+        self.triggered_at.get_or_init(Instant::now);
+        self.token.cancel();
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    pub async fn triggered(&self) {
+        self.token.cancelled().await
+    }
+
+    pub fn track<F: Future>(&self, connection: F) -> TrackedFuture<F> {
+        self.connections.track_future(connection)
+    }
+
+    /// The single point in time everything has to be closed by - `grace_period` after the
+    /// shutdown was first triggered, or from now if it hasn't been yet.
+    pub fn deadline(&self, grace_period: Duration) -> Instant {
+        *self.triggered_at.get_or_init(Instant::now) + grace_period
+    }
+
+    /// Waits for the tracked connections to finish. Returns false if some were still open when
+    /// the deadline passed.
+    pub async fn drain_connections(&self, deadline: Instant) -> bool {
+        self.connections.close();
+
+        tokio::time::timeout_at(deadline, self.connections.wait())
+            .await
+            .is_ok()
+    }
+}
+
+/// Resolves on Ctrl+C or, on unix, SIGTERM - what orchestrators send during a rolling restart.
+pub async fn wait_for_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("FAILED TO LISTEN FOR CTRL+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("FAILED TO LISTEN FOR SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received Ctrl+C, shutting down"),
+        _ = terminate => info!("Received SIGTERM, shutting down"),
+    }
+}
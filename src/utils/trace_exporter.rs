@@ -0,0 +1,552 @@
+use crate::utils::load_config::{AppConfig, TraceExporterKind};
+use anyhow::{Context as _, Result, anyhow};
+use opentelemetry::trace::{
+    SpanContext, SpanId, SpanKind, Status, TraceFlags, TraceId, TraceState,
+};
+use opentelemetry::{InstrumentationScope, KeyValue, Value};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::transform::common::tonic::ResourceAttributesWithSchema;
+use opentelemetry_proto::transform::trace::tonic::group_spans_by_resource_and_scope;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::{
+    IdGenerator, RandomIdGenerator, SpanData, SpanEvents, SpanExporter, SpanLinks,
+};
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::time::{Duration, SystemTime};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{mpsc, oneshot};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber, error};
+use tracing_subscriber::Layer;
+use tracing_subscriber::filter::{Filtered, Targets};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+const DEFAULT_TRACE_FILE: &str = "traces.jsonl";
+const DEFAULT_OTLP_ENDPOINT: &str = "http://localhost:4318";
+
+// spans are buffered and exported in batches, whichever limit is hit first
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_BATCH_SIZE: usize = 512;
+
+// sqlx logs every statement under this target once it finishes, with how long it took
+const SQLX_QUERY_TARGET: &str = "sqlx::query";
+
+pub type ExportFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Anywhere a batch of finished spans can go.
+pub trait SpanSink: Send + Sync + fmt::Debug {
+    fn export(&self, batch: Vec<SpanData>) -> ExportFuture<'_>;
+}
+
+/// A batch as an OTLP `ExportTraceServiceRequest` in its JSON encoding, one line per batch, so
+/// the stdout and file sinks write what a collector would receive.
+fn otlp_json_line(resource: &Resource, batch: Vec<SpanData>) -> Result<String> {
+    let request = ExportTraceServiceRequest {
+        resource_spans: group_spans_by_resource_and_scope(
+            batch,
+            &ResourceAttributesWithSchema::from(resource),
+        ),
+    };
+
+    let json = serde_json::to_string(&request).context("Failed to encode spans")?;
+
+    Ok(format!("{}\n", json))
+}
+
+/// Prints each batch as one JSON line on stdout.
+#[derive(Debug)]
+pub struct StdoutSpanSink {
+    resource: Resource,
+}
+
+impl SpanSink for StdoutSpanSink {
+    fn export(&self, batch: Vec<SpanData>) -> ExportFuture<'_> {
+        Box::pin(async move {
+            let line = otlp_json_line(&self.resource, batch)?;
+            let mut stdout = tokio::io::stdout();
+
+            stdout
+                .write_all(line.as_bytes())
+                .await
+                .context("Failed to write spans to stdout")?;
+            stdout.flush().await.context("Failed to flush stdout")
+        })
+    }
+}
+
+/// Appends each batch as one JSON line to the trace file.
+#[derive(Debug)]
+pub struct FileSpanSink {
+    path: PathBuf,
+    resource: Resource,
+}
+
+impl SpanSink for FileSpanSink {
+    fn export(&self, batch: Vec<SpanData>) -> ExportFuture<'_> {
+        Box::pin(async move {
+            let line = otlp_json_line(&self.resource, batch)?;
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await
+                .context("Failed to open trace file")?;
+
+            file.write_all(line.as_bytes())
+                .await
+                .context("Failed to write spans to trace file")
+        })
+    }
+}
+
+/// Sends each batch to an OpenTelemetry collector with the OTLP/HTTP exporter.
+#[derive(Debug)]
+pub struct OtlpSpanSink {
+    exporter: opentelemetry_otlp::SpanExporter,
+}
+
+impl SpanSink for OtlpSpanSink {
+    fn export(&self, batch: Vec<SpanData>) -> ExportFuture<'_> {
+        Box::pin(async move {
+            self.exporter
+                .export(batch)
+                .await
+                .map_err(|e| anyhow!("{}", e))
+                .context("Failed to export spans to the OTLP collector")
+        })
+    }
+}
+
+fn build_otlp_sink(endpoint: &str, resource: &Resource) -> Result<OtlpSpanSink> {
+    let mut exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()
+        .context("Failed to build the OTLP exporter")?;
+
+    exporter.set_resource(resource);
+
+    Ok(OtlpSpanSink { exporter })
+}
+
+enum ExportMessage {
+    Span(Box<SpanData>),
+    Flush(oneshot::Sender<()>),
+}
+
+impl fmt::Debug for ExportMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportMessage::Span(_) => f.write_str("Span"),
+            ExportMessage::Flush(_) => f.write_str("Flush"),
+        }
+    }
+}
+
+/// Handle on the background task that batches finished spans and hands them to the configured
+/// sink. Cheap to clone; `layer()` is what gets installed on the subscriber.
+#[derive(Clone, Debug)]
+pub struct TraceExporter {
+    sender: mpsc::UnboundedSender<ExportMessage>,
+}
+
+impl TraceExporter {
+    /// Spans are exported for INFO and above, plus a span for every sqlx statement.
+    pub fn layer<S>(&self) -> Filtered<SpanExportLayer, Targets, S>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        SpanExportLayer {
+            sender: self.sender.clone(),
+        }
+        .with_filter(
+            Targets::new()
+                .with_default(Level::INFO)
+                .with_target(SQLX_QUERY_TARGET, Level::DEBUG),
+        )
+    }
+
+    /// Exports whatever is buffered, e.g. right before the process exits.
+    pub async fn flush(&self) {
+        let (done_sender, done_receiver) = oneshot::channel();
+
+        if self.sender.send(ExportMessage::Flush(done_sender)).is_ok() {
+            let _ = done_receiver.await;
+        }
+    }
+}
+
+/// Picks the sink configured in the [observability] section and starts the export task. Needs to
+/// run inside the tokio runtime.
+pub fn build_trace_exporter(config: &AppConfig) -> Result<TraceExporter> {
+    let observability = &config.observability;
+
+    let resource = Resource::builder_empty()
+        .with_attributes([
+            KeyValue::new("service.name", config.app.name.clone()),
+            KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+            KeyValue::new(
+                "deployment.environment",
+                config
+                    .app
+                    .environment
+                    .clone()
+                    .unwrap_or_else(|| "development".to_string()),
+            ),
+        ])
+        .build();
+
+    let sink: Box<dyn SpanSink> = match observability.trace_exporter {
+        TraceExporterKind::Stdout => Box::new(StdoutSpanSink { resource }),
+        TraceExporterKind::File => Box::new(FileSpanSink {
+            path: observability
+                .trace_file
+                .clone()
+                .unwrap_or_else(|| DEFAULT_TRACE_FILE.to_string())
+                .into(),
+            resource,
+        }),
+        TraceExporterKind::Otlp => Box::new(build_otlp_sink(
+            observability
+                .otlp_endpoint
+                .as_deref()
+                .unwrap_or(DEFAULT_OTLP_ENDPOINT),
+            &resource,
+        )?),
+    };
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    tokio::spawn(run_export_loop(sink, receiver));
+
+    Ok(TraceExporter { sender })
+}
+
+async fn run_export_loop(
+    sink: Box<dyn SpanSink>,
+    mut receiver: mpsc::UnboundedReceiver<ExportMessage>,
+) {
+    let mut batch: Vec<SpanData> = Vec::new();
+    let mut interval = tokio::time::interval(EXPORT_INTERVAL);
+
+    loop {
+        tokio::select! {
+            message = receiver.recv() => match message {
+                Some(ExportMessage::Span(span)) => {
+                    batch.push(*span);
+
+                    if batch.len() >= MAX_BATCH_SIZE {
+                        export_batch(sink.as_ref(), &mut batch).await;
+                    }
+                }
+                Some(ExportMessage::Flush(done)) => {
+                    export_batch(sink.as_ref(), &mut batch).await;
+                    let _ = done.send(());
+                }
+                None => {
+                    export_batch(sink.as_ref(), &mut batch).await;
+                    break;
+                }
+            },
+            _ = interval.tick() => export_batch(sink.as_ref(), &mut batch).await,
+        }
+    }
+}
+
+async fn export_batch(sink: &dyn SpanSink, batch: &mut Vec<SpanData>) {
+    if batch.is_empty() {
+        return;
+    }
+
+    let spans = std::mem::take(batch);
+    let span_count = spans.len();
+
+    // a collector that is down shouldn't take the server with it, the batch is dropped
+    if let Err(e) = sink.export(spans).await {
+        error!("FAILED TO EXPORT {} SPANS: {:#}!", span_count, e);
+    }
+}
+
+// ============================================================================
+// Span Export Layer
+// ============================================================================
+
+struct AttributeVisitor<'a>(&'a mut Vec<(String, Value)>);
+
+impl AttributeVisitor<'_> {
+    fn set(&mut self, field: &Field, value: Value) {
+        match self.0.iter_mut().find(|(key, _)| key == field.name()) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((field.name().to_string(), value)),
+        }
+    }
+}
+
+impl Visit for AttributeVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, Value::from(value.to_string()));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.set(field, Value::I64(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.set(field, Value::I64(value as i64));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.set(field, Value::F64(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.set(field, Value::Bool(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.set(field, Value::from(format!("{:?}", value)));
+    }
+}
+
+/// What the layer keeps in a span's extensions between it opening and closing.
+#[derive(Debug)]
+struct SpanRecord {
+    trace_id: TraceId,
+    span_id: SpanId,
+    parent_span_id: Option<SpanId>,
+    name: &'static str,
+    start: SystemTime,
+    attributes: Vec<(String, Value)>,
+}
+
+impl SpanRecord {
+    fn new(
+        parent: Option<(TraceId, SpanId)>,
+        name: &'static str,
+        start: SystemTime,
+        attributes: Vec<(String, Value)>,
+    ) -> Self {
+        let ids = RandomIdGenerator::default();
+
+        SpanRecord {
+            trace_id: parent
+                .map(|(trace_id, _)| trace_id)
+                .unwrap_or_else(|| ids.new_trace_id()),
+            span_id: ids.new_span_id(),
+            parent_span_id: parent.map(|(_, span_id)| span_id),
+            name,
+            start,
+            attributes,
+        }
+    }
+
+    /// `otel.name`, `otel.kind` and `otel.status_code` fields steer the exported span, the
+    /// rest become attributes.
+    fn into_span_data(self, end: SystemTime) -> SpanData {
+        let mut name = self.name.to_string();
+        let mut span_kind = SpanKind::Internal;
+        let mut status = Status::Unset;
+        let mut attributes = Vec::new();
+
+        for (key, value) in self.attributes {
+            match (key.as_str(), &value) {
+                ("otel.name", Value::String(value)) => name = value.to_string(),
+                ("otel.kind", Value::String(value)) => {
+                    span_kind = match value.as_str() {
+                        "server" => SpanKind::Server,
+                        "client" => SpanKind::Client,
+                        _ => SpanKind::Internal,
+                    }
+                }
+                ("otel.status_code", Value::String(value)) => {
+                    if value.as_str().eq_ignore_ascii_case("error") {
+                        status = Status::error("");
+                    }
+                }
+                _ => attributes.push(KeyValue::new(key, value)),
+            }
+        }
+
+        SpanData {
+            span_context: SpanContext::new(
+                self.trace_id,
+                self.span_id,
+                TraceFlags::SAMPLED,
+                false,
+                TraceState::default(),
+            ),
+            parent_span_id: self.parent_span_id.unwrap_or(SpanId::INVALID),
+            parent_span_is_remote: false,
+            span_kind,
+            name: name.into(),
+            start_time: self.start,
+            end_time: end,
+            attributes,
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status,
+            instrumentation_scope: InstrumentationScope::builder(env!("CARGO_PKG_NAME")).build(),
+        }
+    }
+}
+
+/// Turns closed `tracing` spans into OpenTelemetry spans. Parent/child links follow the span tree, and each
+/// sqlx statement event becomes a child span of whatever span ran the query.
+#[derive(Debug)]
+pub struct SpanExportLayer {
+    sender: mpsc::UnboundedSender<ExportMessage>,
+}
+
+impl<S> Layer<S> for SpanExportLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let parent = span.parent().and_then(|parent| {
+            parent
+                .extensions()
+                .get::<SpanRecord>()
+                .map(|record| (record.trace_id, record.span_id))
+        });
+
+        let mut record = SpanRecord::new(parent, span.name(), SystemTime::now(), Vec::new());
+
+        attrs.record(&mut AttributeVisitor(&mut record.attributes));
+        span.extensions_mut().insert(record);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id)
+            && let Some(record) = span.extensions_mut().get_mut::<SpanRecord>()
+        {
+            values.record(&mut AttributeVisitor(&mut record.attributes));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if event.metadata().target() != SQLX_QUERY_TARGET {
+            return;
+        }
+
+        let mut fields = Vec::new();
+        event.record(&mut AttributeVisitor(&mut fields));
+
+        // the event fires when the statement finishes, so the span is rebuilt from its elapsed time
+        let elapsed_secs = fields
+            .iter()
+            .find_map(|(key, value)| match (key.as_str(), value) {
+                ("elapsed_secs", Value::F64(secs)) => Some(*secs),
+                _ => None,
+            });
+        let end = SystemTime::now();
+        let start = elapsed_secs
+            .and_then(|secs| end.checked_sub(Duration::from_secs_f64(secs)))
+            .unwrap_or(end);
+
+        let parent = ctx.event_span(event).and_then(|parent| {
+            parent
+                .extensions()
+                .get::<SpanRecord>()
+                .map(|record| (record.trace_id, record.span_id))
+        });
+
+        let mut attributes = vec![
+            ("otel.kind".to_string(), Value::from("client")),
+            ("db.system".to_string(), Value::from("postgresql")),
+        ];
+
+        let mut summary = None;
+        let mut statement = None;
+
+        for (key, value) in fields {
+            match (key.as_str(), value) {
+                ("summary", Value::String(value)) => summary = Some(value.to_string()),
+                ("db.statement", Value::String(value)) => statement = Some(value.to_string()),
+                ("rows_affected" | "rows_returned", value) => attributes.push((key, value)),
+                _ => {}
+            }
+        }
+
+        // sqlx only fills in the statement when the summary had to cut it short
+        let statement = statement
+            .map(|sql| sql.trim().to_string())
+            .filter(|sql| !sql.is_empty())
+            .or_else(|| summary.clone());
+
+        if let Some(summary) = summary {
+            attributes.push(("otel.name".to_string(), Value::from(summary)));
+        }
+
+        if let Some(statement) = statement {
+            attributes.push(("db.statement".to_string(), Value::from(statement)));
+        }
+
+        let record = SpanRecord::new(parent, "db.query", start, attributes);
+
+        let _ = self
+            .sender
+            .send(ExportMessage::Span(Box::new(record.into_span_data(end))));
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+
+        let Some(record) = span.extensions_mut().remove::<SpanRecord>() else {
+            return;
+        };
+
+        let _ = self.sender.send(ExportMessage::Span(Box::new(
+            record.into_span_data(SystemTime::now()),
+        )));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn otel_fields_steer_the_exported_span() {
+        let record = SpanRecord::new(
+            None,
+            "http.request",
+            SystemTime::now(),
+            vec![
+                ("otel.name".to_string(), Value::from("GET /health")),
+                ("otel.kind".to_string(), Value::from("server")),
+                ("otel.status_code".to_string(), Value::from("ERROR")),
+                ("http.status_code".to_string(), Value::I64(500)),
+            ],
+        );
+
+        let span = record.into_span_data(SystemTime::now());
+
+        assert_eq!(span.name, "GET /health");
+        assert_eq!(span.span_kind, SpanKind::Server);
+        assert_eq!(span.status, Status::error(""));
+        assert_eq!(
+            span.attributes,
+            vec![KeyValue::new("http.status_code", 500)]
+        );
+
+        let line = otlp_json_line(&Resource::builder_empty().build(), vec![span]).unwrap();
+        let request: serde_json::Value = serde_json::from_str(&line).unwrap();
+        let exported = &request["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+
+        assert_eq!(exported["name"], "GET /health");
+        assert_eq!(exported["traceId"].as_str().map(str::len), Some(32));
+    }
+}